@@ -0,0 +1,26 @@
+//! Times both parts of day 5 on a generated input with a million moves.
+
+use criterion::{criterion_group, criterion_main};
+
+#[allow(dead_code)]
+mod day05 {
+    pub fn bench_moves(c: &mut criterion::Criterion) {
+        let Stacks { stacks, moves, .. } = generate(1_000_000);
+
+        let mut group = c.benchmark_group("day05");
+        group.sample_size(10);
+        for (part, preserve_order) in [("part A", false), ("part B", true)] {
+            group.bench_function(part, |b| {
+                b.iter(|| {
+                    move_crates(stacks.clone(), &moves, preserve_order, |_, _, _| ()).unwrap()
+                })
+            });
+        }
+        group.finish();
+    }
+
+    include!("../src/bin/day05.rs");
+}
+
+criterion_group!(benches, day05::bench_moves);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+//! Compares day 8's strategies on generated forests of increasing size.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId};
+
+// The day's tests are compiled in too, without the `#[test]` functions which
+// use their imports.
+#[allow(dead_code, unused_imports)]
+mod day08 {
+    use super::BenchmarkId;
+    use aoc2022::variants::Strategy as _;
+
+    /// The largest forest, in trees along each side, which the cubic
+    /// strategies are run on. Beyond this they can take minutes on a forest
+    /// with long views.
+    const MAX_CUBIC_SIZE: usize = 1000;
+
+    /// Whether the strategy scans outwards from every tree, taking cubic time
+    /// in the forest's size.
+    fn is_cubic(strategy: Strategy) -> bool {
+        match strategy {
+            Strategy::Scan => true,
+            Strategy::Linear | Strategy::Simd => false,
+            #[cfg(feature = "parallel")]
+            Strategy::Parallel => true,
+        }
+    }
+
+    pub fn bench_strategies(c: &mut criterion::Criterion) {
+        for (part, solve) in [
+            (
+                "part A",
+                Strategy::part_a as fn(&Strategy, &Trees, &[Step]) -> usize,
+            ),
+            ("part B", Strategy::part_b),
+        ] {
+            let mut group = c.benchmark_group(format!("day08 {}", part));
+            group.sample_size(10);
+            for size in [100, 1000, 3000] {
+                let trees = generate(size);
+                for &strategy in Strategy::ALL {
+                    if is_cubic(strategy) && size > MAX_CUBIC_SIZE {
+                        continue;
+                    }
+                    group.bench_with_input(
+                        BenchmarkId::new(strategy.name(), size),
+                        &trees,
+                        |b, trees| b.iter(|| solve(&strategy, trees, &ORTHOGONAL)),
+                    );
+                }
+            }
+            group.finish();
+        }
+    }
+
+    include!("../src/bin/day08.rs");
+}
+
+criterion_group!(benches, day08::bench_strategies);
+criterion_main!(benches);
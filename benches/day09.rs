@@ -0,0 +1,53 @@
+//! Compares tracking day 9's visited positions in a hash set with a bitset,
+//! for each strategy, on a generated input with around a million steps.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId};
+
+// The day's tests are compiled in too, without the `#[test]` functions which
+// use their imports.
+#[allow(dead_code, unused_imports)]
+mod day09 {
+    use super::BenchmarkId;
+    use aoc2022::rng::Rng;
+    use aoc2022::variants::Strategy as _;
+
+    /// A random walk of `num_motions` motions, each of up to 20 steps.
+    fn generate(num_motions: usize) -> Vec<Motion> {
+        const DIRECTIONS: [Direction; 4] = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+
+        let mut rng = Rng::default();
+        (0..num_motions)
+            .map(|_| Motion {
+                dir: DIRECTIONS[rng.below(DIRECTIONS.len())],
+                dist: 1 + rng.below(20) as isize,
+            })
+            .collect()
+    }
+
+    pub fn bench_tracking(c: &mut criterion::Criterion) {
+        let motions = generate(100_000);
+        let (min, max) = bounds(&motions);
+
+        let mut group = c.benchmark_group("day09");
+        group.sample_size(10);
+        for &strategy in Strategy::ALL {
+            group.bench_function(BenchmarkId::new(strategy.name(), "hash"), |b| {
+                b.iter(|| strategy.run(&motions, 10, FxHashSet::default())[9].count())
+            });
+            group.bench_function(BenchmarkId::new(strategy.name(), "bitset"), |b| {
+                b.iter(|| strategy.run(&motions, 10, Bitset::new(min, max))[9].count())
+            });
+        }
+        group.finish();
+    }
+
+    include!("../src/bin/day09.rs");
+}
+
+criterion_group!(benches, day09::bench_tracking);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+//! Compares the cost of interpreting day 11's operations with compiling them
+//! into closures, over part B of the example.
+
+use criterion::{criterion_group, criterion_main};
+
+#[allow(dead_code)]
+mod day11 {
+    pub fn bench_evaluation(c: &mut criterion::Criterion) {
+        let example = aoc2022::examples::for_day(11).next().unwrap();
+        let monkeys = parse_all(parse_input, example.input).unwrap();
+
+        let mut group = c.benchmark_group("day11 part B");
+        for evaluation in [Evaluation::Interpret, Evaluation::Compile] {
+            group.bench_function(format!("{:?}", evaluation), |b| {
+                b.iter(|| run(monkeys.clone(), 10000, 1, evaluation, None, |_, _, _| {}).unwrap())
+            });
+        }
+        group.finish();
+    }
+
+    include!("../src/bin/day11.rs");
+}
+
+criterion_group!(benches, day11::bench_evaluation);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+//! Feeds arbitrary bytes to day 3's parser, which should reject bad input
+//! with an error rather than a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+mod day03 {
+    include!("../../src/bin/day03.rs");
+
+    pub fn parse(input: &str) {
+        let rucksacks = parse_input(input);
+        let _ = part_a(&rucksacks);
+        let _ = part_b(&rucksacks, 3);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        day03::parse(input);
+    }
+});
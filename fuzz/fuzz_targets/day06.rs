@@ -0,0 +1,17 @@
+//! Feeds arbitrary bytes to day 6's parser, which should reject bad input
+//! with an error rather than a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+mod day06 {
+    include!("../../src/bin/day06.rs");
+
+    pub fn parse(input: &[u8]) {
+        let _ = find_start_markers(input, &[4, 14]);
+    }
+}
+
+fuzz_target!(|data: &[u8]| day06::parse(data));
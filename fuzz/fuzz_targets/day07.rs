@@ -0,0 +1,21 @@
+//! Feeds arbitrary bytes to day 7's parser, which should reject bad input
+//! with an error rather than a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+mod day07 {
+    include!("../../src/bin/day07.rs");
+
+    pub fn parse(input: &str) {
+        let _ = parse_input(input);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        day07::parse(input);
+    }
+});
@@ -0,0 +1,21 @@
+//! Feeds arbitrary bytes to day 12's parser, which should reject bad input
+//! with an error rather than a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+mod day12 {
+    include!("../../src/bin/day12.rs");
+
+    pub fn parse(input: &str) {
+        let _ = parse_input(input);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        day12::parse(input);
+    }
+});
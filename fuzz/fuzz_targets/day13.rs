@@ -0,0 +1,22 @@
+//! Feeds arbitrary bytes to day 13's parser, which should reject bad input
+//! with an error rather than a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+mod day13 {
+    include!("../../src/bin/day13.rs");
+
+    pub fn parse(input: &str) {
+        let _ = parse_all(parse_input(&mut Arena::default()), input);
+        let _ = parse_input_json(input, &mut Arena::default());
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        day13::parse(input);
+    }
+});
@@ -0,0 +1,17 @@
+//! Feeds arbitrary bytes to day 15's parser, which should reject bad input
+//! with an error rather than a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+mod day15 {
+    include!("../../src/bin/day15.rs");
+
+    pub fn parse(input: &[u8]) {
+        let _ = parse_input(input).collect::<Result<Vec<_>>>();
+    }
+}
+
+fuzz_target!(|data: &[u8]| day15::parse(data));
@@ -0,0 +1,590 @@
+//! Runs the other days' binaries, which must already be built alongside this
+//! one, e.g. `cargo build --release --bins`.
+//!
+//! Usage:
+//!   aoc run [--day N] [--example] [--perf] [--timings out.csv] [--dump-parsed]
+//!   aoc validate [--day N]
+//!   aoc compare [--day N] [--perf]
+//!   aoc report
+//!
+//! `--perf` also reports the hardware performance counters for each day's
+//! parsing and parts, which needs Linux. With `--timings` they're written to
+//! the CSV file too.
+//!
+//! Running the days on their real inputs records their answers and timings in
+//! `results.json`, in the current directory. `--timings` also writes how long
+//! each day took to parse its input and solve each part to a CSV file.
+//!
+//! `--dump-parsed` prints each day's parsed input as a single JSON object
+//! keyed by day instead of solving.
+//!
+//! The runner needs the `serde` feature, which is on by default.
+//!
+//! `report` prints those results as a Markdown table, flagging any answers
+//! which differ from the known correct ones in `res/answers.json`.
+
+use anyhow::{anyhow, Context, Result};
+use aoc2022::examples::{self, Example};
+use aoc2022::perf::{Counters, Counts};
+use aoc2022::results::{self, Answers, DayResult, Results};
+use aoc2022::timing::{self, Measurement, Stage};
+use aoc2022::variants;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+const DAYS: std::ops::RangeInclusive<u32> = 1..=16;
+
+fn day_binary(day: u32) -> Result<PathBuf> {
+    let path = std::env::current_exe()?.with_file_name(format!(
+        "day{:02}{}",
+        day,
+        std::env::consts::EXE_SUFFIX
+    ));
+    if !path.exists() {
+        return Err(anyhow!(
+            "Day {} isn't built, expected it at {}",
+            day,
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+/// The real input which `day` reads by default.
+fn input_path(day: u32) -> PathBuf {
+    PathBuf::from(format!("res/input{:02}.txt", day))
+}
+
+/// Picks the answers out of a day's output, which has lines like
+/// `Day 1, part A: 24000`.
+fn parse_answers(output: &str) -> (Option<&str>, Option<&str>) {
+    let answer = |part: &str| {
+        output.lines().find_map(|line| {
+            let (label, answer) = line.split_once(": ")?;
+            label.ends_with(part).then(|| answer.trim())
+        })
+    };
+    (answer(", part A"), answer(", part B"))
+}
+
+/// Formats a count with thousands separators, as counters run into billions.
+fn group_digits(count: u64) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+fn counter_cells(counts: &Counts) -> [String; 3] {
+    [
+        group_digits(counts.instructions),
+        group_digits(counts.cache_misses),
+        group_digits(counts.branch_misses),
+    ]
+}
+
+/// What a day printed, and how long it took.
+struct Outcome {
+    stdout: String,
+    elapsed: Duration,
+    /// How long each stage took, and with `--perf` its counts, as reported
+    /// by the day.
+    stages: Vec<Measurement>,
+}
+
+impl Outcome {
+    fn stage(&self, stage: Stage) -> Option<&Measurement> {
+        self.stages.iter().find(|m| m.stage == stage)
+    }
+}
+
+/// Runs `day` with `args`, asking it to report its stages' timings, and with
+/// `perf` their counts too.
+fn execute(day: u32, args: &[&str], perf: bool) -> Result<(Outcome, ExitStatus)> {
+    let mut command = Command::new(day_binary(day)?);
+    command
+        .args(args)
+        .env(timing::ENV_VAR, "1")
+        .stderr(Stdio::inherit());
+    if perf {
+        command.env(timing::PERF_ENV_VAR, "1");
+    }
+    let start = Instant::now();
+    let output = command.output()?;
+    let elapsed = start.elapsed();
+
+    let mut stdout = String::new();
+    let mut stages = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match timing::parse_line(line) {
+            Some(measurement) => stages.push(measurement),
+            None => {
+                stdout.push_str(line);
+                stdout.push('\n');
+            }
+        }
+    }
+    let outcome = Outcome {
+        stdout,
+        elapsed,
+        stages,
+    };
+    Ok((outcome, output.status))
+}
+
+/// Runs `day` on its real input with `args`, passing its output through. With
+/// `perf`, it's followed by each stage's time and counts.
+fn run(day: u32, args: &[&str], perf: bool) -> Result<Outcome> {
+    let (outcome, status) = execute(day, args, perf)?;
+
+    print!("{}", outcome.stdout);
+    for measurement in &outcome.stages {
+        if let Some(counts) = &measurement.counts {
+            let [instructions, cache_misses, branch_misses] = counter_cells(counts);
+            println!(
+                "Day {} {}: {:.2?}, {} instructions, {} cache misses, {} branch misses",
+                day,
+                measurement.stage,
+                measurement.elapsed,
+                instructions,
+                cache_misses,
+                branch_misses
+            );
+        }
+    }
+    if !status.success() {
+        return Err(anyhow!("Day {} failed: {}", day, status));
+    }
+    Ok(outcome)
+}
+
+/// Quotes a CSV field if it needs it.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes a row per day and part, with the time spent parsing, the time spent
+/// on the part itself, its counts with `--perf`, and its answer. Parsing is
+/// shared by both parts, so it's repeated on each. Measurements the day didn't
+/// report are left empty.
+fn write_timings(path: &str, outcomes: &[(u32, Outcome)]) -> Result<()> {
+    let seconds = |outcome: &Outcome, stage| {
+        outcome
+            .stage(stage)
+            .map_or_else(String::new, |m| m.elapsed.as_secs_f64().to_string())
+    };
+
+    let mut csv =
+        "day,part,parse_seconds,solve_seconds,instructions,cache_misses,branch_misses,answer\n"
+            .to_string();
+    for (day, outcome) in outcomes {
+        let (part_a, part_b) = parse_answers(&outcome.stdout);
+        for (part, stage, answer) in [("A", Stage::PartA, part_a), ("B", Stage::PartB, part_b)] {
+            let counts = match outcome.stage(stage).and_then(|m| m.counts) {
+                Some(counts) => [
+                    counts.instructions,
+                    counts.cache_misses,
+                    counts.branch_misses,
+                ]
+                .map(|count| count.to_string()),
+                None => Default::default(),
+            };
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                day,
+                part,
+                seconds(outcome, Stage::Parse),
+                seconds(outcome, stage),
+                counts.join(","),
+                csv_field(answer.unwrap_or(""))
+            ));
+        }
+    }
+    std::fs::write(path, csv).with_context(|| format!("Error writing {:?}", path))
+}
+
+/// Records `day`'s answers in `results`, warning if its input has changed
+/// since they were last recorded.
+fn record(
+    results: &mut Results,
+    day: u32,
+    outcome: &Outcome,
+    revision: &Option<String>,
+) -> Result<()> {
+    let input_hash = results::hash_input(input_path(day))?;
+    if let Some(previous) = results.days.get(&day) {
+        if previous.input_hash != input_hash {
+            println!("Day {}: the input has changed since the last run", day);
+        }
+    }
+
+    let (part_a, part_b) = parse_answers(&outcome.stdout);
+    results.days.insert(
+        day,
+        DayResult {
+            part_a: part_a.map(str::to_string),
+            part_b: part_b.map(str::to_string),
+            seconds: outcome.elapsed.as_secs_f64(),
+            input_hash,
+            revision: revision.clone(),
+        },
+    );
+    Ok(())
+}
+
+/// Runs `day` on `example`, returning whether it gave the expected answers.
+fn run_example(example: &Example) -> Result<bool> {
+    let path = std::env::temp_dir().join(format!(
+        "aoc2022-{}-day{:02}{}.txt",
+        std::process::id(),
+        example.day,
+        example.name
+    ));
+    std::fs::write(&path, example.input).with_context(|| format!("Error writing {:?}", path))?;
+
+    let output = Command::new(day_binary(example.day)?)
+        .arg("--input")
+        .arg(&path)
+        .args(example.args)
+        .stderr(Stdio::inherit())
+        .output();
+    std::fs::remove_file(&path)?;
+    let output = output?;
+    if !output.status.success() {
+        return Err(anyhow!("Day {} failed: {}", example.day, output.status));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (part_a, part_b) = parse_answers(&stdout);
+    let title = match example.name {
+        "" => format!("Day {} example", example.day),
+        name => format!("Day {} example {}", example.day, name),
+    };
+
+    let mut passed = true;
+    for (part, expected, actual) in [("A", example.part_a, part_a), ("B", example.part_b, part_b)] {
+        let expected = match expected {
+            Some(expected) => expected,
+            None => continue,
+        };
+        if actual == Some(expected) {
+            println!("{}, part {}: {} ok", title, part, expected);
+        } else {
+            println!(
+                "{}, part {}: expected {}, got {}",
+                title,
+                part,
+                expected,
+                actual.unwrap_or("nothing")
+            );
+            passed = false;
+        }
+    }
+    Ok(passed)
+}
+
+/// Runs every variant of `day` on its real input and prints a table of their
+/// answers and timings, and with `perf` each part's counts too. Returns
+/// whether the answers all agree.
+fn compare(day: u32, perf: bool) -> Result<bool> {
+    let mut rows = vec![vec![
+        format!("Day {}", day),
+        "part A".to_string(),
+        "part B".to_string(),
+        "time".to_string(),
+    ]];
+    if perf {
+        for part in ["A", "B"] {
+            rows[0].extend(
+                ["instructions", "cache misses", "branch misses"]
+                    .map(|counter| format!("{} {}", part, counter)),
+            );
+        }
+    }
+    for variant in variants::for_day(day) {
+        let (outcome, status) = execute(day, variant.args, perf)?;
+        if !status.success() {
+            return Err(anyhow!(
+                "Day {} variant {} failed: {}",
+                day,
+                variant.name,
+                status
+            ));
+        }
+
+        let (part_a, part_b) = parse_answers(&outcome.stdout);
+        let mut row = vec![
+            variant.name.to_string(),
+            part_a.unwrap_or("-").to_string(),
+            part_b.unwrap_or("-").to_string(),
+            format!("{:.2?}", outcome.elapsed),
+        ];
+        if perf {
+            for stage in [Stage::PartA, Stage::PartB] {
+                match outcome.stage(stage).and_then(|m| m.counts) {
+                    Some(counts) => row.extend(counter_cells(&counts)),
+                    None => row.extend(["-", "-", "-"].map(String::from)),
+                }
+            }
+        }
+        rows.push(row);
+    }
+
+    let mut widths = vec![0; rows[0].len()];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in &rows {
+        // The answers are left aligned, and the measurements right aligned.
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, &width))| match i {
+                0..=2 => format!("{:<width$}", cell, width = width),
+                _ => format!("{:>width$}", cell, width = width),
+            })
+            .collect::<Vec<_>>();
+        println!("{}", cells.join("  ").trim_end());
+    }
+
+    let expected = &rows[1];
+    let mut agree = true;
+    for other in &rows[2..] {
+        if other[1..3] != expected[1..3] {
+            println!("Day {}: {} disagrees with {}", day, other[0], expected[0]);
+            agree = false;
+        }
+    }
+    if agree {
+        println!("Day {}: all {} variants agree", day, rows.len() - 1);
+    }
+    Ok(agree)
+}
+
+/// Prints a Markdown table of each day's latest results, marking the days
+/// which aren't solved yet. Returns how many answers differ from `golden`.
+fn report(results: &Results, golden: &BTreeMap<u32, Answers>) -> usize {
+    let mut differences = 0;
+    let mut cell = |answer: &Option<String>, expected: Option<&String>| match (answer, expected) {
+        (Some(answer), Some(expected)) if answer != expected => {
+            differences += 1;
+            format!("**{}** (expected {})", answer, expected)
+        }
+        (Some(answer), _) => answer.clone(),
+        (None, _) => "-".to_string(),
+    };
+
+    println!("| Day | Part A | Part B | Runtime |");
+    println!("|----:|--------|--------|--------:|");
+    // Advent of Code has 25 days.
+    for day in 1..=25 {
+        if !DAYS.contains(&day) {
+            println!("| {} | *not implemented* | | |", day);
+            continue;
+        }
+        let result = match results.days.get(&day) {
+            Some(result) => result,
+            None => {
+                println!("| {} | *not run* | | |", day);
+                continue;
+            }
+        };
+        let expected = golden.get(&day);
+        println!(
+            "| {} | {} | {} | {:.2?} |",
+            day,
+            cell(&result.part_a, expected.and_then(|e| e.part_a.as_ref())),
+            cell(&result.part_b, expected.and_then(|e| e.part_b.as_ref())),
+            Duration::from_secs_f64(result.seconds)
+        );
+    }
+    differences
+}
+
+/// Collects each day's parsed input, as the day prints it with
+/// `--dump-parsed`, into a JSON object keyed by day.
+fn dump_parsed(days: impl Iterator<Item = u32>) -> Result<BTreeMap<u32, serde_json::Value>> {
+    let mut parsed = BTreeMap::new();
+    for day in days {
+        let output = Command::new(day_binary(day)?)
+            .arg("--dump-parsed")
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow!("Day {} failed: {}", day, output.status));
+        }
+        let json = serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("Day {} printed invalid JSON", day))?;
+        parsed.insert(day, json);
+    }
+    Ok(parsed)
+}
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().context(
+        "Usage: aoc run|validate|compare|report [--day N] [--example] [--perf] [--timings out.csv] [--dump-parsed]",
+    )?;
+
+    let mut day = None;
+    let mut example = false;
+    let mut perf = false;
+    let mut timings_path = None;
+    let mut dump = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" => {
+                let n = args.next().context("--day requires a value")?.parse()?;
+                if !DAYS.contains(&n) {
+                    return Err(anyhow!("There is no day {}", n));
+                }
+                day = Some(n);
+            }
+            "--example" => example = true,
+            "--perf" => perf = true,
+            "--timings" => {
+                timings_path = Some(args.next().context("--timings requires a path")?);
+            }
+            "--dump-parsed" => dump = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+    let days = match day {
+        Some(day) => day..=day,
+        None => DAYS,
+    };
+    if perf {
+        if example || !matches!(command.as_str(), "run" | "compare") {
+            return Err(anyhow!("--perf can only be used with run or compare"));
+        }
+        // The days count their own stages, but check up front that they'll
+        // be able to.
+        Counters::open()?;
+    }
+    if timings_path.is_some() && (example || command != "run") {
+        return Err(anyhow!("--timings can only be used with run"));
+    }
+    if dump && (example || command != "run" || perf || timings_path.is_some()) {
+        return Err(anyhow!("--dump-parsed can only be used with run and --day"));
+    }
+
+    match command.as_str() {
+        "run" if example => {
+            let mut failed = 0;
+            for example in days.flat_map(examples::for_day) {
+                if !run_example(example)? {
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow!("{} examples gave the wrong answers", failed));
+            }
+        }
+        "run" if dump => {
+            println!("{}", serde_json::to_string_pretty(&dump_parsed(days)?)?);
+        }
+        "run" => {
+            let mut results = Results::load(Results::PATH)?;
+            let revision = results::git_revision();
+            let mut outcomes = Vec::new();
+            for day in days {
+                let outcome = run(day, &[], perf)?;
+                record(&mut results, day, &outcome, &revision)?;
+                // Saved as it goes, so the days before a failure are kept.
+                results.save(Results::PATH)?;
+                outcomes.push((day, outcome));
+            }
+            if let Some(path) = timings_path {
+                write_timings(&path, &outcomes)?;
+            }
+        }
+        "validate" | "compare" | "report" if example => {
+            return Err(anyhow!("--example can only be used with run"));
+        }
+        "validate" => {
+            for day in days {
+                run(day, &["--validate"], false)?;
+            }
+        }
+        "compare" => {
+            let days = days
+                .filter(|&day| variants::for_day(day).next().is_some())
+                .collect::<Vec<_>>();
+            if days.is_empty() {
+                return Err(anyhow!("Day {} has no variants to compare", day.unwrap()));
+            }
+
+            let mut failed = 0;
+            for (i, &day) in days.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                if !compare(day, perf)? {
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow!("{} days' variants disagree", failed));
+            }
+        }
+        "report" => {
+            if day.is_some() {
+                return Err(anyhow!("--day can't be used with report"));
+            }
+            let golden = results::load_golden(results::GOLDEN_PATH)
+                .with_context(|| format!("Error reading {}", results::GOLDEN_PATH))?;
+            let differences = report(&Results::load(Results::PATH)?, &golden);
+            if differences > 0 {
+                eprintln!(
+                    "{} answers differ from {}",
+                    differences,
+                    results::GOLDEN_PATH
+                );
+            }
+        }
+        _ => {
+            return Err(anyhow!(
+                "Unknown command {:?} (expected run, validate, compare or report)",
+                command
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_are_read_from_the_output() {
+        let output = "Day 9, part A: 13\nsome other output\nDay 9, part B:   1  \n";
+        assert_eq!(parse_answers(output), (Some("13"), Some("1")));
+        assert_eq!(
+            parse_answers("Day 1, part B: 45000\n"),
+            (None, Some("45000"))
+        );
+        assert_eq!(parse_answers(""), (None, None));
+    }
+
+    #[test]
+    fn only_the_first_answer_is_used() {
+        let output = "Day 2, part A: 15\nDay 2, part A: 16\n";
+        assert_eq!(parse_answers(output), (Some("15"), None));
+    }
+}
@@ -1,36 +1,216 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::input::Input;
+use aoc2022::parse::{eol, eol_or_eof, parse_all};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
 use nom::{
-    character::complete::{digit1, newline},
-    combinator::map_res,
-    multi::{fold_many1, separated_list1},
+    character::complete::digit1,
+    combinator::{map_opt, map_res, opt},
+    multi::{fold_many1, many1},
     sequence::terminated,
     IResult,
 };
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::str::FromStr;
 
-fn parse_input(input: &str) -> IResult<&str, Vec<u32>> {
-    separated_list1(
-        newline,
-        fold_many1(
-            terminated(map_res(digit1, |s: &str| s.parse::<u32>()), newline),
-            || 0,
-            |acc: u32, item| acc + item,
-        ),
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Elf {
+    /// The elf's position in the input, starting from 1.
+    index: usize,
+    items: usize,
+    calories: u32,
+}
+
+/// Parses one elf's items, returning how many there are and their total. A
+/// total too large for a `u32` is rejected.
+fn parse_elf(input: &str) -> IResult<&str, (usize, u32)> {
+    map_opt(
+        many1(terminated(
+            map_res(digit1, |s: &str| s.parse::<u32>()),
+            eol_or_eof,
+        )),
+        |items: Vec<u32>| {
+            let calories = items
+                .iter()
+                .try_fold(0u32, |total, &item| total.checked_add(item))?;
+            Some((items.len(), calories))
+        },
     )(input)
 }
 
+fn parse_elves(input: &str) -> IResult<&str, Vec<Elf>> {
+    let (input, elves) = many1(terminated(parse_elf, opt(eol)))(input)?;
+    let elves = elves
+        .into_iter()
+        .enumerate()
+        .map(|(i, (items, calories))| Elf {
+            index: i + 1,
+            items,
+            calories,
+        })
+        .collect();
+    Ok((input, elves))
+}
+
+/// Finds the calorie totals of the `k` elves carrying the most, largest first.
+/// Elves are folded into a bounded min-heap as they're parsed, so only `k`
+/// totals are held at once.
+fn parse_top_elves(input: &str, k: usize) -> IResult<&str, Vec<u32>> {
+    let (input, heap) = fold_many1(
+        terminated(parse_elf, opt(eol)),
+        || BinaryHeap::with_capacity(k + 1),
+        |mut heap, (_, total)| {
+            heap.push(Reverse(total));
+            if heap.len() > k {
+                heap.pop();
+            }
+            heap
+        },
+    )(input)?;
+
+    // Sorting the reversed totals ascending puts the largest first.
+    let top = heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(total)| total)
+        .collect();
+    Ok((input, top))
+}
+
+/// How to list the elves for `--elves`.
+enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(anyhow!(
+                "Unknown format {:?} (expected text, json or csv)",
+                s
+            )),
+        }
+    }
+}
+
+/// Checks there are enough elves for part B.
+fn validate(elves: &[Elf], k: usize) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if elves.len() < k {
+        warnings.push(format!(
+            "Only {} elves, but part B needs at least {}",
+            elves.len(),
+            k
+        ));
+    }
+    warnings
+}
+
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input01.txt")?;
-    let mut elves = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut k = 3;
+    let mut elves_format = None;
+    let mut input_path = "res/input01.txt".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--top-k" => {
+                k = args.next().context("--top-k requires a value")?.parse()?;
+                if k == 0 {
+                    return Err(anyhow!("--top-k must be at least 1"));
+                }
+            }
+            "--elves" => {
+                elves_format = Some(args.next().context("--elves requires a format")?.parse()?)
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    // Large synthetic inputs are memory-mapped, and only the top elves are
+    // kept, so even huge inputs need little memory.
+    let input =
+        Input::open(&input_path).with_context(|| format!("Error reading {:?}", input_path))?;
+
+    if dump_parsed {
+        return Ok(print_json(&parse_all(parse_elves, &input)?)?);
+    }
+
+    if validate_only {
+        let elves = parse_all(parse_elves, &input)?;
+        report_warnings(1, &validate(&elves, k));
+        return Ok(());
+    }
 
-    elves.sort();
+    // The top elves are picked out while parsing, so that's most of the work.
+    let top = timing::time(Stage::Parse, || {
+        parse_all(|input| parse_top_elves(input, k), &input)
+    })?;
 
-    let result_a = elves.last().unwrap();
+    if let Some(format) = elves_format {
+        let mut elves = parse_all(parse_elves, &input)?;
+        // List the best supplied elves first, so the top group leads.
+        elves.sort_by_key(|elf| (Reverse(elf.calories), elf.index));
+
+        match format {
+            Format::Json => print_json(&elves)?,
+            Format::Csv => {
+                println!("rank,index,items,calories");
+                for (rank, elf) in elves.iter().enumerate() {
+                    println!("{},{},{},{}", rank + 1, elf.index, elf.items, elf.calories);
+                }
+            }
+            Format::Text => {
+                for (rank, elf) in elves.iter().enumerate() {
+                    println!(
+                        "{}{}. Elf {}: {} items, {} calories",
+                        if rank < k { "*" } else { " " },
+                        rank + 1,
+                        elf.index,
+                        elf.items,
+                        elf.calories
+                    );
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let result_a = timing::time(Stage::PartA, || top[0]);
     println!("Day 1, part A: {}", result_a);
 
-    let result_b = elves.iter().rev().take(3).sum::<u32>();
+    let result_b = timing::time(Stage::PartB, || {
+        top.iter().map(|&calories| u64::from(calories)).sum::<u64>()
+    });
     println!("Day 1, part B: {}", result_b);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_elf_rejects_overflowing_totals() {
+        assert_eq!(
+            parse_elf("4000000000\n200000000\n"),
+            Ok(("", (2, 4200000000)))
+        );
+        assert!(parse_elf("4000000000\n400000000\n").is_err());
+    }
+}
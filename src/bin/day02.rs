@@ -1,14 +1,18 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::{eol, parse_all};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
 use nom::{
-    bytes::complete::tag,
-    character::complete::{newline, one_of},
-    combinator::map,
-    multi::separated_list1,
-    sequence::separated_pair,
-    IResult,
+    bytes::complete::tag, character::complete::satisfy, multi::separated_list1,
+    sequence::separated_pair, IResult,
 };
+use std::collections::HashMap;
+use std::str::FromStr;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 enum Outcome {
     Win,
     Draw,
@@ -16,6 +20,14 @@ enum Outcome {
 }
 
 impl Outcome {
+    fn name(&self) -> &'static str {
+        match *self {
+            Outcome::Win => "win",
+            Outcome::Draw => "draw",
+            Outcome::Lose => "lose",
+        }
+    }
+
     fn points(&self) -> u32 {
         match *self {
             Outcome::Win => 6,
@@ -25,152 +37,437 @@ impl Outcome {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-enum Move {
-    Rock,
-    Paper,
-    Scissors,
+/// A game where the moves form a cycle, each beating the moves an odd number
+/// of steps before it. With rock, paper and scissors that means each move
+/// beats the previous one, and with rock, paper, scissors, Spock and lizard
+/// each move beats the ones one and three steps before it. A move's points are
+/// its position in the cycle, starting from 1.
+#[derive(Debug)]
+struct Game {
+    moves: Vec<String>,
 }
 
-impl Move {
-    fn points(&self) -> u32 {
-        match *self {
-            Move::Rock => 1,
-            Move::Paper => 2,
-            Move::Scissors => 3,
+impl Game {
+    fn new(moves: Vec<String>) -> Result<Self> {
+        // With an even number of moves some pairs would be undecided.
+        if moves.len() < 3 || moves.len().is_multiple_of(2) {
+            return Err(anyhow!(
+                "A game needs an odd number of at least 3 moves, got {}",
+                moves.len()
+            ));
         }
+        Ok(Game { moves })
     }
-}
 
-trait Turn {
-    fn from_input(input: (char, char)) -> Self;
-    fn my_move(&self) -> Move;
-    fn outcome(&self) -> Outcome;
-    fn points(&self) -> u32 {
-        self.my_move().points() + self.outcome().points()
+    fn rock_paper_scissors() -> Self {
+        Game::new(vec!["rock".into(), "paper".into(), "scissors".into()]).unwrap()
     }
-}
 
-#[derive(Copy, Clone, Debug)]
-struct TurnA {
-    their_move: Move,
-    my_move: Move,
-}
-
-impl Turn for TurnA {
-    fn from_input(input: (char, char)) -> Self {
-        let their_move = match input.0 {
-            'A' => Move::Rock,
-            'B' => Move::Paper,
-            'C' => Move::Scissors,
-            _ => unreachable!(),
-        };
-
-        let my_move = match input.1 {
-            'X' => Move::Rock,
-            'Y' => Move::Paper,
-            'Z' => Move::Scissors,
-            _ => unreachable!(),
-        };
+    fn rock_paper_scissors_spock_lizard() -> Self {
+        Game::new(vec![
+            "rock".into(),
+            "paper".into(),
+            "scissors".into(),
+            "spock".into(),
+            "lizard".into(),
+        ])
+        .unwrap()
+    }
 
-        Self {
-            their_move,
-            my_move,
-        }
+    fn move_named(&self, name: &str) -> Option<usize> {
+        self.moves.iter().position(|m| m == name)
     }
 
-    fn my_move(&self) -> Move {
-        self.my_move
+    fn points(&self, m: usize) -> u32 {
+        m as u32 + 1
     }
 
-    fn outcome(&self) -> Outcome {
-        match (self.my_move, self.their_move) {
-            (Move::Rock, Move::Rock) => Outcome::Draw,
-            (Move::Rock, Move::Paper) => Outcome::Lose,
-            (Move::Rock, Move::Scissors) => Outcome::Win,
-            (Move::Paper, Move::Rock) => Outcome::Win,
-            (Move::Paper, Move::Paper) => Outcome::Draw,
-            (Move::Paper, Move::Scissors) => Outcome::Lose,
-            (Move::Scissors, Move::Rock) => Outcome::Lose,
-            (Move::Scissors, Move::Paper) => Outcome::Win,
-            (Move::Scissors, Move::Scissors) => Outcome::Draw,
+    fn outcome(&self, mine: usize, theirs: usize) -> Outcome {
+        let steps = (mine + self.moves.len() - theirs) % self.moves.len();
+        if steps == 0 {
+            Outcome::Draw
+        } else if steps % 2 == 1 {
+            Outcome::Win
+        } else {
+            Outcome::Lose
         }
     }
+
+    /// The first move in the cycle which gives `outcome` against `theirs`.
+    fn move_for(&self, theirs: usize, outcome: Outcome) -> usize {
+        (0..self.moves.len())
+            .find(|&mine| self.outcome(mine, theirs) == outcome)
+            .unwrap()
+    }
 }
 
+/// What a letter in the second column asks me to do.
 #[derive(Copy, Clone, Debug)]
-struct TurnB {
-    their_move: Move,
-    outcome: Outcome,
+enum Instruction {
+    Play(usize),
+    Get(Outcome),
 }
 
-impl Turn for TurnB {
-    fn from_input(input: (char, char)) -> Self {
-        let their_move = match input.0 {
-            'A' => Move::Rock,
-            'B' => Move::Paper,
-            'C' => Move::Scissors,
-            _ => unreachable!(),
-        };
+/// How to read the two columns of the strategy guide. The first column is
+/// always the opponent's move, with A being the first move of the game, B the
+/// second and so on.
+#[derive(Debug)]
+struct Interpretation {
+    mine: HashMap<char, Instruction>,
+}
+
+impl Interpretation {
+    /// The second column is my move, ending with Z for the last move of the
+    /// game, so X, Y and Z for rock, paper and scissors. Fails if the game
+    /// has more moves than there are letters.
+    fn moves(game: &Game) -> Result<Self> {
+        let letters = (b'A'..=b'Z').len();
+        if game.moves.len() > letters {
+            return Err(anyhow!(
+                "A game with {} moves has too many to give each a letter (at most {})",
+                game.moves.len(),
+                letters
+            ));
+        }
+        let first = b'Z' - (game.moves.len() - 1) as u8;
+        Ok(Interpretation {
+            mine: (0..game.moves.len())
+                .map(|m| ((first + m as u8) as char, Instruction::Play(m)))
+                .collect(),
+        })
+    }
+
+    /// The second column is the outcome: X to lose, Y to draw and Z to win.
+    fn outcomes() -> Self {
+        Interpretation {
+            mine: [
+                ('X', Instruction::Get(Outcome::Lose)),
+                ('Y', Instruction::Get(Outcome::Draw)),
+                ('Z', Instruction::Get(Outcome::Win)),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    /// Parses a mapping such as "X=rock,Y=draw,Z=win", where each letter is
+    /// either a move of the game or an outcome.
+    fn parse(game: &Game, spec: &str) -> Result<Self> {
+        let mine = spec
+            .split(',')
+            .map(|entry| {
+                let (letter, meaning) = entry
+                    .split_once('=')
+                    .with_context(|| format!("Expected letter=meaning, got {:?}", entry))?;
+                let mut chars = letter.chars();
+                let letter = match (chars.next(), chars.next()) {
+                    (Some(letter), None) => letter,
+                    _ => return Err(anyhow!("Expected a single letter, got {:?}", letter)),
+                };
+
+                let instruction = match meaning {
+                    "win" => Instruction::Get(Outcome::Win),
+                    "draw" => Instruction::Get(Outcome::Draw),
+                    "lose" => Instruction::Get(Outcome::Lose),
+                    _ => Instruction::Play(game.move_named(meaning).with_context(|| {
+                        format!(
+                            "Unknown meaning {:?} (expected win, draw, lose or one of {})",
+                            meaning,
+                            game.moves.join(", ")
+                        )
+                    })?),
+                };
+                Ok((letter, instruction))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Interpretation { mine })
+    }
+
+    fn turn(&self, game: &Game, (theirs, mine): (char, char)) -> Result<Turn> {
+        let their_move = (theirs as usize)
+            .checked_sub('A' as usize)
+            .filter(|&m| m < game.moves.len())
+            .with_context(|| format!("Unknown opponent move {:?}", theirs))?;
 
-        let outcome = match input.1 {
-            'X' => Outcome::Lose,
-            'Y' => Outcome::Draw,
-            'Z' => Outcome::Win,
-            _ => unreachable!(),
+        let (my_move, outcome) = match self.mine.get(&mine) {
+            Some(&Instruction::Play(my_move)) => (my_move, game.outcome(my_move, their_move)),
+            Some(&Instruction::Get(outcome)) => (game.move_for(their_move, outcome), outcome),
+            None => return Err(anyhow!("No meaning given for {:?}", mine)),
         };
 
-        Self {
+        Ok(Turn {
             their_move,
+            my_move,
             outcome,
-        }
+        })
     }
+}
 
-    fn my_move(&self) -> Move {
-        match (self.their_move, self.outcome) {
-            (Move::Rock, Outcome::Lose) => Move::Scissors,
-            (Move::Rock, Outcome::Win) => Move::Paper,
-            (Move::Rock, Outcome::Draw) => Move::Rock,
-            (Move::Paper, Outcome::Lose) => Move::Rock,
-            (Move::Paper, Outcome::Win) => Move::Scissors,
-            (Move::Paper, Outcome::Draw) => Move::Paper,
-            (Move::Scissors, Outcome::Lose) => Move::Paper,
-            (Move::Scissors, Outcome::Win) => Move::Rock,
-            (Move::Scissors, Outcome::Draw) => Move::Scissors,
-        }
-    }
+#[derive(Copy, Clone, Debug)]
+struct Turn {
+    their_move: usize,
+    my_move: usize,
+    outcome: Outcome,
+}
 
-    fn outcome(&self) -> Outcome {
-        self.outcome
+impl Turn {
+    fn points(&self, game: &Game) -> u32 {
+        game.points(self.my_move) + self.outcome.points()
     }
 }
 
-fn parse_input<T: Turn>(input: &str) -> IResult<&str, Vec<T>> {
+fn parse_input(input: &str) -> IResult<&str, Vec<(char, char)>> {
     separated_list1(
-        newline,
-        map(
-            separated_pair(one_of("ABC"), tag(" "), one_of("XYZ")),
-            Turn::from_input,
+        eol,
+        separated_pair(
+            satisfy(|c| c.is_ascii_uppercase()),
+            tag(" "),
+            satisfy(|c| c.is_ascii_uppercase()),
         ),
     )(input)
 }
 
+fn total_points(
+    game: &Game,
+    interpretation: &Interpretation,
+    rounds: &[(char, char)],
+) -> Result<u32> {
+    rounds
+        .iter()
+        .enumerate()
+        .map(|(i, &round)| {
+            let turn = interpretation
+                .turn(game, round)
+                .with_context(|| format!("Round {}", i + 1))?;
+            Ok(turn.points(game))
+        })
+        .sum()
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+struct TurnReport<'a> {
+    my_move: &'a str,
+    outcome: Outcome,
+    points: u32,
+}
+
+impl<'a> TurnReport<'a> {
+    fn new(game: &'a Game, turn: &Turn) -> Self {
+        TurnReport {
+            my_move: &game.moves[turn.my_move],
+            outcome: turn.outcome,
+            points: turn.points(game),
+        }
+    }
+}
+
+/// A round as played under both parts' interpretations.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+struct RoundReport<'a> {
+    round: usize,
+    their_move: &'a str,
+    part_a: TurnReport<'a>,
+    part_b: TurnReport<'a>,
+}
+
+fn round_reports<'a>(game: &'a Game, rounds: &[(char, char)]) -> Result<Vec<RoundReport<'a>>> {
+    let (a, b) = (Interpretation::moves(game)?, Interpretation::outcomes());
+    rounds
+        .iter()
+        .enumerate()
+        .map(|(i, &round)| {
+            let turn_a = a
+                .turn(game, round)
+                .with_context(|| format!("Round {}", i + 1))?;
+            let turn_b = b
+                .turn(game, round)
+                .with_context(|| format!("Round {}", i + 1))?;
+            Ok(RoundReport {
+                round: i + 1,
+                their_move: &game.moves[turn_a.their_move],
+                part_a: TurnReport::new(game, &turn_a),
+                part_b: TurnReport::new(game, &turn_b),
+            })
+        })
+        .collect()
+}
+
+/// Counts the wins, draws and losses in each part.
+fn print_stats(reports: &[RoundReport]) {
+    for (part, turns) in [
+        ("A", reports.iter().map(|r| &r.part_a).collect::<Vec<_>>()),
+        ("B", reports.iter().map(|r| &r.part_b).collect::<Vec<_>>()),
+    ] {
+        let count = |outcome| turns.iter().filter(|t| t.outcome == outcome).count();
+        println!(
+            "Part {}: {} wins, {} draws, {} losses, {} points",
+            part,
+            count(Outcome::Win),
+            count(Outcome::Draw),
+            count(Outcome::Lose),
+            turns.iter().map(|t| t.points).sum::<u32>()
+        );
+    }
+}
+
+/// How to export the rounds for `--rounds`.
+enum Format {
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(anyhow!("Unknown format {:?} (expected json or csv)", s)),
+        }
+    }
+}
+
+/// Checks each round can be played under both parts' interpretations.
+fn validate(game: &Game, rounds: &[(char, char)]) -> Vec<String> {
+    let moves = match Interpretation::moves(game) {
+        Ok(moves) => moves,
+        Err(e) => return vec![e.to_string()],
+    };
+    let interpretations = [moves, Interpretation::outcomes()];
+    rounds
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &round)| {
+            interpretations
+                .iter()
+                .find_map(|interpretation| interpretation.turn(game, round).err())
+                .map(|e| format!("Round {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input02.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input02.txt".to_string();
+    let mut game = Game::rock_paper_scissors();
+    let mut custom = None;
+    let mut rounds_format = None;
+    let mut stats = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--game" => {
+                game = match args.next().context("--game requires a value")?.as_str() {
+                    "rps" => Game::rock_paper_scissors(),
+                    "rpsls" => Game::rock_paper_scissors_spock_lizard(),
+                    moves => Game::new(moves.split(',').map(String::from).collect())?,
+                }
+            }
+            "--interpretation" => {
+                custom = Some(args.next().context("--interpretation requires a value")?)
+            }
+            "--rounds" => {
+                rounds_format = Some(args.next().context("--rounds requires a format")?.parse()?)
+            }
+            "--stats" => stats = true,
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
 
-    let turns_a: Vec<TurnA> = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
-    let result_a = turns_a.iter().map(Turn::points).sum::<u32>();
+    let custom = match custom {
+        Some(spec) => Some((Interpretation::parse(&game, &spec)?, spec)),
+        None => None,
+    };
 
-    println!("Day 2, part A: {}", result_a);
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
+
+    let rounds = timing::time(Stage::Parse, || parse_all(parse_input, &input))?;
+
+    if dump_parsed {
+        return Ok(print_json(&rounds)?);
+    }
+
+    if validate_only {
+        report_warnings(2, &validate(&game, &rounds));
+        return Ok(());
+    }
+
+    if let Some(format) = rounds_format {
+        let reports = round_reports(&game, &rounds)?;
+        match format {
+            Format::Json => print_json(&reports)?,
+            Format::Csv => {
+                println!(
+                    "round,their_move,a_my_move,a_outcome,a_points,b_my_move,b_outcome,b_points"
+                );
+                for r in &reports {
+                    println!(
+                        "{},{},{},{},{},{},{},{}",
+                        r.round,
+                        r.their_move,
+                        r.part_a.my_move,
+                        r.part_a.outcome.name(),
+                        r.part_a.points,
+                        r.part_b.my_move,
+                        r.part_b.outcome.name(),
+                        r.part_b.points
+                    );
+                }
+            }
+        }
+        return Ok(());
+    }
 
-    let turns_b: Vec<TurnB> = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
-    let result_b = turns_b.iter().map(Turn::points).sum::<u32>();
+    if stats {
+        print_stats(&round_reports(&game, &rounds)?);
+    }
 
+    let result_a = timing::time(Stage::PartA, || {
+        total_points(&game, &Interpretation::moves(&game)?, &rounds)
+    })?;
+    println!("Day 2, part A: {}", result_a);
+
+    let result_b = timing::time(Stage::PartB, || {
+        total_points(&game, &Interpretation::outcomes(), &rounds)
+    })?;
     println!("Day 2, part B: {}", result_b);
 
+    if let Some((interpretation, spec)) = custom {
+        let result = total_points(&game, &interpretation, &rounds)?;
+        println!("Day 2, {}: {}", spec, result);
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(len: usize) -> Game {
+        Game::new((0..len).map(|m| m.to_string()).collect()).unwrap()
+    }
+
+    #[test]
+    fn moves_end_with_z() {
+        let interpretation = Interpretation::moves(&game(5)).unwrap();
+        let mut letters = interpretation.mine.keys().copied().collect::<Vec<_>>();
+        letters.sort();
+        assert_eq!(letters, ['V', 'W', 'X', 'Y', 'Z']);
+        assert!(Interpretation::moves(&game(25)).is_ok());
+    }
+
+    #[test]
+    fn moves_rejects_games_with_too_many_moves() {
+        assert!(Interpretation::moves(&game(27)).is_err());
+    }
+}
@@ -1,47 +1,157 @@
 use std::collections::HashSet;
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
 
-fn parse_input(input: &str) -> Vec<Vec<char>> {
-    input.lines().map(|line| line.chars().collect::<Vec<char>>()).collect()
+fn parse_input(input: &str) -> Vec<&str> {
+    input.lines().collect()
 }
 
-fn priority(c: char) -> u32 {
+/// Splits a rucksack into its two compartments, before and after the middle
+/// item.
+fn compartments(rucksack: &str) -> (&str, &str) {
+    let middle = rucksack.char_indices().nth(rucksack.chars().count() / 2).map_or(rucksack.len(), |(i, _)| i);
+    rucksack.split_at(middle)
+}
+
+fn priority(c: char) -> Result<u32> {
     match c {
-        c if ('a'..='z').contains(&c) => c as u32 - 'a' as u32 + 1,
-        c if ('A'..='Z').contains(&c) => c as u32 - 'A' as u32 + 27,
-        _ => unreachable!(),
+        c if c.is_ascii_lowercase() => Ok(c as u32 - 'a' as u32 + 1),
+        c if c.is_ascii_uppercase() => Ok(c as u32 - 'A' as u32 + 27),
+        _ => Err(anyhow!("Invalid item {:?}", c)),
     }
 }
 
-fn part_a(rucksacks: &[Vec<char>]) -> u32 {
-    rucksacks.iter().map(|rucksack| {
-        let (first, second) = rucksack.split_at(rucksack.len() / 2);
-        let set = first.iter().collect::<HashSet<_>>();
+fn part_a(rucksacks: &[&str]) -> Result<u32> {
+    rucksacks.iter().enumerate().map(|(i, rucksack)| {
+        // Reject bad items anywhere in the rucksack, not just a bad duplicate.
+        for item in rucksack.chars() {
+            priority(item).with_context(|| format!("Line {}", i + 1))?;
+        }
+
+        let (first, second) = compartments(rucksack);
+        let set = first.chars().collect::<HashSet<_>>();
 
-        let duplicate = second.iter().find(|item| set.contains(item)).unwrap();
-        priority(*duplicate)
+        let duplicate = second.chars().find(|item| set.contains(item))
+            .with_context(|| format!("Line {}: no item is in both compartments", i + 1))?;
+        priority(duplicate).with_context(|| format!("Line {}", i + 1))
     }).sum()
 }
 
-fn part_b(rucksacks: &[Vec<char>]) -> u32 {
-    rucksacks.chunks_exact(3).map(|group| {
-        let sets = group.iter().map(|elf| elf.iter().cloned().collect::<HashSet<char>>()).collect::<Vec<_>>();
-        let intersection = &(&sets[0] & &sets[1]) & &sets[2];
+/// Sums the priorities of the single item shared by each group of
+/// `group_size` consecutive elves.
+fn part_b(rucksacks: &[&str], group_size: usize) -> Result<u32> {
+    let leftover = rucksacks.len() % group_size;
+    if leftover != 0 {
+        return Err(anyhow!(
+            "{} lines left over after the last group of {}",
+            leftover,
+            group_size
+        ));
+    }
+
+    rucksacks.chunks_exact(group_size).enumerate().map(|(i, group)| {
+        let first_line = i * group_size + 1;
+        let intersection = group.iter()
+            .map(|elf| elf.chars().collect::<HashSet<char>>())
+            .reduce(|acc, set| &acc & &set)
+            .unwrap();
 
-        priority(*intersection.iter().next().unwrap())
+        let badge = intersection.iter().next().with_context(|| {
+            format!("Lines {}-{}: no item is shared by the group", first_line, first_line + group_size - 1)
+        })?;
+        priority(*badge).with_context(|| format!("Lines {}-{}", first_line, first_line + group_size - 1))
     }).sum()
 }
 
+/// Checks that each rucksack's compartments share exactly one item, and that
+/// each group of `group_size` rucksacks does too.
+fn validate(rucksacks: &[&str], group_size: usize) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (i, rucksack) in rucksacks.iter().enumerate() {
+        let len = rucksack.chars().count();
+        if len % 2 != 0 {
+            warnings.push(format!("Line {}: {} items can't be split into equal compartments", i + 1, len));
+        }
+        if let Some(c) = rucksack.chars().find(|c| priority(*c).is_err()) {
+            warnings.push(format!("Line {}: invalid item {:?}", i + 1, c));
+        }
+        let (first, second) = compartments(rucksack);
+        let shared = first.chars().filter(|c| second.contains(*c)).collect::<HashSet<_>>();
+        if shared.len() != 1 {
+            warnings.push(format!("Line {}: {} items are in both compartments (expected 1)", i + 1, shared.len()));
+        }
+    }
+
+    if !rucksacks.len().is_multiple_of(group_size) {
+        warnings.push(format!("{} lines left over after the last group of {}", rucksacks.len() % group_size, group_size));
+    }
+    for (i, group) in rucksacks.chunks_exact(group_size).enumerate() {
+        let shared = group.iter()
+            .map(|elf| elf.chars().collect::<HashSet<char>>())
+            .reduce(|acc, set| &acc & &set)
+            .unwrap();
+        if shared.len() != 1 {
+            let first_line = i * group_size + 1;
+            warnings.push(format!("Lines {}-{}: {} items are shared by the group (expected 1)", first_line, first_line + group_size - 1, shared.len()));
+        }
+    }
+    warnings
+}
+
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input03.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input03.txt".to_string();
+    let mut group_size = 3;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--group-size" => {
+                group_size = args.next().context("--group-size requires a value")?.parse()?;
+                if group_size == 0 {
+                    return Err(anyhow!("--group-size must be at least 1"));
+                }
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
+
+    let rucksacks = timing::time(Stage::Parse, || parse_input(&input));
+
+    if dump_parsed {
+        return Ok(print_json(&rucksacks)?);
+    }
 
-    let rucksacks = parse_input(&input);
+    if validate_only {
+        report_warnings(3, &validate(&rucksacks, group_size));
+        return Ok(());
+    }
 
-    let result_a = part_a(&rucksacks);
+    let result_a = timing::time(Stage::PartA, || part_a(&rucksacks))?;
     println!("Day 3, part A: {}", result_a);
 
-    let result_b = part_b(&rucksacks);
+    let result_b = timing::time(Stage::PartB, || part_b(&rucksacks, group_size))?;
     println!("Day 3, part B: {}", result_b);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_a_rejects_invalid_items_anywhere() {
+        assert_eq!(part_a(&["abcAbC"]).unwrap(), 2);
+        assert!(part_a(&["a1cAbC"]).is_err());
+        assert!(part_a(&["abcAb!"]).is_err());
+    }
+}
@@ -1,9 +1,14 @@
+use std::collections::BTreeMap;
 use std::ops::RangeInclusive;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::{eol, parse_all};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
 use nom::{
     bytes::complete::tag,
-    character::complete::{digit1, newline},
+    character::complete::digit1,
     combinator::{map, map_res},
     multi::separated_list1,
     sequence::separated_pair,
@@ -23,28 +28,127 @@ fn parse_range(input: &str) -> IResult<&str, RangeInclusive<u32>> {
 
 #[allow(clippy::type_complexity)]
 fn parse_input(input: &str) -> IResult<&str, Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>> {
-    separated_list1(newline, separated_pair(parse_range, tag(","), parse_range))(input)
+    separated_list1(eol, separated_pair(parse_range, tag(","), parse_range))(input)
 }
 
-fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input04.txt")?;
-    let pairs = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+/// The number of sections in both ranges of a pair.
+fn overlap_length(pair: &(RangeInclusive<u32>, RangeInclusive<u32>)) -> u32 {
+    let start = pair.0.start().max(pair.1.start());
+    let end = pair.0.end().min(pair.1.end());
+    if start <= end {
+        end - start + 1
+    } else {
+        0
+    }
+}
+
+/// Reports each pair's overlap, the total number of overlapping sections, and
+/// a histogram of overlap lengths.
+fn print_stats(pairs: &[(RangeInclusive<u32>, RangeInclusive<u32>)]) {
+    let lengths = pairs.iter().map(overlap_length).collect::<Vec<_>>();
+
+    for (i, (pair, length)) in pairs.iter().zip(&lengths).enumerate() {
+        println!(
+            "Pair {}: {}-{},{}-{} overlap {}",
+            i + 1,
+            pair.0.start(),
+            pair.0.end(),
+            pair.1.start(),
+            pair.1.end(),
+            length
+        );
+    }
+
+    println!(
+        "Total overlapping sections: {}",
+        lengths.iter().sum::<u32>()
+    );
 
-    let result_a = pairs
+    let mut histogram = BTreeMap::new();
+    for &length in &lengths {
+        *histogram.entry(length).or_insert(0usize) += 1;
+    }
+    let widest = histogram.values().copied().max().unwrap_or(0);
+    println!("Overlap length histogram:");
+    for (length, count) in histogram {
+        // Scale the bars so the most common length is 50 wide.
+        let bar = "#".repeat((count * 50).div_ceil(widest));
+        println!("{:>4}: {:>4} {}", length, count, bar);
+    }
+}
+
+/// Checks that no range runs backwards.
+fn validate(pairs: &[(RangeInclusive<u32>, RangeInclusive<u32>)]) -> Vec<String> {
+    pairs
         .iter()
-        .filter(|pair| {
-            (pair.0.start() <= pair.1.start() && pair.0.end() >= pair.1.end())
-                || (pair.1.start() <= pair.0.start() && pair.1.end() >= pair.0.end())
+        .enumerate()
+        .flat_map(|(i, pair)| {
+            [&pair.0, &pair.1]
+                .into_iter()
+                .filter(|range| range.is_empty())
+                .map(move |range| {
+                    format!(
+                        "Line {}: range {}-{} runs backwards",
+                        i + 1,
+                        range.start(),
+                        range.end()
+                    )
+                })
         })
-        .count();
+        .collect()
+}
+
+fn main() -> Result<()> {
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input04.txt".to_string();
+    let mut stats = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stats" => stats = true,
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
+
+    let pairs = timing::time(Stage::Parse, || parse_all(parse_input, &input))?;
+
+    if dump_parsed {
+        return Ok(print_json(&pairs)?);
+    }
+
+    if validate_only {
+        report_warnings(4, &validate(&pairs));
+        return Ok(());
+    }
+
+    if stats {
+        print_stats(&pairs);
+    }
+
+    let result_a = timing::time(Stage::PartA, || {
+        pairs
+            .iter()
+            .filter(|pair| {
+                (pair.0.start() <= pair.1.start() && pair.0.end() >= pair.1.end())
+                    || (pair.1.start() <= pair.0.start() && pair.1.end() >= pair.0.end())
+            })
+            .count()
+    });
     println!("Day 4, part A: {}", result_a);
 
-    let result_b = pairs
-        .iter()
-        .filter(|pair| pair.0.start() <= pair.1.end() && pair.1.start() <= pair.0.end())
-        .count();
+    let result_b = timing::time(Stage::PartB, || {
+        pairs
+            .iter()
+            .filter(|pair| pair.0.start() <= pair.1.end() && pair.1.start() <= pair.0.end())
+            .count()
+    });
     println!("Day 4, part B: {}", result_b);
 
     Ok(())
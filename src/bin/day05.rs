@@ -1,52 +1,78 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::{eol, parse_all};
+use aoc2022::rng::Rng;
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{anychar, char, digit1, multispace1, newline},
-    combinator::{map, map_res},
+    character::complete::{anychar, digit1, multispace1},
+    combinator::{map, map_res, opt},
+    error::{Error, ErrorKind},
     multi::separated_list1,
     sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
+use std::fmt;
+use std::time::Duration;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Move {
     count: usize,
     from: usize,
     to: usize,
 }
 
-fn parse_input(input: &str) -> IResult<&str, (Vec<Vec<char>>, Vec<Move>)> {
-    let (input, raw_stacks) = terminated(
-        separated_list1(
-            alt((newline, char(' '))),
-            alt((
-                map(delimited(tag("["), anychar, tag("]")), Some),
-                map(tag("   "), |_| None),
-            )),
-        ),
-        newline,
+/// The starting arrangement of crates, and the moves to make. The stacks'
+/// names are borrowed from the input.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Stacks<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    names: Vec<&'a str>,
+    stacks: Vec<Vec<char>>,
+    moves: Vec<Move>,
+}
+
+/// Parses a row of crates, returning it along with the input it starts at so
+/// that a malformed row can be reported.
+fn parse_crate_row(input: &str) -> IResult<&str, (&str, Vec<Option<char>>)> {
+    let (rest, row) = separated_list1(
+        tag(" "),
+        alt((
+            map(delimited(tag("["), anychar, tag("]")), Some),
+            map(tag("   "), |_| None),
+        )),
     )(input)?;
+    Ok((rest, (input, row)))
+}
+
+fn parse_input(input: &str) -> IResult<&str, Stacks<'_>> {
+    let (input, rows) = terminated(separated_list1(eol, parse_crate_row), eol)(input)?;
 
     let (input, stack_names) = terminated(
-        delimited(tag(" "), separated_list1(tag("   "), digit1), tag(" ")),
+        delimited(tag(" "), separated_list1(tag("   "), digit1), opt(tag(" "))),
         multispace1,
     )(input)?;
 
+    // Rows may be missing their trailing empty spaces if the input has had
+    // trailing whitespace stripped, but can't have more crates than stacks.
     let num_stacks = stack_names.len();
     let mut stacks = vec![Vec::new(); num_stacks];
-    assert_eq!(raw_stacks.len() % num_stacks, 0);
-    let tallest_stack_height = raw_stacks.len() / num_stacks;
-    for y in (0..tallest_stack_height).rev() {
-        for (x, stack) in stacks.iter_mut().enumerate() {
-            if let Some(c) = raw_stacks[y * num_stacks + x] {
+    for (row_input, row) in rows.into_iter().rev() {
+        if row.len() > num_stacks {
+            return Err(nom::Err::Failure(Error::new(row_input, ErrorKind::Count)));
+        }
+        for (stack, c) in stacks.iter_mut().zip(row) {
+            if let Some(c) = c {
                 stack.push(c);
             }
         }
     }
 
     let (input, moves) = separated_list1(
-        newline,
+        eol,
         map(
             tuple((
                 map_res(preceded(tag("move "), digit1), |s: &str| s.parse::<usize>()),
@@ -59,45 +85,340 @@ fn parse_input(input: &str) -> IResult<&str, (Vec<Vec<char>>, Vec<Move>)> {
         ),
     )(input)?;
 
-    Ok((input, (stacks, moves)))
+    Ok((
+        input,
+        Stacks {
+            names: stack_names,
+            stacks,
+            moves,
+        },
+    ))
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
+/// How to print the complete final stacks.
+enum FinalFormat {
+    Text,
+    Json,
 }
 
-fn move_crates(stacks: &[Vec<char>], moves: &[Move], preserve_order: bool) -> String {
-    let mut stacks = stacks.to_vec();
-    for m in moves {
-        // Appease the borrow checker
-        let mut from = std::mem::take(&mut stacks[m.from - 1]);
-        let mut to = std::mem::take(&mut stacks[m.to - 1]);
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+struct FinalStacks {
+    part_a: Vec<String>,
+    part_b: Vec<String>,
+}
+
+/// Draws the stacks in the same bracketed column format as the input.
+fn render_stacks(names: &[&str], stacks: &[Vec<char>]) -> String {
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
 
-        let moved = from.drain((from.len() - m.count)..);
+    let mut out = String::new();
+    for y in (0..height).rev() {
+        let row = stacks
+            .iter()
+            .map(|stack| match stack.get(y) {
+                Some(c) => format!("[{}]", c),
+                None => "   ".to_string(),
+            })
+            .collect::<Vec<_>>();
+        out.push_str(&row.join(" "));
+        out.push('\n');
+    }
+    let names = names
+        .iter()
+        .map(|name| format!(" {} ", name))
+        .collect::<Vec<_>>();
+    out.push_str(&names.join(" "));
+    out.push('\n');
+    out
+}
+
+/// Borrows two distinct elements of `slice` mutably at once.
+fn two_mut<T>(slice: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert_ne!(a, b);
+    if a < b {
+        let (left, right) = slice.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = slice.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+/// Applies `moves` to `stacks`, calling `after_move` with the index of each
+/// move and the resulting stacks, and returns the final stacks.
+/// Fails if a move refers to a missing stack or moves more crates than the
+/// stack holds.
+fn move_crates(
+    mut stacks: Vec<Vec<char>>,
+    moves: &[Move],
+    preserve_order: bool,
+    mut after_move: impl FnMut(usize, &Move, &[Vec<char>]),
+) -> Result<Vec<Vec<char>>> {
+    for (i, m) in moves.iter().enumerate() {
+        for stack in [m.from, m.to] {
+            if stack == 0 || stack > stacks.len() {
+                return Err(anyhow!(
+                    "Move {} ({}): stack {} does not exist, there are {} stacks",
+                    i + 1,
+                    m,
+                    stack,
+                    stacks.len()
+                ));
+            }
+        }
+        if m.from == m.to {
+            return Err(anyhow!(
+                "Move {} ({}): cannot move from stack {} to itself",
+                i + 1,
+                m,
+                m.from
+            ));
+        }
+        let available = stacks[m.from - 1].len();
+        if m.count > available {
+            return Err(anyhow!(
+                "Move {} ({}): stack {} has {} crates but {} were requested",
+                i + 1,
+                m,
+                m.from,
+                available,
+                m.count
+            ));
+        }
+
+        let (from, to) = two_mut(&mut stacks, m.from - 1, m.to - 1);
+        let remaining = from.len() - m.count;
         if preserve_order {
-            to.extend(moved);
+            to.extend_from_slice(&from[remaining..]);
         } else {
-            to.extend(moved.rev())
+            to.extend(from[remaining..].iter().rev());
         }
+        from.truncate(remaining);
 
-        stacks[m.from - 1] = from;
-        stacks[m.to - 1] = to;
+        after_move(i, m, &stacks);
     }
 
+    Ok(stacks)
+}
+
+/// The crates on top of each stack.
+fn tops(stacks: &[Vec<char>]) -> String {
+    // An empty stack has nothing on top, so leave a gap for it.
     stacks
         .iter()
-        .map(|stack| stack.last().unwrap())
-        .collect::<String>()
+        .map(|stack| stack.last().copied().unwrap_or(' '))
+        .collect()
+}
+
+/// Generates nine stacks and `num_moves` pseudo-random moves which are valid
+/// for both parts, for `--generate` and benchmarking.
+fn generate(num_moves: usize) -> Stacks<'static> {
+    let mut rng = Rng::default();
+    let mut next = |n| rng.below(n);
+
+    let stacks = (0..9)
+        .map(|_| (0..50).map(|_| (b'A' + next(26) as u8) as char).collect())
+        .collect::<Vec<Vec<_>>>();
+
+    // Both parts move the same number of crates between the same stacks, so
+    // tracking the heights is enough to keep every move valid.
+    let mut heights = stacks.iter().map(Vec::len).collect::<Vec<_>>();
+    let moves = (0..num_moves)
+        .map(|_| {
+            let from = loop {
+                let from = next(heights.len());
+                if heights[from] > 0 {
+                    break from;
+                }
+            };
+            let to = (from + 1 + next(heights.len() - 1)) % heights.len();
+            let count = 1 + next(heights[from].min(20));
+            heights[from] -= count;
+            heights[to] += count;
+
+            Move {
+                count,
+                from: from + 1,
+                to: to + 1,
+            }
+        })
+        .collect();
+
+    Stacks {
+        names: vec!["1", "2", "3", "4", "5", "6", "7", "8", "9"],
+        stacks,
+        moves,
+    }
+}
+
+/// Checks that every move is between two different stacks which exist, and
+/// that the source stack has enough crates to move.
+fn validate(stacks: &[Vec<char>], moves: &[Move]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut heights = stacks.iter().map(Vec::len).collect::<Vec<_>>();
+    for (i, m) in moves.iter().enumerate() {
+        let missing = [m.from, m.to]
+            .into_iter()
+            .filter(|&stack| stack == 0 || stack > heights.len())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            for stack in missing {
+                warnings.push(format!(
+                    "Move {} ({}): stack {} does not exist, there are {} stacks",
+                    i + 1,
+                    m,
+                    stack,
+                    heights.len()
+                ));
+            }
+            continue;
+        }
+
+        if m.from == m.to {
+            warnings.push(format!("Move {} ({}): moves to the same stack", i + 1, m));
+        }
+        if m.count == 0 {
+            warnings.push(format!("Move {} ({}): moves no crates", i + 1, m));
+        }
+        let available = heights[m.from - 1];
+        if m.count > available {
+            warnings.push(format!(
+                "Move {} ({}): stack {} only has {} crates",
+                i + 1,
+                m,
+                m.from,
+                available
+            ));
+        }
+        let moved = m.count.min(available);
+        heights[m.from - 1] -= moved;
+        heights[m.to - 1] += moved;
+    }
+    warnings
 }
 
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input05.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input05.txt".to_string();
+    let mut visualize = None;
+    let mut delay = None;
+    let mut final_format = None;
+    let mut generate_moves = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--visualize" => match args.next().context("--visualize requires a part")?.as_str() {
+                "a" => visualize = Some(false),
+                "b" => visualize = Some(true),
+                part => return Err(anyhow!("Unknown part {:?} (expected a or b)", part)),
+            },
+            "--final" => match args.next().context("--final requires a format")?.as_str() {
+                "text" => final_format = Some(FinalFormat::Text),
+                "json" => final_format = Some(FinalFormat::Json),
+                format => {
+                    return Err(anyhow!(
+                        "Unknown format {:?} (expected text or json)",
+                        format
+                    ))
+                }
+            },
+            "--generate" => {
+                generate_moves = Some(
+                    args.next()
+                        .context("--generate requires a value")?
+                        .parse()?,
+                )
+            }
+            "--delay" => {
+                let ms = args.next().context("--delay requires a value")?.parse()?;
+                delay = Some(Duration::from_millis(ms));
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    let input;
+    let parsed = match generate_moves {
+        Some(num_moves) => generate(num_moves),
+        None => {
+            input = std::fs::read_to_string(&input_path)
+                .with_context(|| format!("Error reading {:?}", input_path))?;
+            timing::time(Stage::Parse, || parse_all(parse_input, &input))?
+        }
+    };
 
-    let (stacks, moves) = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+    if dump_parsed {
+        return Ok(print_json(&parsed)?);
+    }
+    let Stacks {
+        names,
+        stacks,
+        moves,
+    } = parsed;
+
+    if validate_only {
+        report_warnings(5, &validate(&stacks, &moves));
+        return Ok(());
+    }
 
-    let result_a = move_crates(&stacks, &moves, false);
-    println!("Day 5, part A: {}", result_a);
+    if let Some(preserve_order) = visualize {
+        let show = |title: &str, stacks: &[Vec<char>]| match delay {
+            Some(delay) => {
+                // Clear the terminal before drawing.
+                print!("\x1b[2J\x1b[H{}\n{}", title, render_stacks(&names, stacks));
+                std::thread::sleep(delay);
+            }
+            None => println!("{}\n{}", title, render_stacks(&names, stacks)),
+        };
+
+        show("Initial stacks:", &stacks);
+        move_crates(stacks.clone(), &moves, preserve_order, |i, m, stacks| {
+            show(&format!("Move {}: {}", i + 1, m), stacks)
+        })?;
+    }
+
+    let final_a = timing::time(Stage::PartA, || {
+        move_crates(stacks.clone(), &moves, false, |_, _, _| ())
+    })?;
+    let final_b = timing::time(Stage::PartB, || {
+        move_crates(stacks, &moves, true, |_, _, _| ())
+    })?;
+
+    match final_format {
+        Some(FinalFormat::Text) => {
+            println!("Part A final stacks:\n{}", render_stacks(&names, &final_a));
+            println!("Part B final stacks:\n{}", render_stacks(&names, &final_b));
+        }
+        Some(FinalFormat::Json) => {
+            // Each stack is listed from bottom to top.
+            let to_strings = |stacks: &[Vec<char>]| {
+                stacks
+                    .iter()
+                    .map(|stack| stack.iter().collect::<String>())
+                    .collect::<Vec<_>>()
+            };
+            let stacks = FinalStacks {
+                part_a: to_strings(&final_a),
+                part_b: to_strings(&final_b),
+            };
+            return Ok(print_json(&stacks)?);
+        }
+        None => (),
+    }
 
-    let result_b = move_crates(&stacks, &moves, true);
-    println!("Day 5, part B: {}", result_b);
+    println!("Day 5, part A: {}", tops(&final_a));
+    println!("Day 5, part B: {}", tops(&final_b));
 
     Ok(())
 }
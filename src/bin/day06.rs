@@ -1,29 +1,267 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
 use itertools::Itertools;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Bytes, Read, Write};
 
-fn find_start_marker(input: &[char], marker_length: usize) -> Result<usize> {
-    let position = input
-        .windows(marker_length)
-        .position(|chars| chars.iter().duplicates().next().is_none())
-        .context(format!(
-            "Cannot find {} unique consecutive characters",
-            marker_length
-        ))?;
+/// The most recent `length` bytes of the stream, along with how many times
+/// each byte occurs in them and how many bytes occur more than once.
+struct Window {
+    length: usize,
+    recent: VecDeque<u8>,
+    counts: [u32; 256],
+    duplicates: usize,
+}
+
+impl Window {
+    fn new(length: usize) -> Self {
+        Window {
+            length,
+            recent: VecDeque::with_capacity(length),
+            counts: [0; 256],
+            duplicates: 0,
+        }
+    }
+
+    /// Adds `byte` to the window, returning whether the window is now full of
+    /// unique bytes.
+    fn push(&mut self, byte: u8) -> bool {
+        if self.recent.len() == self.length {
+            let oldest = self.recent.pop_front().unwrap();
+            self.counts[oldest as usize] -= 1;
+            if self.counts[oldest as usize] == 1 {
+                self.duplicates -= 1;
+            }
+        }
+
+        self.recent.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.duplicates += 1;
+        }
+
+        self.recent.len() == self.length && self.duplicates == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Marker {
+    length: usize,
+    /// The number of bytes read up to and including the end of the marker.
+    position: usize,
+}
+
+/// Scans a datastream a byte at a time, yielding every marker of each length
+/// in order of position. The stream ends at the first newline or EOF.
+struct Markers<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    windows: Vec<Window>,
+    position: usize,
+    pending: VecDeque<Marker>,
+    finished: bool,
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(marker) = self.pending.pop_front() {
+                return Some(Ok(marker));
+            }
+            if self.finished {
+                return None;
+            }
+
+            match self.bytes.next() {
+                None | Some(Ok(b'\n' | b'\r')) => self.finished = true,
+                Some(Err(e)) => {
+                    self.finished = true;
+                    return Some(Err(e.into()));
+                }
+                Some(Ok(byte)) => {
+                    self.position += 1;
+                    for window in &mut self.windows {
+                        if window.push(byte) {
+                            self.pending.push_back(Marker {
+                                length: window.length,
+                                position: self.position,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn markers<R: Read>(reader: R, lengths: &[usize]) -> Markers<R> {
+    Markers {
+        bytes: BufReader::new(reader).bytes(),
+        windows: lengths.iter().map(|&length| Window::new(length)).collect(),
+        position: 0,
+        pending: VecDeque::new(),
+        finished: false,
+    }
+}
+
+/// Finds the first marker of each length, stopping as soon as all are found.
+fn find_start_markers<R: Read>(reader: R, lengths: &[usize]) -> Result<Vec<usize>> {
+    let mut found = vec![None; lengths.len()];
+    for marker in markers(reader, lengths) {
+        let marker = marker?;
+        let i = lengths
+            .iter()
+            .position(|&length| length == marker.length)
+            .unwrap();
+        found[i].get_or_insert(marker.position);
+        if found.iter().all(Option::is_some) {
+            break;
+        }
+    }
+
+    lengths
+        .iter()
+        .zip(found)
+        .map(|(length, position)| {
+            position.context(format!(
+                "Cannot find {} unique consecutive characters",
+                length
+            ))
+        })
+        .collect()
+}
+
+/// Checks that the datastream is a single line of lowercase letters.
+fn validate(input: &[u8]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut lines = input.split(|&byte| byte == b'\n');
+    let stream = lines.next().unwrap_or_default();
+    let stream = stream.strip_suffix(b"\r").unwrap_or(stream);
 
-    Ok(position + marker_length)
+    if stream.is_empty() {
+        warnings.push("The datastream is empty".to_string());
+    }
+    if let Some(i) = stream.iter().position(|byte| !byte.is_ascii_lowercase()) {
+        warnings.push(format!(
+            "Character {} is {:?}, expected a lowercase letter",
+            i + 1,
+            stream[i] as char
+        ));
+    }
+    if lines.any(|line| line.iter().any(|byte| !byte.is_ascii_whitespace())) {
+        warnings.push("Only the first line is read, the rest is ignored".to_string());
+    }
+    warnings
 }
 
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input06.txt")?;
-    let input_chars = input.trim_end().chars().collect::<Vec<char>>();
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut lengths = None;
+    let mut all = false;
+    let mut lines = false;
+    let mut input_path = "res/input06.txt".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lengths" => {
+                lengths = Some(
+                    args.next()
+                        .context("--lengths requires a value")?
+                        .split(',')
+                        .map(|length| length.parse::<usize>())
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into_iter()
+                        .unique()
+                        .collect(),
+                );
+                if lengths.iter().flatten().any(|&length| length == 0) {
+                    return Err(anyhow!("Marker lengths must be at least 1"));
+                }
+            }
+            "--all" => all = true,
+            "--lines" => lines = true,
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    // Read from stdin when the path is "-", so generated streams can be piped
+    // through without storing them.
+    let mut reader: Box<dyn Read> = if input_path == "-" {
+        Box::new(std::io::stdin())
+    } else {
+        Box::new(File::open(&input_path).with_context(|| format!("Cannot open {}", input_path))?)
+    };
+
+    // There's nothing to parse besides the datastream itself.
+    if dump_parsed {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        return Ok(print_json(input.trim_end())?);
+    }
+
+    if validate_only {
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input)?;
+        report_warnings(6, &validate(&input));
+        return Ok(());
+    }
+
+    if lines {
+        // Each line is an independent datastream, as in the puzzle examples.
+        let lengths = lengths.unwrap_or_else(|| vec![4, 14]);
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let positions = find_start_markers(line?.as_bytes(), &lengths)
+                .with_context(|| format!("Line {}", i + 1))?;
+            println!("Line {}: {}", i + 1, positions.iter().join(", "));
+        }
+        return Ok(());
+    }
+
+    if all || lengths.is_some() {
+        let lengths = lengths.unwrap_or_else(|| vec![4, 14]);
+        if all {
+            // Print markers as they're found rather than collecting them, as
+            // there may be a great many in a long stream.
+            let mut out = BufWriter::new(std::io::stdout().lock());
+            for marker in markers(reader, &lengths) {
+                let marker = marker?;
+                writeln!(
+                    out,
+                    "Marker of length {}: {}",
+                    marker.length, marker.position
+                )?;
+            }
+        } else {
+            for (length, position) in lengths.iter().zip(find_start_markers(reader, &lengths)?) {
+                println!("Marker of length {}: {}", length, position);
+            }
+        }
+        return Ok(());
+    }
+
+    // The puzzle input is small, so is read up front to time each part
+    // separately. The modes above stream instead.
+    let input = timing::time(Stage::Parse, || -> Result<_> {
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input)?;
+        Ok(input)
+    })?;
 
-    let result_a =
-        find_start_marker(&input_chars, 4).context("Cannot find start-of-packet marker")?;
-    println!("Day 6, part A: {}", result_a);
+    let result_a = timing::time(Stage::PartA, || find_start_markers(&input[..], &[4]))
+        .context("Cannot find a start-of-packet marker")?;
+    println!("Day 6, part A: {}", result_a[0]);
 
-    let result_b =
-        find_start_marker(&input_chars, 14).context("Cannot find start-of-message marker")?;
-    println!("Day 6, part B: {}", result_b);
+    let result_b = timing::time(Stage::PartB, || find_start_markers(&input[..], &[14]))
+        .context("Cannot find a start-of-message marker")?;
+    println!("Day 6, part B: {}", result_b[0]);
 
     Ok(())
 }
@@ -1,111 +1,486 @@
-use anyhow::{Context, Result};
-use std::cell::Cell;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_till1},
+    character::complete::{char, digit1},
+    combinator::{all_consuming, map, map_res},
+    sequence::{preceded, separated_pair},
+    IResult,
+};
 
-type FileSystem = HashMap<PathBuf, Dir>;
+type DirId = usize;
 
+/// A directory, whose name and files' names are borrowed from the input.
 #[derive(Debug)]
-struct Dir {
-    path: PathBuf,
-    subdirs: Vec<String>,
-    immediate_size: u32,
-    cached_recursive_size: Cell<Option<u32>>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Dir<'a> {
+    name: &'a str,
+    parent: Option<DirId>,
+    subdirs: Vec<DirId>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    files: Vec<(&'a str, u64)>,
+    immediate_size: u64,
+    listed: bool,
 }
 
-impl Dir {
-    fn new(path: PathBuf) -> Self {
+impl<'a> Dir<'a> {
+    fn new(name: &'a str, parent: Option<DirId>) -> Self {
         Dir {
-            path,
+            name,
+            parent,
             subdirs: Vec::new(),
+            files: Vec::new(),
             immediate_size: 0,
-            cached_recursive_size: Cell::new(None),
+            listed: false,
         }
     }
+}
 
-    fn size(&self, fs: &FileSystem) -> u32 {
-        self.cached_recursive_size.get().unwrap_or_else(|| {
-            let size = self.immediate_size
-                + self
-                    .subdirs
-                    .iter()
-                    .map(|name| fs.get(&self.path.join(name)).unwrap().size(fs))
-                    .sum::<u32>();
-            self.cached_recursive_size.set(Some(size));
-            size
-        })
+/// Directories stored in an arena, indexed by `DirId`. A directory is always
+/// added after its parent, so walking the arena backwards visits children
+/// before their parents.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct FileSystem<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    dirs: Vec<Dir<'a>>,
+    sizes: Vec<u64>,
+}
+
+impl<'a> FileSystem<'a> {
+    const ROOT: DirId = 0;
+
+    fn new() -> Self {
+        FileSystem {
+            dirs: vec![Dir::new("/", None)],
+            sizes: Vec::new(),
+        }
+    }
+
+    fn add_dir(&mut self, parent: DirId, name: &'a str) -> DirId {
+        let id = self.dirs.len();
+        self.dirs.push(Dir::new(name, Some(parent)));
+        self.dirs[parent].subdirs.push(id);
+        id
+    }
+
+    fn subdir(&self, dir: DirId, name: &str) -> Option<DirId> {
+        self.dirs[dir]
+            .subdirs
+            .iter()
+            .copied()
+            .find(|&subdir| self.dirs[subdir].name == name)
+    }
+
+    fn compute_sizes(&mut self) {
+        self.sizes = self.dirs.iter().map(|dir| dir.immediate_size).collect();
+        for id in (1..self.dirs.len()).rev() {
+            let parent = self.dirs[id].parent.unwrap();
+            self.sizes[parent] += self.sizes[id];
+        }
+    }
+
+    fn size(&self, dir: DirId) -> u64 {
+        self.sizes[dir]
+    }
+
+    fn ids(&self) -> impl Iterator<Item = DirId> {
+        0..self.dirs.len()
+    }
+
+    /// Directories whose recursive size satisfies `predicate`.
+    fn find<'b>(
+        &'b self,
+        predicate: impl Fn(u64) -> bool + 'b,
+    ) -> impl Iterator<Item = DirId> + 'b {
+        self.ids().filter(move |&id| predicate(self.size(id)))
+    }
+
+    /// Directories whose path matches `pattern`, where `*` matches within a
+    /// path component, `**` matches across components and `?` matches one
+    /// character.
+    fn find_glob<'b>(&'b self, pattern: &'b str) -> impl Iterator<Item = DirId> + 'b {
+        self.ids()
+            .filter(move |&id| glob_match(pattern.as_bytes(), self.path(id).as_bytes()))
+    }
+
+    fn total_size(&self, dirs: impl IntoIterator<Item = DirId>) -> u64 {
+        dirs.into_iter().map(|id| self.size(id)).sum()
+    }
+
+    fn smallest(&self, dirs: impl IntoIterator<Item = DirId>) -> Option<DirId> {
+        dirs.into_iter().min_by_key(|&id| self.size(id))
     }
+
+    /// The `n` largest directories, largest first, with ties in path order.
+    fn largest(&self, n: usize) -> Vec<DirId> {
+        let mut dirs = self.ids().map(|id| (id, self.path(id))).collect::<Vec<_>>();
+        dirs.sort_by(|a, b| {
+            self.size(b.0)
+                .cmp(&self.size(a.0))
+                .then_with(|| a.1.cmp(&b.1))
+        });
+        dirs.into_iter().take(n).map(|(id, _)| id).collect()
+    }
+
+    fn path(&self, dir: DirId) -> String {
+        let mut names = Vec::new();
+        let mut current = dir;
+        while let Some(parent) = self.dirs[current].parent {
+            names.push(self.dirs[current].name);
+            current = parent;
+        }
+
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_match(rest, &text[i..])),
+        [b'?', rest @ ..] => matches!(text, [c, tail @ ..] if *c != b'/' && glob_match(rest, tail)),
+        [p, rest @ ..] => matches!(text, [c, tail @ ..] if c == p && glob_match(rest, tail)),
+    }
+}
+
+/// A single line of the terminal transcript.
+#[derive(Debug, PartialEq, Eq)]
+enum Line<'a> {
+    Cd(&'a str),
+    Ls,
+    Dir(&'a str),
+    File(u64, &'a str),
 }
 
-fn parse_input(input: &str) -> Result<FileSystem> {
-    let mut current_path = PathBuf::new();
+/// Names run to the end of the line, so may contain spaces.
+fn parse_name(input: &str) -> IResult<&str, &str> {
+    take_till1(|c: char| c == '\r' || c == '\n')(input)
+}
+
+fn parse_line(input: &str) -> IResult<&str, Line<'_>> {
+    alt((
+        map(preceded(tag("$ cd "), parse_name), Line::Cd),
+        map(tag("$ ls"), |_| Line::Ls),
+        map(preceded(tag("dir "), parse_name), Line::Dir),
+        map(
+            separated_pair(map_res(digit1, str::parse), char(' '), parse_name),
+            |(size, name)| Line::File(size, name),
+        ),
+    ))(input)
+}
+
+/// Explains why `line` didn't match any of the forms `parse_line` accepts.
+fn describe_error(line: &str) -> String {
+    match line.strip_prefix("$ ") {
+        Some(command) => format!("unknown command {:?}", command),
+        None => match line.split_once(' ') {
+            Some(("dir", _)) => format!("expected a dir name, got {:?}", line),
+            Some((size, _)) if size.parse::<u64>().is_err() => {
+                format!("expected file size, got {:?}", size)
+            }
+            _ => format!("unexpected ls output {:?}", line),
+        },
+    }
+}
+
+fn parse_input(input: &str) -> Result<FileSystem<'_>> {
+    let mut current = FileSystem::ROOT;
     let mut is_ls_running = false;
 
     let mut fs = FileSystem::new();
-    fs.insert(PathBuf::from("/"), Dir::new(PathBuf::from("/")));
 
-    for line in input.lines() {
-        if line.starts_with('$') {
-            is_ls_running = false;
+    for (line_no, line) in input.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        let (_, parsed) = all_consuming(parse_line)(line)
+            .map_err(|_| anyhow!("line {}: {}", line_no, describe_error(line)))?;
 
-            if let Some((_, dir)) = line.split_once("$ cd ") {
-                if dir.starts_with('/') {
-                    current_path = PathBuf::from(dir);
-                } else if dir == ".." {
-                    current_path.pop();
-                } else {
-                    current_path.push(dir);
+        match parsed {
+            Line::Cd(dir) => {
+                is_ls_running = false;
+
+                let (start, relative) = match dir.strip_prefix('/') {
+                    Some(relative) => (FileSystem::ROOT, relative),
+                    None => (current, dir),
+                };
+                current = start;
+                for name in relative.split('/').filter(|name| !name.is_empty()) {
+                    current = if name == ".." {
+                        fs.dirs[current].parent.with_context(|| {
+                            format!("line {}: cd .. from {:?}", line_no, fs.path(current))
+                        })?
+                    } else {
+                        fs.subdir(current, name).with_context(|| {
+                            format!(
+                                "line {}: {:?} is not a known dir in {:?}",
+                                line_no,
+                                name,
+                                fs.path(current)
+                            )
+                        })?
+                    };
                 }
-            } else if line == "$ ls" {
+            }
+            Line::Ls => {
+                if fs.dirs[current].listed {
+                    return Err(anyhow!(
+                        "line {}: {:?} was already listed",
+                        line_no,
+                        fs.path(current)
+                    ));
+                }
+                fs.dirs[current].listed = true;
                 is_ls_running = true;
             }
-        } else if is_ls_running {
-            let current_dir = fs
-                .get_mut(&current_path)
-                .with_context(|| format!("{:?} is not a known dir", current_path))?;
-
-            let (node_type, name) = line
-                .split_once(' ')
-                .with_context(|| format!("Unexpected ls output: {}", line))?;
-
-            if node_type == "dir" {
-                current_dir.subdirs.push(name.to_string());
-                let new_path = current_path.join(name);
-                fs.entry(new_path.clone())
-                    .or_insert_with(|| Dir::new(new_path));
-            } else {
-                let size = node_type
-                    .parse::<u32>()
-                    .with_context(|| format!("Expected file size, got {}", node_type))?;
-                current_dir.immediate_size += size;
-            };
+            Line::Dir(name) | Line::File(_, name) => {
+                if !is_ls_running {
+                    return Err(anyhow!("line {}: output outside of ls: {}", line_no, line));
+                }
+
+                if fs.subdir(current, name).is_some()
+                    || fs.dirs[current].files.iter().any(|(file, _)| *file == name)
+                {
+                    return Err(anyhow!(
+                        "line {}: {:?} listed twice in {:?}",
+                        line_no,
+                        name,
+                        fs.path(current)
+                    ));
+                }
+
+                if let Line::File(size, _) = parsed {
+                    let current_dir = &mut fs.dirs[current];
+                    current_dir.files.push((name, size));
+                    current_dir.immediate_size += size;
+                } else {
+                    fs.add_dir(current, name);
+                }
+            }
         }
     }
 
+    fs.compute_sizes();
     Ok(fs)
 }
 
+/// Sums the recursive sizes of every directory no larger than `threshold`.
+fn part_a(fs: &FileSystem, threshold: u64) -> u64 {
+    fs.total_size(fs.find(|size| size <= threshold))
+}
+
+/// Finds the smallest directory whose deletion leaves at least `required_free`
+/// space on a disk of size `capacity`.
+fn part_b(fs: &FileSystem, capacity: u64, required_free: u64) -> Result<u64> {
+    let used = fs.size(FileSystem::ROOT);
+    let free = capacity
+        .checked_sub(used)
+        .with_context(|| format!("{} used exceeds the capacity of {}", used, capacity))?;
+    let required = required_free
+        .checked_sub(free)
+        .filter(|required| *required > 0)
+        .with_context(|| {
+            format!(
+                "No deletion needed: {} free of {} required",
+                free, required_free
+            )
+        })?;
+
+    fs.smallest(fs.find(|size| size > required))
+        .map(|id| fs.size(id))
+        .context("Cannot find any directories of required size")
+}
+
+/// Renders the filesystem in the indented style of the puzzle description,
+/// with each directory annotated with its recursive size.
+fn render_tree(fs: &FileSystem) -> String {
+    enum Entry {
+        Dir(DirId),
+        File(u64),
+    }
+
+    fn render_dir(fs: &FileSystem, id: DirId, depth: usize, out: &mut String) {
+        let dir = &fs.dirs[id];
+        out.push_str(&format!(
+            "{}- {} (dir, size={})\n",
+            "  ".repeat(depth),
+            dir.name,
+            fs.size(id)
+        ));
+
+        let mut entries = dir
+            .subdirs
+            .iter()
+            .map(|&subdir| (fs.dirs[subdir].name, Entry::Dir(subdir)))
+            .chain(
+                dir.files
+                    .iter()
+                    .map(|(name, size)| (*name, Entry::File(*size))),
+            )
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(name, _)| *name);
+
+        for (name, entry) in entries {
+            match entry {
+                Entry::File(size) => out.push_str(&format!(
+                    "{}- {} (file, size={})\n",
+                    "  ".repeat(depth + 1),
+                    name,
+                    size
+                )),
+                Entry::Dir(subdir) => render_dir(fs, subdir, depth + 1, out),
+            }
+        }
+    }
+
+    let mut out = String::new();
+    render_dir(fs, FileSystem::ROOT, 0, &mut out);
+    out
+}
+
+/// Formats a size like `du -h`, in powers of 1024 with one decimal place
+/// below 10.
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+
+    if size < 1024 {
+        return size.to_string();
+    }
+
+    let mut value = size as f64;
+    let mut unit = "";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+
+    if value < 10.0 {
+        format!("{:.1}{}", value, unit)
+    } else {
+        format!("{:.0}{}", value, unit)
+    }
+}
+
+/// Lists directories with their recursive sizes, one per line.
+fn du(fs: &FileSystem, dirs: impl IntoIterator<Item = DirId>) -> String {
+    dirs.into_iter()
+        .map(|id| format!("{}\t{}\n", human_size(fs.size(id)), fs.path(id)))
+        .collect()
+}
+
+/// Checks that every directory is listed, as otherwise its size is unknown.
+fn validate(fs: &FileSystem) -> Vec<String> {
+    fs.ids()
+        .filter(|&dir| !fs.dirs[dir].listed)
+        .map(|dir| {
+            format!(
+                "Directory {} is never listed, so its size is unknown",
+                fs.path(dir)
+            )
+        })
+        .collect()
+}
+
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input07.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input07.txt".to_string();
+    let mut tree = false;
+    let mut du_output = false;
+    let mut largest = None;
+    let mut find = None;
+    let mut threshold = 100000;
+    let mut capacity = 70000000;
+    let mut required_free = 30000000;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tree" => tree = true,
+            "--du" => du_output = true,
+            "--largest" => {
+                largest = Some(args.next().context("--largest requires a value")?.parse()?)
+            }
+            "--find" => find = Some(args.next().context("--find requires a value")?),
+            "--threshold" => {
+                threshold = args
+                    .next()
+                    .context("--threshold requires a value")?
+                    .parse()?
+            }
+            "--capacity" => {
+                capacity = args
+                    .next()
+                    .context("--capacity requires a value")?
+                    .parse()?
+            }
+            "--required" => {
+                required_free = args
+                    .next()
+                    .context("--required requires a value")?
+                    .parse()?
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
 
-    let fs = parse_input(&input).context("Error parsing input")?;
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
 
-    let result_a = fs
-        .values()
-        .map(|dir| dir.size(&fs))
-        .filter(|size| *size <= 100000)
-        .sum::<u32>();
-    println!("Day 7, part A: {}", result_a);
+    let fs = timing::time(Stage::Parse, || parse_input(&input)).context("Error parsing input")?;
 
-    let required = 30000000 - (70000000 - fs[&PathBuf::from("/")].size(&fs));
+    if dump_parsed {
+        return Ok(print_json(&fs)?);
+    }
 
-    let result_b = fs
-        .values()
-        .map(|dir| dir.size(&fs))
-        .filter(|size| *size > required)
-        .min()
-        .context("Cannot find any directories of required size")?;
+    if validate_only {
+        report_warnings(7, &validate(&fs));
+        return Ok(());
+    }
+
+    if tree {
+        print!("{}", render_tree(&fs));
+    }
+    if du_output {
+        print!("{}", du(&fs, fs.largest(usize::MAX)));
+    }
+    if let Some(n) = largest {
+        print!("{}", du(&fs, fs.largest(n)));
+    }
+    if let Some(pattern) = &find {
+        let dirs = fs.find_glob(pattern).collect::<Vec<_>>();
+        print!("{}", du(&fs, dirs.iter().copied()));
+        println!("{}\ttotal", human_size(fs.total_size(dirs)));
+    }
+
+    let result_a = timing::time(Stage::PartA, || part_a(&fs, threshold));
+    println!("Day 7, part A: {}", result_a);
+
+    let result_b = timing::time(Stage::PartB, || part_b(&fs, capacity, required_free))?;
     println!("Day 7, part B: {}", result_b);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_may_contain_spaces() {
+        let parse = |line| all_consuming(parse_line)(line).map(|(_, parsed)| parsed);
+        assert_eq!(parse("$ cd my dir"), Ok(Line::Cd("my dir")));
+        assert_eq!(parse("dir a b"), Ok(Line::Dir("a b")));
+        assert_eq!(parse("1234 notes .txt"), Ok(Line::File(1234, "notes .txt")));
+    }
+}
@@ -1,99 +1,67 @@
 use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::grid::{Grid, Position, Ray};
+use aoc2022::rng::Rng;
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::variants;
 use itertools::{iproduct, Itertools};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{cmp::Reverse, str::FromStr};
 use take_until::TakeUntilExt;
 
-#[derive(Debug)]
-struct Trees {
-    width: usize,
-    length: usize,
-    trees: Vec<u32>,
-}
+type Trees = Grid<u32>;
 
-struct TreeIter<'a> {
-    trees: &'a Trees,
-    pos: (isize, isize),
-    step: (isize, isize),
-}
+/// A direction to look in, as the step between one position and the next.
+type Step = (isize, isize);
 
-impl<'a> TreeIter<'a> {
-    fn new(trees: &'a Trees, pos: (usize, usize), step: (isize, isize)) -> Self {
-        assert!(pos.0 < trees.width, "invalid x: {}", pos.0);
-        assert!(pos.1 < trees.length, "invalid y: {}", pos.1);
-        assert!(step.0 != 0 || step.1 != 0);
+/// Up, left, right, and down.
+const ORTHOGONAL: [Step; 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
 
-        TreeIter {
-            trees,
-            pos: (pos.0 as isize, pos.1 as isize),
-            step,
-        }
-    }
-}
-
-impl<'a> Iterator for TreeIter<'a> {
-    type Item = &'a u32;
+/// The orthogonal directions followed by the diagonals.
+const ALL_DIRECTIONS: [Step; 8] = [
+    (0, -1),
+    (-1, 0),
+    (1, 0),
+    (0, 1),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.pos.0 += self.step.0;
-        self.pos.1 += self.step.1;
-        if self.pos.0 >= 0
-            && self.pos.1 >= 0
-            && self.pos.0 < self.trees.width as isize
-            && self.pos.1 < self.trees.length as isize
-        {
-            Some(
-                &self.trees.trees[self
-                    .trees
-                    .tree_idx(self.pos.0 as usize, self.pos.1 as usize)],
-            )
-        } else {
-            None
-        }
+fn direction_name(step: Step) -> &'static str {
+    match step {
+        (0, -1) => "up",
+        (-1, 0) => "left",
+        (1, 0) => "right",
+        (0, 1) => "down",
+        (-1, -1) => "up-left",
+        (1, -1) => "up-right",
+        (-1, 1) => "down-left",
+        (1, 1) => "down-right",
+        _ => "elsewhere",
     }
 }
 
-impl Trees {
-    fn tree_idx(&self, x: usize, y: usize) -> usize {
-        assert!(x < self.width, "invalid x: {}", x);
-        assert!(y < self.length, "invalid y: {}", y);
-        y * self.width + x
-    }
-
-    fn height_at(&self, x: usize, y: usize) -> u32 {
-        self.trees[self.tree_idx(x, y)]
-    }
-
-    fn to_left(&self, x: usize, y: usize) -> TreeIter {
-        TreeIter::new(self, (x, y), (-1, 0))
-    }
-
-    fn to_right(&self, x: usize, y: usize) -> TreeIter {
-        TreeIter::new(self, (x, y), (1, 0))
-    }
-
-    fn above(&self, x: usize, y: usize) -> TreeIter {
-        TreeIter::new(self, (x, y), (0, -1))
-    }
-
-    fn below(&self, x: usize, y: usize) -> TreeIter {
-        TreeIter::new(self, (x, y), (0, 1))
-    }
-
-    fn all_dirs(&self, x: usize, y: usize) -> [TreeIter; 4] {
-        [
-            self.above(x, y),
-            self.to_left(x, y),
-            self.to_right(x, y),
-            self.below(x, y),
-        ]
-    }
+/// Lines of sight from a tree in each of `dirs`.
+fn all_dirs<'a>(
+    trees: &'a Trees,
+    x: usize,
+    y: usize,
+    dirs: &'a [Step],
+) -> impl Iterator<Item = Ray<'a, u32>> {
+    let pos = Position { x, y };
+    dirs.iter().map(move |&step| trees.ray(pos, step))
 }
 
 fn parse_input(input: &str) -> Result<Trees> {
+    let input = input.trim_end();
     let width = input.lines().next().context("Empty input")?.len();
     let length = input.lines().count();
 
     let trees = input
-        .trim_end()
         .lines()
         .enumerate()
         .map(|(i, l)| {
@@ -117,41 +85,619 @@ fn parse_input(input: &str) -> Result<Trees> {
         })
         .collect::<Result<Vec<u32>>>()?;
 
-    Ok(Trees {
-        width,
-        length,
-        trees,
-    })
+    Ok(Trees::new(width, length, trees)?)
 }
 
-fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input08.txt")?;
+/// Whether a tree is visible from outside the forest, found by looking in
+/// each direction from it.
+fn is_visible(trees: &Trees, x: usize, y: usize, dirs: &[Step]) -> bool {
+    let height = trees[Position { x, y }];
+    all_dirs(trees, x, y, dirs).any(|mut dir| dir.all(|(_, other)| *other < height))
+}
+
+/// How far can be seen from a tree in each direction.
+fn viewing_distances<'a>(
+    trees: &'a Trees,
+    x: usize,
+    y: usize,
+    dirs: &'a [Step],
+) -> impl Iterator<Item = usize> + 'a {
+    let height = trees[Position { x, y }];
+    all_dirs(trees, x, y, dirs)
+        .map(move |dir| dir.take_until(|(_, other)| **other >= height).count())
+}
+
+/// A tree's scenic score, found by looking in each direction from it.
+fn scenic_score(trees: &Trees, x: usize, y: usize, dirs: &[Step]) -> usize {
+    viewing_distances(trees, x, y, dirs).product()
+}
+
+fn part_a_scan(trees: &Trees, dirs: &[Step]) -> usize {
+    iproduct!(0..trees.width(), 0..trees.height())
+        .filter(|&(x, y)| is_visible(trees, x, y, dirs))
+        .count()
+}
 
-    let trees = parse_input(&input).context("Error parsing input")?;
+fn part_b_scan(trees: &Trees, dirs: &[Step]) -> usize {
+    iproduct!(0..trees.width(), 0..trees.height())
+        .map(|(x, y)| scenic_score(trees, x, y, dirs))
+        .max()
+        .unwrap_or(0)
+}
 
-    let result_a = iproduct!(0..trees.width, 0..trees.length)
-        .filter(|(x, y)| {
-            let height = trees.height_at(*x, *y);
-            trees
-                .all_dirs(*x, *y)
-                .iter_mut()
-                .any(|dir| dir.all(|other| *other < height))
+/// As `part_a_scan`, but scanning rows in parallel.
+#[cfg(feature = "parallel")]
+fn part_a_parallel(trees: &Trees, dirs: &[Step]) -> usize {
+    (0..trees.height())
+        .into_par_iter()
+        .map(|y| {
+            (0..trees.width())
+                .filter(|&x| is_visible(trees, x, y, dirs))
+                .count()
         })
-        .count();
-    println!("Day 8, part A: {}", result_a);
+        .sum()
+}
 
-    let result_b: usize = iproduct!(0..trees.width, 0..trees.length)
-        .map(|(x, y)| {
-            let height = trees.height_at(x, y);
-            trees
-                .all_dirs(x, y)
-                .iter_mut()
-                .map(|dir| dir.take_until(|other| **other >= height).count())
-                .product()
+/// As `part_b_scan`, but scanning rows in parallel.
+#[cfg(feature = "parallel")]
+fn part_b_parallel(trees: &Trees, dirs: &[Step]) -> usize {
+    (0..trees.height())
+        .into_par_iter()
+        .map(|y| {
+            (0..trees.width())
+                .map(|x| scenic_score(trees, x, y, dirs))
+                .max()
+                .unwrap_or(0)
         })
         .max()
+        .unwrap_or(0)
+}
+
+/// Lines of tree indices for each of `dirs`, such that looking back along a
+/// line from a tree means looking in that direction. Each line starts at the
+/// edge of the forest which that direction faces, and every tree is in
+/// exactly one line per direction.
+fn lines<'a>(trees: &'a Trees, dirs: &'a [Step]) -> impl Iterator<Item = Vec<usize>> + 'a {
+    dirs.iter().flat_map(move |&(dx, dy)| {
+        trees
+            .positions()
+            .filter(move |pos| {
+                let next = (pos.x as isize + dx, pos.y as isize + dy);
+                next.0 < 0
+                    || next.1 < 0
+                    || next.0 >= trees.width() as isize
+                    || next.1 >= trees.height() as isize
+            })
+            .map(move |start| {
+                std::iter::once(trees.idx(start))
+                    .chain(trees.ray(start, (-dx, -dy)).map(|(pos, _)| trees.idx(pos)))
+                    .collect()
+            })
+    })
+}
+
+/// Works out which trees are visible in linear time, returning a flag for each
+/// tree. A tree is visible from one end of a line if it is taller than the
+/// running maximum of the trees before it.
+fn visibility(trees: &Trees, dirs: &[Step]) -> Vec<bool> {
+    let mut visible = vec![false; trees.cells().len()];
+    for line in lines(trees, dirs) {
+        let mut tallest = None;
+        for idx in line {
+            let height = trees.cells()[idx];
+            if tallest.is_none_or(|tallest| height > tallest) {
+                visible[idx] = true;
+                tallest = Some(height);
+            }
+        }
+    }
+    visible
+}
+
+fn part_a_linear(trees: &Trees, dirs: &[Step]) -> usize {
+    visibility(trees, dirs)
+        .iter()
+        .filter(|&&visible| visible)
+        .count()
+}
+
+/// The number of trees whose running maxima are updated at once by
+/// `part_a_simd`. The compiler turns each chunk into vector instructions.
+const LANES: usize = 32;
+
+/// Marks the trees visible from the top of `heights`, given row by row, or
+/// from the bottom if `reverse` is set. Rather than following each column in
+/// turn, this keeps the running maximum of every column and sweeps a whole row
+/// at a time, so that neighbouring trees can be compared together. Heights are
+/// offset by one so that zero means no tree has been seen yet.
+fn sweep(heights: &[u8], width: usize, visible: &mut [u8], reverse: bool) {
+    let mut tallest = vec![0u8; width];
+    let mut sweep_row = |y: usize| {
+        let row = &heights[y * width..(y + 1) * width];
+        let row_visible = &mut visible[y * width..(y + 1) * width];
+
+        let mut chunks = row
+            .chunks_exact(LANES)
+            .zip(row_visible.chunks_exact_mut(LANES))
+            .zip(tallest.chunks_exact_mut(LANES));
+        for ((row, row_visible), tallest) in &mut chunks {
+            for i in 0..LANES {
+                row_visible[i] |= (row[i] > tallest[i]) as u8;
+                tallest[i] = tallest[i].max(row[i]);
+            }
+        }
+
+        let done = width - width % LANES;
+        for x in done..width {
+            row_visible[x] |= (row[x] > tallest[x]) as u8;
+            tallest[x] = tallest[x].max(row[x]);
+        }
+    };
+
+    let length = heights.len().checked_div(width).unwrap_or(0);
+    if reverse {
+        (0..length).rev().for_each(&mut sweep_row);
+    } else {
+        (0..length).for_each(&mut sweep_row);
+    }
+}
+
+fn transpose<T: Copy + Default>(cells: &[T], width: usize, length: usize) -> Vec<T> {
+    let mut transposed = vec![T::default(); cells.len()];
+    for y in 0..length {
+        for x in 0..width {
+            transposed[x * length + y] = cells[y * width + x];
+        }
+    }
+    transposed
+}
+
+/// Counts the visible trees by sweeping whole rows at a time, in each of the
+/// four orthogonal directions. Looking left and right is done by sweeping the
+/// transposed forest. Falls back to `part_a_linear` for other directions, or
+/// if the trees are too tall to fit in a byte.
+fn part_a_simd(trees: &Trees, dirs: &[Step]) -> usize {
+    let heights = trees
+        .cells()
+        .iter()
+        .map(|&height| u8::try_from(height + 1))
+        .collect::<std::result::Result<Vec<u8>, _>>();
+    let heights = match heights {
+        Ok(heights) if dirs == ORTHOGONAL => heights,
+        _ => return part_a_linear(trees, dirs),
+    };
+    let (width, length) = (trees.width(), trees.height());
+
+    let mut visible = vec![0; heights.len()];
+    sweep(&heights, width, &mut visible, false);
+    sweep(&heights, width, &mut visible, true);
+
+    let transposed = transpose(&heights, width, length);
+    let mut transposed_visible = transpose(&visible, width, length);
+    sweep(&transposed, length, &mut transposed_visible, false);
+    sweep(&transposed, length, &mut transposed_visible, true);
+
+    transposed_visible
+        .iter()
+        .filter(|&&visible| visible != 0)
+        .count()
+}
+
+/// The positions of the visible trees, in reading order.
+fn visible_trees(trees: &Trees, dirs: &[Step]) -> Vec<(usize, usize)> {
+    visibility(trees, dirs)
+        .iter()
+        .enumerate()
+        .filter(|(_, &visible)| visible)
+        .map(|(idx, _)| (idx % trees.width(), idx / trees.width()))
+        .collect()
+}
+
+/// The tree with the highest scenic score, and how far can be seen from it.
+#[derive(Debug)]
+struct BestTree {
+    x: usize,
+    y: usize,
+    score: usize,
+    /// The viewing distance in each direction.
+    distances: Vec<(Step, usize)>,
+}
+
+fn best_tree(trees: &Trees, dirs: &[Step]) -> BestTree {
+    let (x, y) = iproduct!(0..trees.width(), 0..trees.height())
+        .max_by_key(|&(x, y)| scenic_score(trees, x, y, dirs))
         .unwrap();
+
+    let distances = dirs
+        .iter()
+        .copied()
+        .zip(viewing_distances(trees, x, y, dirs))
+        .collect::<Vec<_>>();
+
+    BestTree {
+        x,
+        y,
+        score: distances.iter().map(|(_, distance)| distance).product(),
+        distances,
+    }
+}
+
+/// Works out every tree's scenic score in linear time. Walking along a line, a
+/// stack holds the trees which could still block the view back from later
+/// trees: any tree shorter than the current one is hidden behind it, so is
+/// popped. Whatever is left on top is the tree which blocks the view.
+fn scenic_scores(trees: &Trees, dirs: &[Step]) -> Vec<usize> {
+    let mut scores = vec![1; trees.cells().len()];
+    for line in lines(trees, dirs) {
+        let mut stack: Vec<usize> = Vec::new();
+        for (i, &idx) in line.iter().enumerate() {
+            let height = trees.cells()[idx];
+            while stack
+                .last()
+                .is_some_and(|&j| trees.cells()[line[j]] < height)
+            {
+                stack.pop();
+            }
+            scores[idx] *= match stack.last() {
+                Some(&j) => i - j,
+                None => i,
+            };
+            stack.push(i);
+        }
+    }
+    scores
+}
+
+fn part_b_linear(trees: &Trees, dirs: &[Step]) -> usize {
+    // An empty forest, as generated with size 0, has no trees to score.
+    scenic_scores(trees, dirs).into_iter().max().unwrap_or(0)
+}
+
+/// The `k` highest scenic scores with the positions of their trees, highest
+/// first. Ties are broken in reading order.
+fn top_scenic_scores(trees: &Trees, k: usize, dirs: &[Step]) -> Vec<(usize, (usize, usize))> {
+    scenic_scores(trees, dirs)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, score)| (score, (idx % trees.width(), idx / trees.width())))
+        .sorted_by_key(|&(score, (x, y))| (Reverse(score), y, x))
+        .take(k)
+        .collect()
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+struct DirectionAnalysis {
+    direction: &'static str,
+    visible: bool,
+    viewing_distance: usize,
+}
+
+/// Everything worked out about a single tree, for `--dump json`.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+struct TreeAnalysis {
+    x: usize,
+    y: usize,
+    height: u32,
+    visible: bool,
+    scenic_score: usize,
+    directions: Vec<DirectionAnalysis>,
+}
+
+fn analyse(trees: &Trees, dirs: &[Step]) -> Vec<TreeAnalysis> {
+    trees
+        .positions()
+        .map(|pos| {
+            let height = trees[pos];
+            let directions = dirs
+                .iter()
+                .map(|&step| DirectionAnalysis {
+                    direction: direction_name(step),
+                    visible: trees.ray(pos, step).all(|(_, other)| *other < height),
+                    viewing_distance: trees
+                        .ray(pos, step)
+                        .take_until(|(_, other)| **other >= height)
+                        .count(),
+                })
+                .collect::<Vec<_>>();
+
+            TreeAnalysis {
+                x: pos.x,
+                y: pos.y,
+                height,
+                visible: directions.iter().any(|dir| dir.visible),
+                scenic_score: directions.iter().map(|dir| dir.viewing_distance).product(),
+                directions,
+            }
+        })
+        .collect()
+}
+
+/// How to draw the forest.
+#[derive(Debug, Clone, Copy)]
+enum Render {
+    /// Visible trees in green and hidden ones dimmed, with the best tree
+    /// highlighted in red.
+    Color,
+    /// Visible trees as their heights and hidden ones as `.`, with the best
+    /// tree as `*`.
+    Plain,
+}
+
+impl FromStr for Render {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "color" => Ok(Render::Color),
+            "plain" => Ok(Render::Plain),
+            _ => Err(anyhow!("Unknown render {:?} (expected color or plain)", s)),
+        }
+    }
+}
+
+fn render(trees: &Trees, style: Render, dirs: &[Step]) -> String {
+    let visible = visibility(trees, dirs);
+    let best = best_tree(trees, dirs);
+
+    let mut map = String::new();
+    for y in 0..trees.height() {
+        for x in 0..trees.width() {
+            let idx = trees.idx(Position { x, y });
+            let height = trees.cells()[idx];
+            let is_best = (x, y) == (best.x, best.y);
+            match style {
+                Render::Color => {
+                    let color = if is_best {
+                        "\x1b[1;41m"
+                    } else if visible[idx] {
+                        "\x1b[1;32m"
+                    } else {
+                        "\x1b[2m"
+                    };
+                    map += &format!("{}{}\x1b[0m", color, height);
+                }
+                Render::Plain => map.push(if is_best {
+                    '*'
+                } else if visible[idx] {
+                    char::from_digit(height, 10).unwrap_or('#')
+                } else {
+                    '.'
+                }),
+            }
+        }
+        map.push('\n');
+    }
+    map
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    Scan,
+    Linear,
+    /// Part A as `part_a_simd`, and part B as `part_b_linear`.
+    Simd,
+    #[cfg(feature = "parallel")]
+    Parallel,
+}
+
+impl Strategy {
+    fn part_a(&self, trees: &Trees, dirs: &[Step]) -> usize {
+        match self {
+            Strategy::Scan => part_a_scan(trees, dirs),
+            Strategy::Linear => part_a_linear(trees, dirs),
+            Strategy::Simd => part_a_simd(trees, dirs),
+            #[cfg(feature = "parallel")]
+            Strategy::Parallel => part_a_parallel(trees, dirs),
+        }
+    }
+
+    fn part_b(&self, trees: &Trees, dirs: &[Step]) -> usize {
+        match self {
+            Strategy::Scan => part_b_scan(trees, dirs),
+            Strategy::Linear | Strategy::Simd => part_b_linear(trees, dirs),
+            #[cfg(feature = "parallel")]
+            Strategy::Parallel => part_b_parallel(trees, dirs),
+        }
+    }
+}
+
+impl variants::Strategy for Strategy {
+    #[cfg(not(feature = "parallel"))]
+    const ALL: &'static [Strategy] = &[Strategy::Scan, Strategy::Linear, Strategy::Simd];
+    #[cfg(feature = "parallel")]
+    const ALL: &'static [Strategy] = &[
+        Strategy::Scan,
+        Strategy::Linear,
+        Strategy::Simd,
+        Strategy::Parallel,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Scan => "scan",
+            Strategy::Linear => "linear",
+            Strategy::Simd => "simd",
+            #[cfg(feature = "parallel")]
+            Strategy::Parallel => "parallel",
+        }
+    }
+}
+
+/// Parses a `--strategy` name, explaining that the parallel strategy needs the
+/// `parallel` feature rather than just calling it unknown.
+fn parse_strategy(name: &str) -> Result<Strategy> {
+    if cfg!(not(feature = "parallel")) && name == "parallel" {
+        return Err(anyhow!(
+            "The parallel strategy requires building with --features parallel"
+        ));
+    }
+    Ok(variants::parse_strategy(name)?)
+}
+
+/// Generates a square forest of pseudo-random heights, for `--generate` and
+/// benchmarking.
+fn generate(size: usize) -> Trees {
+    let mut rng = Rng::default();
+    let trees = (0..size * size).map(|_| rng.below(10) as u32).collect();
+
+    Trees::new(size, size, trees).unwrap()
+}
+
+fn main() -> Result<()> {
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input08.txt".to_string();
+    let mut strategy = Strategy::Linear;
+    let mut cross_check = false;
+    let mut generate_size = None;
+    let mut verbose = false;
+    let mut render_style = None;
+    let mut top_k = None;
+    let mut dirs = &ORTHOGONAL[..];
+    let mut dump_json = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strategy" => {
+                strategy = parse_strategy(&args.next().context("--strategy requires a value")?)?;
+            }
+            "--cross-check" => cross_check = true,
+            "--verbose" => verbose = true,
+            "--diagonals" => dirs = &ALL_DIRECTIONS[..],
+            "--dump" => match args.next().context("--dump requires a value")?.as_str() {
+                "json" => dump_json = true,
+                format => return Err(anyhow!("Unknown dump format {:?} (expected json)", format)),
+            },
+            "--top-k" => top_k = Some(args.next().context("--top-k requires a value")?.parse()?),
+            "--render" => {
+                render_style = Some(args.next().context("--render requires a value")?.parse()?);
+            }
+            "--generate" => {
+                generate_size = Some(
+                    args.next()
+                        .context("--generate requires a value")?
+                        .parse()?,
+                );
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    let trees = match generate_size {
+        Some(size) => generate(size),
+        None => {
+            let input = std::fs::read_to_string(&input_path)
+                .with_context(|| format!("Error reading {:?}", input_path))?;
+            timing::time(Stage::Parse, || parse_input(&input)).context("Error parsing input")?
+        }
+    };
+
+    if dump_parsed {
+        return Ok(print_json(&trees)?);
+    }
+
+    // Parsing checks everything the solutions rely on: that the grid is
+    // rectangular and every height is a digit.
+    if validate_only {
+        report_warnings(8, &[]);
+        return Ok(());
+    }
+
+    if dump_json {
+        return Ok(print_json(&analyse(&trees, dirs))?);
+    }
+
+    let result_a = timing::time(Stage::PartA, || strategy.part_a(&trees, dirs));
+    println!("Day 8, part A: {}", result_a);
+
+    let result_b = timing::time(Stage::PartB, || strategy.part_b(&trees, dirs));
     println!("Day 8, part B: {}", result_b);
 
+    if let Some(k) = top_k {
+        for (score, (x, y)) in top_scenic_scores(&trees, k, dirs) {
+            println!("Scenic score {} at ({}, {})", score, x, y);
+        }
+    }
+
+    if let Some(style) = render_style {
+        print!("{}", render(&trees, style, dirs));
+    }
+
+    if verbose {
+        println!(
+            "Visible trees: {}",
+            visible_trees(&trees, dirs)
+                .iter()
+                .map(|(x, y)| format!("({}, {})", x, y))
+                .join(", ")
+        );
+
+        let best = best_tree(&trees, dirs);
+        println!(
+            "Best tree: ({}, {}) with score {}, seeing {}",
+            best.x,
+            best.y,
+            best.score,
+            best.distances
+                .iter()
+                .map(|&(step, distance)| format!("{} {}", distance, direction_name(step)))
+                .join(", ")
+        );
+    }
+
+    if cross_check {
+        variants::cross_check(8, strategy, &(result_a, result_b), |other| {
+            Ok::<_, anyhow::Error>((other.part_a(&trees, dirs), other.part_b(&trees, dirs)))
+        })?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2022::examples;
+    use aoc2022::variants::Strategy as _;
+
+    #[test]
+    fn strategies_handle_an_empty_forest() {
+        let trees = generate(0);
+        for &strategy in Strategy::ALL {
+            assert_eq!(strategy.part_a(&trees, &ORTHOGONAL), 0, "{:?}", strategy);
+            assert_eq!(strategy.part_b(&trees, &ORTHOGONAL), 0, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn accepts_trailing_blank_lines() {
+        let example = examples::for_day(8).next().unwrap().input;
+        for input in [example.to_string(), format!("{}\n\n", example)] {
+            let trees = parse_input(&input).unwrap();
+            for &strategy in Strategy::ALL {
+                assert_eq!(strategy.part_a(&trees, &ORTHOGONAL), 21, "{:?}", strategy);
+                assert_eq!(strategy.part_b(&trees, &ORTHOGONAL), 8, "{:?}", strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_strategy_names_every_strategy() {
+        for &strategy in Strategy::ALL {
+            assert_eq!(
+                parse_strategy(strategy.name()).unwrap().name(),
+                strategy.name()
+            );
+        }
+        assert!(parse_strategy("parallel").is_ok() == cfg!(feature = "parallel"));
+        assert!(parse_strategy("unknown").is_err());
+    }
+}
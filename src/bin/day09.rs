@@ -1,39 +1,70 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::{eol, parse_all};
+use aoc2022::render::animation::Animation;
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::variants;
 use nom::{
-    character::complete::{digit1, newline, one_of, space1},
-    combinator::{map, map_res},
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{digit1, one_of, space1},
+    combinator::{map, map_res, value},
     multi::separated_list1,
     sequence::separated_pair,
     IResult,
 };
-use std::collections::HashSet;
+use rustc_hash::FxHashSet;
+use std::{collections::HashSet, fmt, str::FromStr};
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Direction {
     Up,
     Down,
     Left,
     Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Motion {
     dir: Direction,
     dist: isize,
 }
 
+/// Parses a direction, either as letters such as `U` or `DR`, or as the digit
+/// in that direction from 5 on a numeric keypad.
+fn parse_direction(input: &str) -> IResult<&str, Direction> {
+    alt((
+        value(Direction::UpLeft, tag("UL")),
+        value(Direction::UpRight, tag("UR")),
+        value(Direction::DownLeft, tag("DL")),
+        value(Direction::DownRight, tag("DR")),
+        map(one_of("UDLR12346789"), |c| match c {
+            'U' | '8' => Direction::Up,
+            'D' | '2' => Direction::Down,
+            'L' | '4' => Direction::Left,
+            'R' | '6' => Direction::Right,
+            '7' => Direction::UpLeft,
+            '9' => Direction::UpRight,
+            '1' => Direction::DownLeft,
+            '3' => Direction::DownRight,
+            _ => unreachable!(),
+        }),
+    ))(input)
+}
+
 fn parse_input(input: &str) -> IResult<&str, Vec<Motion>> {
     separated_list1(
-        newline,
+        eol,
         map(
             separated_pair(
-                map(one_of("UDLR"), |c| match c {
-                    'U' => Direction::Up,
-                    'D' => Direction::Down,
-                    'L' => Direction::Left,
-                    'R' => Direction::Right,
-                    _ => unreachable!(),
-                }),
+                parse_direction,
                 space1,
                 map_res(digit1, |c: &str| c.parse::<isize>()),
             ),
@@ -55,6 +86,22 @@ impl Position {
             Direction::Down => self.y -= 1,
             Direction::Left => self.x -= 1,
             Direction::Right => self.x += 1,
+            Direction::UpLeft => {
+                self.x -= 1;
+                self.y += 1;
+            }
+            Direction::UpRight => {
+                self.x += 1;
+                self.y += 1;
+            }
+            Direction::DownLeft => {
+                self.x -= 1;
+                self.y -= 1;
+            }
+            Direction::DownRight => {
+                self.x += 1;
+                self.y -= 1;
+            }
         }
     }
 
@@ -66,40 +113,679 @@ impl Position {
         self.x += (other.x - self.x).signum();
         self.y += (other.y - self.y).signum();
     }
+
+    /// Moves a knot after the knot in front of it has moved a step, so that
+    /// they are touching again.
+    fn catch_up(&mut self, leader: &Position) {
+        if !self.is_touching(leader) {
+            self.move_towards(leader);
+        }
+    }
+}
+
+/// A set of positions visited by a knot.
+trait Visited: Clone {
+    fn insert(&mut self, pos: Position);
+    fn contains(&self, pos: &Position) -> bool;
+    fn count(&self) -> usize;
+    fn positions(&self) -> Vec<Position>;
+}
+
+impl Visited for FxHashSet<Position> {
+    fn insert(&mut self, pos: Position) {
+        HashSet::insert(self, pos);
+    }
+
+    fn contains(&self, pos: &Position) -> bool {
+        HashSet::contains(self, pos)
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn positions(&self) -> Vec<Position> {
+        self.iter().copied().collect()
+    }
+}
+
+/// A dense set of positions within a fixed rectangle, one bit per position.
+/// Much faster than hashing for long walks, provided the rectangle is known
+/// in advance.
+#[derive(Debug, Clone)]
+struct Bitset {
+    left: isize,
+    bottom: isize,
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+    count: usize,
+}
+
+impl Bitset {
+    /// Creates an empty set which can hold positions between `min` and `max`
+    /// inclusive.
+    fn new(min: Position, max: Position) -> Self {
+        let width = (max.x - min.x + 1) as usize;
+        let height = (max.y - min.y + 1) as usize;
+        Bitset {
+            left: min.x,
+            bottom: min.y,
+            width,
+            height,
+            bits: vec![0; (width * height).div_ceil(64)],
+            count: 0,
+        }
+    }
+
+    fn idx(&self, pos: &Position) -> usize {
+        debug_assert!(
+            (self.left..self.left + self.width as isize).contains(&pos.x)
+                && (self.bottom..self.bottom + self.height as isize).contains(&pos.y),
+            "{:?} is outside the bitset",
+            pos
+        );
+        (pos.y - self.bottom) as usize * self.width + (pos.x - self.left) as usize
+    }
+}
+
+impl Visited for Bitset {
+    fn insert(&mut self, pos: Position) {
+        let idx = self.idx(&pos);
+        let (word, bit) = (idx / 64, 1 << (idx % 64));
+        if self.bits[word] & bit == 0 {
+            self.bits[word] |= bit;
+            self.count += 1;
+        }
+    }
+
+    fn contains(&self, pos: &Position) -> bool {
+        let idx = self.idx(pos);
+        self.bits[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn positions(&self) -> Vec<Position> {
+        (0..self.width * self.height)
+            .filter(|idx| self.bits[idx / 64] & (1 << (idx % 64)) != 0)
+            .map(|idx| Position {
+                x: self.left + (idx % self.width) as isize,
+                y: self.bottom + (idx / self.width) as isize,
+            })
+            .collect()
+    }
+}
+
+/// The smallest rectangle containing every position the head visits, which
+/// also contains every position the other knots visit, as they only ever
+/// move towards the knot in front.
+fn bounds(motions: &[Motion]) -> (Position, Position) {
+    let mut head = Position { x: 0, y: 0 };
+    let (mut min, mut max) = (head, head);
+    for motion in motions {
+        // The head moves in a straight line, so only the end matters.
+        let mut step = Position { x: 0, y: 0 };
+        step.step(motion.dir);
+        head.x += step.x * motion.dist;
+        head.y += step.y * motion.dist;
+
+        min.x = min.x.min(head.x);
+        min.y = min.y.min(head.y);
+        max.x = max.x.max(head.x);
+        max.y = max.y.max(head.y);
+    }
+    (min, max)
+}
+
+/// How visited positions are tracked.
+#[derive(Debug, Clone, Copy)]
+enum Tracking {
+    Hash,
+    Bitset,
+}
+
+impl FromStr for Tracking {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "hash" => Ok(Tracking::Hash),
+            "bitset" => Ok(Tracking::Bitset),
+            _ => Err(anyhow!(
+                "Unknown tracking {:?} (expected hash or bitset)",
+                s
+            )),
+        }
+    }
 }
 
-fn run(rope: &mut [Position], motions: &[Motion]) -> usize {
-    let mut tail_positions: HashSet<Position> = HashSet::default();
-    tail_positions.insert(*rope.last().unwrap());
+/// Moves a rope with `knots` knots, and returns the positions visited by each
+/// knot, starting from the `empty` set. As knots are only affected by those in
+/// front of them, the tail of a shorter rope behaves exactly like the
+/// corresponding knot of a longer one, so the result answers the question for
+/// every length up to `knots`.
+fn run<V: Visited>(motions: &[Motion], knots: usize, empty: V) -> Vec<V> {
+    let mut rope = vec![Position { x: 0, y: 0 }; knots];
+    let mut visited = vec![empty; knots];
+    for positions in &mut visited {
+        positions.insert(Position { x: 0, y: 0 });
+    }
 
     for motion in motions {
         for _step in 0..motion.dist {
             rope[0].step(motion.dir);
             for i in 1..rope.len() {
                 let head = rope[i - 1];
-                if !rope[i].is_touching(&head) {
-                    rope[i].move_towards(&head);
-                }
+                rope[i].catch_up(&head);
+            }
+            for (positions, knot) in visited.iter_mut().zip(&rope) {
+                positions.insert(*knot);
+            }
+        }
+    }
+
+    visited
+}
+
+/// A knot's path as a series of segments, each a number of identical moves.
+type Trajectory = Vec<(Position, isize)>;
+
+fn push_segment(trajectory: &mut Trajectory, delta: Position, count: isize) {
+    match trajectory.last_mut() {
+        Some((last, last_count)) if *last == delta => *last_count += count,
+        _ => trajectory.push((delta, count)),
+    }
+}
+
+/// Works out the path of a knot starting at `start` which follows a leader
+/// starting at `leader_start` along `leader`. Whenever the knot makes the same
+/// move as its leader, their offset is unchanged, so it will keep doing so
+/// until the leader changes direction. The rest of the segment can then be
+/// skipped rather than stepped through.
+fn follow(leader_start: Position, leader: &Trajectory, start: Position) -> Trajectory {
+    let mut lead = leader_start;
+    let mut pos = start;
+    let mut trajectory = Trajectory::new();
+
+    for &(delta, count) in leader {
+        let mut remaining = count;
+        while remaining > 0 {
+            lead.x += delta.x;
+            lead.y += delta.y;
+            remaining -= 1;
+
+            let before = pos;
+            pos.catch_up(&lead);
+            let moved = Position {
+                x: pos.x - before.x,
+                y: pos.y - before.y,
+            };
+            push_segment(&mut trajectory, moved, 1);
+
+            if moved == delta {
+                lead.x += delta.x * remaining;
+                lead.y += delta.y * remaining;
+                pos.x += delta.x * remaining;
+                pos.y += delta.y * remaining;
+                push_segment(&mut trajectory, delta, remaining);
+                remaining = 0;
+            }
+        }
+    }
+
+    trajectory
+}
+
+/// Equivalent to `run`, but works out each knot's whole path in turn from the
+/// path of the knot in front, using `follow`.
+fn run_segments<V: Visited>(motions: &[Motion], knots: usize, empty: V) -> Vec<V> {
+    let start = Position { x: 0, y: 0 };
+    let mut trajectory = Trajectory::new();
+    for motion in motions {
+        let mut delta = start;
+        delta.step(motion.dir);
+        push_segment(&mut trajectory, delta, motion.dist);
+    }
+
+    let mut visited = vec![empty; knots];
+    for (knot, positions) in visited.iter_mut().enumerate() {
+        if knot > 0 {
+            trajectory = follow(start, &trajectory, start);
+        }
+
+        let mut pos = start;
+        positions.insert(pos);
+        for &(delta, count) in &trajectory {
+            if delta == start {
+                continue;
+            }
+            for _ in 0..count {
+                pos.x += delta.x;
+                pos.y += delta.y;
+                positions.insert(pos);
+            }
+        }
+    }
+
+    visited
+}
+
+/// How the rope's movement is simulated.
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    /// The whole rope a step at a time, using `run`.
+    Steps,
+    /// A knot a segment at a time, using `run_segments`.
+    Segments,
+}
+
+impl Strategy {
+    fn run<V: Visited>(&self, motions: &[Motion], knots: usize, empty: V) -> Vec<V> {
+        match self {
+            Strategy::Steps => run(motions, knots, empty),
+            Strategy::Segments => run_segments(motions, knots, empty),
+        }
+    }
+}
+
+/// The positions each knot visits, for comparing strategies. As there may be
+/// thousands, only how many each knot visits is shown.
+#[derive(PartialEq)]
+struct VisitedByKnot(Vec<FxHashSet<Position>>);
+
+impl fmt::Debug for VisitedByKnot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts = self.0.iter().map(HashSet::len).collect::<Vec<_>>();
+        write!(f, "visited counts {:?}", counts)
+    }
+}
+
+impl variants::Strategy for Strategy {
+    const ALL: &'static [Strategy] = &[Strategy::Steps, Strategy::Segments];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Steps => "steps",
+            Strategy::Segments => "segments",
+        }
+    }
+}
+
+/// Draws the visited positions as in the puzzle text, with `#` for visited,
+/// `.` for unvisited, and `s` for the start, in the smallest rectangle
+/// containing them all. Up is towards the top.
+fn render(visited: &impl Visited) -> String {
+    let start = Position { x: 0, y: 0 };
+    let positions = visited.positions();
+    let (min_x, max_x) = positions
+        .iter()
+        .map(|p| p.x)
+        .fold((start.x, start.x), |(min, max), x| (min.min(x), max.max(x)));
+    let (min_y, max_y) = positions
+        .iter()
+        .map(|p| p.y)
+        .fold((start.y, start.y), |(min, max), y| (min.min(y), max.max(y)));
+
+    let mut map = String::new();
+    for y in (min_y..=max_y).rev() {
+        for x in min_x..=max_x {
+            let pos = Position { x, y };
+            map.push(if pos == start {
+                's'
+            } else if visited.contains(&pos) {
+                '#'
+            } else {
+                '.'
+            });
+        }
+        map.push('\n');
+    }
+    map
+}
+
+/// The colours of the animation's pixels: unvisited, visited by the tail, a
+/// knot, and the head.
+const ANIMATION_PALETTE: &[[u8; 3]] =
+    &[[255, 255, 255], [255, 190, 110], [70, 70, 70], [210, 0, 0]];
+
+/// Ropes can take many thousands of steps, so only some are animated.
+const MAX_FRAMES: usize = 300;
+
+/// Animates a rope with `knots` knots following the motions, leaving a trail
+/// where its tail has been, and writes it to `path` as a GIF.
+fn animate(motions: &[Motion], knots: usize, path: &str) -> Result<()> {
+    let (min, max) = bounds(motions);
+    let width = (max.x + 1 - min.x) as usize;
+    let height = (max.y + 1 - min.y) as usize;
+    // Up is towards the top, as in the map.
+    let idx = |pos: &Position| (max.y - pos.y) as usize * width + (pos.x - min.x) as usize;
+    let scale = (600 / width.max(height)).max(1);
+    let mut animation = Animation::create(path, width, height, scale, ANIMATION_PALETTE)?;
+
+    let steps = motions
+        .iter()
+        .map(|motion| motion.dist as usize)
+        .sum::<usize>();
+    let frame_every = steps.div_ceil(MAX_FRAMES).max(1);
+
+    let mut rope = vec![Position { x: 0, y: 0 }; knots];
+    let mut trail = vec![0; width * height];
+    trail[idx(&rope[knots - 1])] = 1;
+    let mut draw = |rope: &[Position], trail: &[u8], delay| {
+        let mut frame = trail.to_vec();
+        for knot in &rope[1..] {
+            frame[idx(knot)] = 2;
+        }
+        frame[idx(&rope[0])] = 3;
+        animation.frame(&frame, delay)
+    };
+
+    let mut step = 0;
+    for motion in motions {
+        for _step in 0..motion.dist {
+            rope[0].step(motion.dir);
+            for i in 1..rope.len() {
+                let head = rope[i - 1];
+                rope[i].catch_up(&head);
+            }
+            trail[idx(&rope[knots - 1])] = 1;
+
+            step += 1;
+            if step % frame_every == 0 {
+                draw(&rope, &trail, 4)?;
             }
-            tail_positions.insert(*rope.last().unwrap());
         }
     }
+    // Linger on the finished trail before looping.
+    draw(&rope, &trail, 300)?;
+    Ok(())
+}
+
+/// The number of positions visited by the tail of a rope of each length, given
+/// the positions visited by each knot of a rope at least as long as any. This
+/// lets ropes of many lengths share a single walk through the motions.
+fn tail_counts(visited: &[impl Visited], lengths: &[usize]) -> Vec<usize> {
+    lengths
+        .iter()
+        .map(|length| visited[length - 1].count())
+        .collect()
+}
+
+/// Prints the number of positions visited by the tails of ropes of each
+/// length, or by every knot, and returns the map of the last rope's tail.
+fn report(visited: &[impl Visited], lengths: &[usize], custom: bool, all_knots: bool) -> String {
+    if all_knots {
+        for (knot, positions) in visited.iter().enumerate() {
+            println!("Day 9, {} knots: {}", knot + 1, positions.count());
+        }
+    } else if custom {
+        for (length, count) in lengths.iter().zip(tail_counts(visited, lengths)) {
+            println!("Day 9, {} knots: {}", length, count);
+        }
+    } else {
+        let counts = tail_counts(visited, lengths);
+        println!("Day 9, part A: {}", counts[0]);
+        println!("Day 9, part B: {}", counts[1]);
+    }
+
+    render(&visited[lengths.last().unwrap() - 1])
+}
 
-    tail_positions.len()
+/// Checks that every motion moves somewhere.
+fn validate(motions: &[Motion]) -> Vec<String> {
+    motions
+        .iter()
+        .enumerate()
+        .filter(|(_, motion)| motion.dist <= 0)
+        .map(|(i, motion)| {
+            format!(
+                "Line {}: {:?} {} moves nowhere",
+                i + 1,
+                motion.dir,
+                motion.dist
+            )
+        })
+        .collect()
 }
 
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input09.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input09.txt".to_string();
+    let mut lengths = Vec::new();
+    let mut print_map = false;
+    let mut map_path = None;
+    let mut all_knots = false;
+    let mut tracking = Tracking::Hash;
+    let mut strategy = Strategy::Segments;
+    let mut cross_check = false;
+    let mut animation_path = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--knots" => {
+                for length in args.next().context("--knots requires a value")?.split(',') {
+                    lengths.push(
+                        length
+                            .parse()
+                            .with_context(|| format!("Invalid rope length {:?}", length))?,
+                    );
+                }
+            }
+            "--map" => print_map = true,
+            "--all-knots" => all_knots = true,
+            "--map-file" => map_path = Some(args.next().context("--map-file requires a value")?),
+            "--tracking" => {
+                tracking = args
+                    .next()
+                    .context("--tracking requires a value")?
+                    .parse()?;
+            }
+            "--strategy" => {
+                strategy =
+                    variants::parse_strategy(&args.next().context("--strategy requires a value")?)?;
+            }
+            "--cross-check" => cross_check = true,
+            "--animate" => {
+                animation_path = Some(args.next().context("--animate requires a path")?);
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
 
-    let motions = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
 
-    let result_a = run(&mut [Position { x: 0, y: 0 }; 2], &motions);
-    println!("Day 9, part A: {}", result_a);
+    // Both parts follow the same rope, so only parsing is timed separately.
+    let motions = timing::time(Stage::Parse, || parse_all(parse_input, &input))?;
 
-    let result_b = run(&mut [Position { x: 0, y: 0 }; 10], &motions);
-    println!("Day 9, part B: {}", result_b);
+    if dump_parsed {
+        return Ok(print_json(&motions)?);
+    }
+
+    if validate_only {
+        report_warnings(9, &validate(&motions));
+        return Ok(());
+    }
+
+    let custom = !lengths.is_empty();
+    if !custom {
+        lengths = vec![2, 10];
+    }
+
+    if lengths.contains(&0) {
+        return Err(anyhow!("Rope lengths must be non-zero"));
+    }
+    let knots = lengths.iter().copied().max().unwrap();
+
+    let map = match tracking {
+        Tracking::Hash => report(
+            &strategy.run(&motions, knots, FxHashSet::default()),
+            &lengths,
+            custom,
+            all_knots,
+        ),
+        Tracking::Bitset => {
+            let (min, max) = bounds(&motions);
+            report(
+                &strategy.run(&motions, knots, Bitset::new(min, max)),
+                &lengths,
+                custom,
+                all_knots,
+            )
+        }
+    };
+
+    if cross_check {
+        let visited = |strategy: Strategy| {
+            Ok::<_, anyhow::Error>(VisitedByKnot(strategy.run(
+                &motions,
+                knots,
+                FxHashSet::default(),
+            )))
+        };
+        variants::cross_check(9, strategy, &visited(strategy)?, visited)?;
+    }
+
+    if print_map {
+        print!("{}", map);
+    }
+    if let Some(path) = map_path {
+        std::fs::write(&path, map).with_context(|| format!("Error writing {:?}", path))?;
+    }
+    if let Some(path) = animation_path {
+        animate(&motions, knots, &path)
+            .with_context(|| format!("Error writing animation {:?}", path))?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2022::examples;
+    use aoc2022::parse::parse_all;
+    use proptest::prelude::{prop, prop_assert_eq, proptest};
+    use proptest::strategy::Strategy as _;
+
+    const ORIGIN: Position = Position { x: 0, y: 0 };
+
+    /// Where a knot at the origin moves to when the knot in front of it is at
+    /// `(x, y)`.
+    fn caught_up(x: isize, y: isize) -> (isize, isize) {
+        let mut knot = ORIGIN;
+        knot.catch_up(&Position { x, y });
+        (knot.x, knot.y)
+    }
+
+    #[test]
+    fn catch_up_stays_put_when_touching() {
+        for x in -1..=1 {
+            for y in -1..=1 {
+                assert_eq!(caught_up(x, y), (0, 0), "leader at ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn catch_up_moves_straight_when_in_line() {
+        assert_eq!(caught_up(2, 0), (1, 0));
+        assert_eq!(caught_up(-2, 0), (-1, 0));
+        assert_eq!(caught_up(0, 2), (0, 1));
+        assert_eq!(caught_up(0, -2), (0, -1));
+    }
+
+    #[test]
+    fn catch_up_moves_diagonally_otherwise() {
+        for (leader, expected) in [
+            ((2, 1), (1, 1)),
+            ((1, 2), (1, 1)),
+            ((2, 2), (1, 1)),
+            ((-2, 1), (-1, 1)),
+            ((-1, 2), (-1, 1)),
+            ((-2, 2), (-1, 1)),
+            ((2, -1), (1, -1)),
+            ((1, -2), (1, -1)),
+            ((2, -2), (1, -1)),
+            ((-2, -1), (-1, -1)),
+            ((-1, -2), (-1, -1)),
+            ((-2, -2), (-1, -1)),
+        ] {
+            assert_eq!(
+                caught_up(leader.0, leader.1),
+                expected,
+                "leader at {:?}",
+                leader
+            );
+        }
+    }
+
+    #[test]
+    fn catch_up_leaves_knots_touching() {
+        for x in -2..=2 {
+            for y in -2..=2 {
+                let leader = Position { x, y };
+                let mut knot = ORIGIN;
+                knot.catch_up(&leader);
+                assert!(knot.is_touching(&leader), "left behind by {:?}", leader);
+                assert!(knot.is_touching(&ORIGIN), "jumped to {:?}", knot);
+            }
+        }
+    }
+
+    fn visited(strategy: Strategy, motions: &[Motion], knots: usize) -> Vec<FxHashSet<Position>> {
+        strategy.run(motions, knots, FxHashSet::default())
+    }
+
+    #[test]
+    fn segments_match_steps_on_the_examples() {
+        for example in examples::for_day(9) {
+            let motions = parse_all(parse_input, example.input).unwrap();
+            for knots in [2, 10] {
+                assert!(
+                    visited(Strategy::Segments, &motions, knots)
+                        == visited(Strategy::Steps, &motions, knots),
+                    "example {:?} with {} knots",
+                    example.name,
+                    knots
+                );
+            }
+        }
+    }
+
+    fn motion() -> impl proptest::strategy::Strategy<Value = Motion> {
+        const DIRECTIONS: [Direction; 8] = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+            Direction::UpLeft,
+            Direction::UpRight,
+            Direction::DownLeft,
+            Direction::DownRight,
+        ];
+        (prop::sample::select(&DIRECTIONS[..]), 0..8isize)
+            .prop_map(|(dir, dist)| Motion { dir, dist })
+    }
+
+    proptest! {
+        #[test]
+        fn segments_match_steps(
+            motions in prop::collection::vec(motion(), 1..40),
+            knots in 1..12usize,
+        ) {
+            prop_assert_eq!(
+                visited(Strategy::Segments, &motions, knots),
+                visited(Strategy::Steps, &motions, knots)
+            );
+        }
+    }
+}
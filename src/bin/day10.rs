@@ -1,171 +1,626 @@
 use advent_of_code_ocr::parse_string_to_letters;
 use anyhow::{anyhow, Context, Result};
-use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::{char, digit1, newline, space1},
-    combinator::{map, map_res, opt, recognize},
-    multi::separated_list1,
-    sequence::{pair, separated_pair},
-    IResult,
+use aoc2022::dump::{from_json, print_json, to_json};
+use aoc2022::render::svg::{self, Style, Svg};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::vm::{disassemble, parse_program, Assembler, Costs, Cpu, CpuState, Instr, Register};
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    str::FromStr,
 };
 
+/// The signal strength during a cycle.
+fn signal_strength(state: &CpuState) -> isize {
+    state.cycle as isize * state.registers[Register::X]
+}
+
+/// The cycles during which to sample the signal strength.
 #[derive(Debug, Clone)]
-enum Instr {
-    Noop,
-    Addx(isize),
+enum Samples {
+    /// An explicit list of cycles, e.g. `20,60,100`.
+    List(Vec<usize>),
+    /// Every `stride` cycles from `start`, up to and including `end` if given,
+    /// e.g. `20+40k..220`.
+    Stride {
+        start: usize,
+        stride: usize,
+        end: Option<usize>,
+    },
 }
 
-impl Instr {
-    fn cycles(&self) -> usize {
-        match self {
-            Instr::Noop => 1,
-            Instr::Addx(_) => 2,
+impl Default for Samples {
+    fn default() -> Self {
+        Samples::Stride {
+            start: 20,
+            stride: 40,
+            end: Some(220),
         }
     }
 }
 
-#[derive(Debug, Clone)]
-struct Cpu {
-    cycle_count: usize,
-    x: isize,
-    program: Vec<Instr>,
-    pc: usize,
-    instr_remaining_cycles: usize,
+impl Samples {
+    fn contains(&self, cycle: usize) -> bool {
+        match *self {
+            Samples::List(ref cycles) => cycles.contains(&cycle),
+            Samples::Stride { start, stride, end } => {
+                cycle >= start
+                    && end.is_none_or(|end| cycle <= end)
+                    && (cycle - start).is_multiple_of(stride)
+            }
+        }
+    }
 }
 
-impl Cpu {
-    fn new(program: Vec<Instr>) -> Self {
-        Cpu {
-            cycle_count: 0,
-            x: 1,
-            program,
-            pc: 0,
-            instr_remaining_cycles: 0,
+impl FromStr for Samples {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || format!("Invalid samples {:?}", s);
+
+        if let Some((start, rest)) = s.split_once('+') {
+            let (stride, end) = match rest.split_once("..") {
+                Some((stride, end)) => (stride, Some(end.parse().with_context(invalid)?)),
+                None => (rest, None),
+            };
+            let stride = stride
+                .strip_suffix('k')
+                .with_context(invalid)?
+                .parse()
+                .with_context(invalid)?;
+            if stride == 0 {
+                return Err(anyhow!("Sample stride must be non-zero"));
+            }
+            Ok(Samples::Stride {
+                start: start.parse().with_context(invalid)?,
+                stride,
+                end,
+            })
+        } else {
+            Ok(Samples::List(
+                s.split(',')
+                    .map(|cycle| cycle.parse().with_context(invalid))
+                    .collect::<Result<_>>()?,
+            ))
         }
     }
+}
 
-    fn tick(&mut self) -> Option<CpuState> {
-        self.program.get(self.pc).map(|instr| {
-            self.cycle_count += 1;
+/// The sum of the signal strengths during the sampled cycles.
+fn part_a(cpu: Cpu, samples: &Samples) -> Result<isize> {
+    let mut total = 0;
+    for state in cpu.iter() {
+        let state = state?;
+        if samples.contains(state.cycle) {
+            total += signal_strength(&state);
+        }
+    }
+    Ok(total)
+}
 
-            if self.instr_remaining_cycles == 0 {
-                self.instr_remaining_cycles = instr.cycles() - 1;
-            } else {
-                self.instr_remaining_cycles -= 1;
-            }
+/// Writes the cycle, X, and signal strength during each cycle as CSV.
+fn write_csv(cpu: Cpu, mut out: impl Write) -> Result<()> {
+    writeln!(out, "cycle,x,signal_strength")?;
+    for state in cpu.iter() {
+        let state = state?;
+        writeln!(
+            out,
+            "{},{},{}",
+            state.cycle,
+            state.registers[Register::X],
+            signal_strength(&state)
+        )?;
+    }
+    Ok(())
+}
 
-            let state = CpuState {
-                cycle: self.cycle_count,
-                x: self.x,
-                signal_strength: self.cycle_count as isize * self.x,
-            };
+/// Plots X during each cycle, one cycle per line, marking the sampled cycles
+/// with their signal strength.
+fn plot_ascii(cpu: Cpu, samples: &Samples) -> Result<String> {
+    let states = cpu.iter().collect::<Result<Vec<_>, _>>()?;
+    let min_x = states
+        .iter()
+        .map(|state| state.registers[Register::X])
+        .min()
+        .unwrap_or(0);
 
-            if self.instr_remaining_cycles == 0 {
-                match instr {
-                    Instr::Noop => {}
-                    Instr::Addx(val) => self.x += val,
-                }
-                self.pc += 1;
-            }
+    let mut plot = String::new();
+    for state in &states {
+        let x = state.registers[Register::X];
+        write!(
+            plot,
+            "{:>4} {:>4} |{:>width$}",
+            state.cycle,
+            x,
+            '*',
+            width = (x - min_x) as usize + 1
+        )
+        .unwrap();
+        if samples.contains(state.cycle) {
+            write!(plot, "  <- {}", signal_strength(state)).unwrap();
+        }
+        plot.push('\n');
+    }
+    Ok(plot)
+}
 
-            state
-        })
+/// Plots X against cycle as an SVG line graph, with a vertical line through
+/// each sampled cycle labelled with its signal strength.
+fn plot_svg(cpu: Cpu, samples: &Samples) -> Result<String> {
+    const CYCLE_WIDTH: f64 = 4.0;
+    const X_HEIGHT: f64 = 8.0;
+
+    let states = cpu.iter().collect::<Result<Vec<_>, _>>()?;
+    let (min_x, max_x) = states
+        .iter()
+        .map(|state| state.registers[Register::X])
+        .fold((0, 0), |(min, max), x| (min.min(x), max.max(x)));
+    let to_y = |x: isize| (max_x - x) as f64 * X_HEIGHT;
+    let left = |state: &CpuState| (state.cycle - 1) as f64 * CYCLE_WIDTH;
+
+    let mut plot = Svg::new();
+
+    // Each cycle is drawn as a horizontal segment, so that X visibly holds its
+    // value for the whole of the cycle.
+    let points = states.iter().flat_map(|state| {
+        let y = to_y(state.registers[Register::X]);
+        [(left(state), y), (left(state) + CYCLE_WIDTH, y)]
+    });
+    plot.path(&svg::Path::through(points), &Style::stroke("black"));
+
+    for state in states.iter().filter(|state| samples.contains(state.cycle)) {
+        let centre = left(state) + CYCLE_WIDTH / 2.0;
+        plot.path(
+            &svg::Path::new()
+                .move_to(centre, 0.0)
+                .line_to(centre, to_y(min_x)),
+            &Style::stroke("red"),
+        );
+        plot.text(
+            centre + 2.0,
+            -4.0,
+            10.0,
+            &format!("{}: {}", state.cycle, signal_strength(state)),
+            "red",
+        );
     }
 
-    fn iter(self) -> CpuIter {
-        CpuIter { cpu: self }
+    Ok(plot.finish(20.0, 1.0))
+}
+
+/// The dimensions of the CRT, and the width of the sprite drawn on it.
+#[derive(Debug, Clone, Copy)]
+struct CrtSize {
+    width: usize,
+    height: usize,
+    sprite_width: usize,
+}
+
+impl Default for CrtSize {
+    fn default() -> Self {
+        CrtSize {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+        }
     }
 }
 
-struct CpuState {
-    cycle: usize,
-    x: isize,
-    signal_strength: isize,
+impl CrtSize {
+    /// The position of the pixel drawn during a cycle. Once the beam has
+    /// passed the bottom row this is off the screen.
+    fn beam(&self, cycle: usize) -> (usize, usize) {
+        ((cycle - 1) % self.width, (cycle - 1) / self.width)
+    }
+
+    /// Whether the sprite covers the pixel being drawn during a cycle. The
+    /// sprite is centred on X, or just left of it if its width is even.
+    fn is_lit(&self, state: &CpuState) -> bool {
+        let (x, _) = self.beam(state.cycle);
+        let left = state.registers[Register::X] - (self.sprite_width as isize - 1) / 2;
+        (left..left + self.sprite_width as isize).contains(&(x as isize))
+    }
+
+    /// Parses dimensions such as `40x6`, keeping the current sprite width.
+    fn with_dimensions(self, s: &str) -> Result<Self> {
+        let invalid = || format!("Invalid CRT dimensions {:?} (expected WIDTHxHEIGHT)", s);
+        let (width, height) = s.split_once('x').with_context(invalid)?;
+        let width = width.parse().with_context(invalid)?;
+        let height = height.parse().with_context(invalid)?;
+        if width == 0 || height == 0 {
+            return Err(anyhow!("CRT dimensions must be non-zero"));
+        }
+        Ok(CrtSize {
+            width,
+            height,
+            ..self
+        })
+    }
 }
 
-struct CpuIter {
-    cpu: Cpu,
+/// The pixels lit by running a program.
+struct Crt {
+    size: CrtSize,
+    pixels: Vec<Vec<bool>>,
 }
 
-impl Iterator for CpuIter {
-    type Item = CpuState;
+impl Crt {
+    fn draw(cpu: Cpu, size: CrtSize) -> Result<Self> {
+        let mut pixels = vec![vec![false; size.width]; size.height];
+        for state in cpu.iter() {
+            let state = state?;
+            let (x, y) = size.beam(state.cycle);
+            if y < size.height && size.is_lit(&state) {
+                pixels[y][x] = true;
+            }
+        }
+        Ok(Crt { size, pixels })
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.cpu.tick()
+    /// Renders the display as text, one line per row.
+    fn render(&self, on: char, off: char) -> String {
+        let mut display = String::new();
+        for row in &self.pixels {
+            for &pixel in row {
+                display.push(match pixel {
+                    true => on,
+                    false => off,
+                });
+            }
+            display.push('\n');
+        }
+        display
+    }
+
+    /// Writes the display to a greyscale PNG, with each pixel drawn as a
+    /// `scale` by `scale` square.
+    fn write_png(&self, path: &Path, scale: usize) -> Result<()> {
+        let width = self.size.width * scale;
+        let height = self.size.height * scale;
+
+        let mut data = Vec::with_capacity(width * height);
+        for row in &self.pixels {
+            let line = row
+                .iter()
+                .flat_map(|&pixel| std::iter::repeat_n(if pixel { 0xff } else { 0x00 }, scale))
+                .collect::<Vec<u8>>();
+            for _ in 0..scale {
+                data.extend_from_slice(&line);
+            }
+        }
+
+        let file = File::create(path).with_context(|| format!("Error creating {:?}", path))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&data)?;
+
+        Ok(())
     }
 }
 
-fn parse_input(input: &str) -> IResult<&str, Vec<Instr>> {
-    separated_list1(
-        newline,
-        alt((
-            map(tag("noop"), |_| Instr::Noop),
-            map(
-                separated_pair(
-                    tag("addx"),
-                    space1,
-                    map_res(recognize(pair(opt(char('-')), digit1)), |s: &str| {
-                        s.parse::<isize>()
-                            .with_context(|| format!("Error parsing addx argument {:?}", s))
-                    }),
-                ),
-                |(_, val)| Instr::Addx(val),
-            ),
-        )),
-    )(input)
+/// Builds a program which draws `pattern`, a grid of `#` and `.`, which also
+/// determines the CRT's dimensions. X is set every cycle, ready for the next,
+/// to either the column of the following pixel or somewhere clear of it.
+fn synthesize(pattern: &str, sprite_width: usize) -> Result<(Vec<Instr>, CrtSize)> {
+    let rows = pattern.lines().collect::<Vec<_>>();
+    let size = CrtSize {
+        width: rows.first().map_or(0, |row| row.len()),
+        height: rows.len(),
+        sprite_width,
+    };
+    if size.width == 0 || rows.iter().any(|row| row.len() != size.width) {
+        return Err(anyhow!(
+            "Pattern rows must be non-empty and of equal length"
+        ));
+    }
+
+    let mut asm = Assembler::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, pixel) in row.chars().enumerate() {
+            // The first pixel is drawn before any instruction takes effect.
+            if (x, y) == (0, 0) {
+                continue;
+            }
+            let target = match pixel {
+                '#' => x as isize,
+                '.' => (x + sprite_width + 1) as isize,
+                _ => return Err(anyhow!("Invalid pixel {:?} in pattern", pixel)),
+            };
+            asm.set(Register::X, target);
+        }
+    }
+    // Keep running for the final pixel.
+    asm.noop();
+    let program = asm.finish();
+
+    // Check the program survives being written out and read back in, and that
+    // it really does draw the pattern.
+    let reassembled = parse_program(&disassemble(&program))?;
+    if reassembled != program {
+        return Err(anyhow!("Program changed when disassembled and reassembled"));
+    }
+
+    let mut cpu = Cpu::new(&program, Costs::default());
+    cpu.registers[Register::X] = 1;
+    let drawn = Crt::draw(cpu, size)?.render('#', '.');
+    if drawn.lines().ne(rows.iter().copied()) {
+        return Err(anyhow!(
+            "Synthesized program draws\n{}instead of\n{}",
+            drawn,
+            pattern
+        ));
+    }
+
+    Ok((program, size))
 }
 
-fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input10.txt")?;
+/// Steps through the program a cycle at a time, printing the CPU's state
+/// during each. If there are breakpoints then runs until the first of them,
+/// otherwise stops at the first cycle.
+fn debug(cpu: Cpu, size: CrtSize, mut breakpoints: Vec<usize>) -> Result<()> {
+    println!(
+        "Press Enter to step one cycle, c then Enter to continue to the next breakpoint, \
+         b N then Enter to add a breakpoint at cycle N, or q then Enter to quit."
+    );
 
-    let instructions = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+    let stdin = std::io::stdin();
+    let mut running = !breakpoints.is_empty();
+    for state in cpu.iter() {
+        let state = state?;
+        if breakpoints.contains(&state.cycle) {
+            println!("Breakpoint at cycle {}", state.cycle);
+            running = false;
+        }
+        if running {
+            continue;
+        }
 
-    let cpu = Cpu::new(instructions);
+        let (beam_x, beam_y) = size.beam(state.cycle);
+        println!(
+            "cycle {:>3} | pc {:>3} | x {:>3} | {:<10} ({} more cycles) | beam ({:>2}, {}) {}",
+            state.cycle,
+            state.pc,
+            state.registers[Register::X],
+            state.instr.to_string(),
+            state.remaining_cycles,
+            beam_x,
+            beam_y,
+            if size.is_lit(&state) { "lit" } else { "dark" }
+        );
 
-    let result_a = cpu
-        .clone()
-        .iter()
-        .filter_map(|state| {
-            if state.cycle == 20
-                || state.cycle == 60
-                || state.cycle == 100
-                || state.cycle == 140
-                || state.cycle == 180
-                || state.cycle == 220
-            {
-                Some(state.signal_strength)
-            } else {
-                None
+        loop {
+            let mut line = String::new();
+            if stdin.read_line(&mut line)? == 0 {
+                return Ok(());
             }
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [] => break,
+                ["c"] => {
+                    running = true;
+                    break;
+                }
+                ["q"] => return Ok(()),
+                ["b", cycle] => match cycle.parse() {
+                    Ok(cycle) => breakpoints.push(cycle),
+                    Err(_) => println!("Invalid cycle {:?}", cycle),
+                },
+                _ => println!("Unknown command {:?}", line.trim()),
+            }
+        }
+    }
+
+    println!("Program halted");
+    Ok(())
+}
+
+fn parse_glyphs(s: &str) -> Result<(char, char)> {
+    match s.chars().collect::<Vec<_>>()[..] {
+        [on, off] => Ok((on, off)),
+        _ => Err(anyhow!(
+            "Invalid glyphs {:?} (expected an on and an off character)",
+            s
+        )),
+    }
+}
+
+/// Checks that the program only uses the puzzle's instructions, and runs for
+/// long enough to draw the whole CRT.
+fn validate(program: &[Instr], size: CrtSize) -> Vec<String> {
+    let mut warnings = program
+        .iter()
+        .enumerate()
+        .filter(|(_, instr)| !matches!(instr, Instr::Noop | Instr::Add(Register::X, _)))
+        .map(|(i, instr)| {
+            format!(
+                "Line {}: {} isn't one of the puzzle's instructions",
+                i + 1,
+                instr
+            )
         })
-        .sum::<isize>();
-    println!("Day 10, part A: {}", result_a);
+        .collect::<Vec<_>>();
 
-    let mut pixels = [[false; 40]; 6];
-    for state in cpu.iter() {
-        let y = (state.cycle - 1) / 40;
-        let x = (state.cycle - 1) % 40;
+    // Without jumps, the program runs each instruction once.
+    let jumps = program
+        .iter()
+        .any(|instr| matches!(instr, Instr::Jmp(_) | Instr::Jnz(_, _)));
+    if !jumps {
+        let costs = Costs::default();
+        let cycles = program
+            .iter()
+            .map(|instr| costs.cycles(instr))
+            .sum::<usize>();
+        if cycles < size.width * size.height {
+            warnings.push(format!(
+                "The program ends after {} cycles, before all {} pixels are drawn",
+                cycles,
+                size.width * size.height
+            ));
+        }
+    }
+    warnings
+}
+
+fn main() -> Result<()> {
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input10.txt".to_string();
+    let mut glyphs = None;
+    let mut png_path = None;
+    let mut scale = 8;
+    let mut size = CrtSize::default();
+    let mut samples = Samples::default();
+    let mut debugging = false;
+    let mut breakpoints = Vec::new();
+    let mut synthesize_path = None;
+    let mut save_at = None;
+    let mut snapshot_path = None;
+    let mut resume_path = None;
+    let mut csv_path = None;
+    let mut plot_ascii_enabled = false;
+    let mut svg_path = None;
 
-        if ((state.x - 1)..=(state.x + 1)).contains(&(x as isize)) {
-            pixels[y][x] = true;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--glyphs" => {
+                glyphs = Some(parse_glyphs(
+                    &args.next().context("--glyphs requires a value")?,
+                )?);
+            }
+            "--blocks" => glyphs = Some(('\u{2588}', ' ')),
+            "--png" => png_path = Some(args.next().context("--png requires a value")?),
+            "--samples" => {
+                samples = args.next().context("--samples requires a value")?.parse()?;
+            }
+            "--debug" => debugging = true,
+            "--save-at" => {
+                save_at = Some(args.next().context("--save-at requires a value")?.parse()?);
+            }
+            "--snapshot" => {
+                snapshot_path = Some(args.next().context("--snapshot requires a value")?);
+            }
+            "--resume" => resume_path = Some(args.next().context("--resume requires a value")?),
+            "--synthesize" => {
+                synthesize_path = Some(args.next().context("--synthesize requires a value")?);
+            }
+            "--csv" => csv_path = Some(args.next().context("--csv requires a value")?),
+            "--plot" => plot_ascii_enabled = true,
+            "--svg" => svg_path = Some(args.next().context("--svg requires a value")?),
+            "--break" => {
+                breakpoints.push(args.next().context("--break requires a value")?.parse()?);
+            }
+            "--crt" => {
+                size = size.with_dimensions(&args.next().context("--crt requires a value")?)?;
+            }
+            "--sprite-width" => {
+                size.sprite_width = args
+                    .next()
+                    .context("--sprite-width requires a value")?
+                    .parse()?;
+            }
+            "--scale" => scale = args.next().context("--scale requires a value")?.parse()?,
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
         }
     }
 
-    let mut display = String::new();
-    for row in pixels {
-        for pixel in row {
-            display.push(match pixel {
-                true => '#',
-                false => '.',
-            });
+    if let Some(path) = synthesize_path {
+        let pattern =
+            std::fs::read_to_string(&path).with_context(|| format!("Error reading {:?}", path))?;
+        let (program, _) = synthesize(&pattern, size.sprite_width)?;
+        print!("{}", disassemble(&program));
+        return Ok(());
+    }
+
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
+
+    let program = timing::time(Stage::Parse, || parse_program(&input))?;
+
+    if dump_parsed {
+        return Ok(print_json(&program)?);
+    }
+
+    if validate_only {
+        report_warnings(10, &validate(&program, size));
+        return Ok(());
+    }
+
+    let mut cpu = match resume_path {
+        Some(path) => {
+            let snapshot = std::fs::read_to_string(&path)
+                .with_context(|| format!("Error reading {:?}", path))?;
+            Cpu::restore(&program, from_json(&snapshot)?)
         }
-        display.push('\n');
+        None => {
+            let mut cpu = Cpu::new(&program, Costs::default());
+            cpu.registers[Register::X] = 1;
+            cpu
+        }
+    };
+
+    // Run until the requested cycle and save the CPU's state rather than
+    // solving, so that the run can be continued later with --resume.
+    if let Some(cycles) = save_at {
+        let path = snapshot_path.context("--save-at requires --snapshot")?;
+        for _ in 0..cycles {
+            cpu.tick()?
+                .context("Program halted before the requested cycle")?;
+        }
+        std::fs::write(&path, to_json(&cpu.snapshot())?)
+            .with_context(|| format!("Error writing {:?}", path))?;
+        return Ok(());
+    }
+
+    if debugging {
+        return debug(cpu, size, breakpoints);
+    }
+
+    let result_a = timing::time(Stage::PartA, || part_a(cpu.clone(), &samples))?;
+    println!("Day 10, part A: {}", result_a);
+
+    if let Some(path) = csv_path {
+        let file = File::create(&path).with_context(|| format!("Error creating {:?}", path))?;
+        write_csv(cpu.clone(), BufWriter::new(file))?;
+    }
+
+    if plot_ascii_enabled {
+        print!("{}", plot_ascii(cpu.clone(), &samples)?);
+    }
+
+    if let Some(path) = svg_path {
+        std::fs::write(&path, plot_svg(cpu.clone(), &samples)?)
+            .with_context(|| format!("Error writing {:?}", path))?;
+    }
+
+    let (crt, display) = timing::time(Stage::PartB, || -> Result<_> {
+        let crt = Crt::draw(cpu, size)?;
+        let display = crt.render('#', '.');
+        Ok((crt, display))
+    })?;
+
+    // The OCR crate expects exactly these glyphs, and only knows letters six
+    // pixels tall. Otherwise leave the reading to the user.
+    if size.height == 6 {
+        let result_b = parse_string_to_letters(&display);
+        println!("Day 10, part B: {}", result_b);
+    } else {
+        print!("Day 10, part B:\n{}", display);
+    }
+
+    if let Some((on, off)) = glyphs {
+        print!("{}", crt.render(on, off));
+    }
+
+    if let Some(path) = png_path {
+        crt.write_png(Path::new(&path), scale)?;
     }
-    let result_b = parse_string_to_letters(&display);
-    print!("Day 10, part B: {}", result_b);
 
     Ok(())
 }
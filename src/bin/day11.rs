@@ -1,30 +1,195 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::{eol, eol_or_eof, parse_all};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::variants;
 use itertools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{digit1, newline},
+    character::complete::{char, digit1, one_of, space0},
     combinator::{cut, map, map_res},
-    multi::{many1, separated_list1},
-    sequence::{delimited, preceded, tuple},
+    multi::{fold_many0, many1, separated_list1},
+    sequence::{delimited, pair, tuple},
     IResult,
 };
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use smallvec::SmallVec;
+use std::{
+    fmt,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Operand {
     Old,
     Literal(usize),
 }
 
-#[derive(Debug, Clone, Copy)]
+/// An arithmetic expression giving a new worry level in terms of the old one.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Operation {
-    Add(Operand),
-    Mul(Operand),
+    Operand(Operand),
+    Add(Box<Operation>, Box<Operation>),
+    Sub(Box<Operation>, Box<Operation>),
+    Mul(Box<Operation>, Box<Operation>),
+    Div(Box<Operation>, Box<Operation>),
+}
+
+impl Operation {
+    /// Whether the operation can be evaluated modulo some number without
+    /// affecting the result's remainder. This does not hold for division.
+    fn is_modular(&self) -> bool {
+        match self {
+            Operation::Operand(_) => true,
+            Operation::Add(lhs, rhs) | Operation::Sub(lhs, rhs) | Operation::Mul(lhs, rhs) => {
+                lhs.is_modular() && rhs.is_modular()
+            }
+            Operation::Div(_, _) => false,
+        }
+    }
+
+    /// Evaluates the operation, optionally modulo `modulus`. Fails if the
+    /// result would overflow, be negative, or divide by zero.
+    fn apply(&self, old: usize, modulus: Option<usize>) -> Result<usize> {
+        let result = match self {
+            Operation::Operand(Operand::Old) => Some(old),
+            Operation::Operand(Operand::Literal(val)) => Some(*val),
+            Operation::Add(lhs, rhs) => lhs
+                .apply(old, modulus)?
+                .checked_add(rhs.apply(old, modulus)?),
+            Operation::Sub(lhs, rhs) => {
+                let lhs = lhs.apply(old, modulus)?;
+                let rhs = rhs.apply(old, modulus)?;
+                match modulus {
+                    // Both operands are already reduced, so adding the modulus
+                    // keeps the result positive without changing its remainder.
+                    Some(modulus) => (lhs + modulus).checked_sub(rhs % modulus),
+                    None => lhs.checked_sub(rhs),
+                }
+            }
+            Operation::Mul(lhs, rhs) => lhs
+                .apply(old, modulus)?
+                .checked_mul(rhs.apply(old, modulus)?),
+            Operation::Div(lhs, rhs) => lhs
+                .apply(old, modulus)?
+                .checked_div(rhs.apply(old, modulus)?),
+        }
+        .with_context(|| format!("Error evaluating {:?} with old = {}", self, old))?;
+
+        Ok(match modulus {
+            Some(modulus) => result % modulus,
+            None => result,
+        })
+    }
+
+    /// Converts the operation into a closure ahead of time, so that it needn't
+    /// be matched on for every item. Returns `None` where `apply` would fail.
+    fn compile(&self, modulus: Option<usize>) -> CompiledOperation {
+        let reduce = move |val: usize| match modulus {
+            Some(modulus) => val % modulus,
+            None => val,
+        };
+
+        match self {
+            // Special case the forms which appear in the puzzle input, to
+            // avoid calling further closures for their operands.
+            Operation::Add(lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+                (Operation::Operand(Operand::Old), Operation::Operand(Operand::Literal(val))) => {
+                    let val = *val;
+                    Box::new(move |old| Some(reduce(old.checked_add(val)?)))
+                }
+                _ => {
+                    let (lhs, rhs) = (lhs.compile(modulus), rhs.compile(modulus));
+                    Box::new(move |old| Some(reduce(lhs(old)?.checked_add(rhs(old)?)?)))
+                }
+            },
+            Operation::Mul(lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+                (Operation::Operand(Operand::Old), Operation::Operand(Operand::Old)) => {
+                    Box::new(move |old| Some(reduce(old.checked_mul(old)?)))
+                }
+                (Operation::Operand(Operand::Old), Operation::Operand(Operand::Literal(val))) => {
+                    let val = *val;
+                    Box::new(move |old| Some(reduce(old.checked_mul(val)?)))
+                }
+                _ => {
+                    let (lhs, rhs) = (lhs.compile(modulus), rhs.compile(modulus));
+                    Box::new(move |old| Some(reduce(lhs(old)?.checked_mul(rhs(old)?)?)))
+                }
+            },
+            Operation::Operand(Operand::Old) => Box::new(move |old| Some(reduce(old))),
+            Operation::Operand(Operand::Literal(val)) => {
+                let val = *val;
+                Box::new(move |_| Some(reduce(val)))
+            }
+            Operation::Sub(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.compile(modulus), rhs.compile(modulus));
+                Box::new(move |old| {
+                    let (lhs, rhs) = (lhs(old)?, rhs(old)?);
+                    match modulus {
+                        Some(modulus) => Some(reduce((lhs + modulus).checked_sub(rhs % modulus)?)),
+                        None => lhs.checked_sub(rhs),
+                    }
+                })
+            }
+            Operation::Div(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.compile(modulus), rhs.compile(modulus));
+                Box::new(move |old| Some(reduce(lhs(old)?.checked_div(rhs(old)?)?)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Old => write!(f, "old"),
+            Operand::Literal(val) => write!(f, "{}", val),
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Parenthesise every nested binary operation rather than working out
+        // which are needed to preserve precedence.
+        let operand = |f: &mut fmt::Formatter<'_>, op: &Operation| match op {
+            Operation::Operand(operand) => write!(f, "{}", operand),
+            _ => write!(f, "({})", op),
+        };
+        let (lhs, symbol, rhs) = match self {
+            Operation::Operand(operand) => return write!(f, "{}", operand),
+            Operation::Add(lhs, rhs) => (lhs, '+', rhs),
+            Operation::Sub(lhs, rhs) => (lhs, '-', rhs),
+            Operation::Mul(lhs, rhs) => (lhs, '*', rhs),
+            Operation::Div(lhs, rhs) => (lhs, '/', rhs),
+        };
+        operand(f, lhs)?;
+        write!(f, " {} ", symbol)?;
+        operand(f, rhs)
+    }
+}
+
+type CompiledOperation = Box<dyn Fn(usize) -> Option<usize> + Send + Sync>;
+
+/// How monkeys' operations are evaluated. Interpreting is only a baseline for
+/// the benchmark and tests to compare compiling against.
+#[derive(Debug, Clone, Copy)]
+enum Evaluation {
+    #[cfg_attr(not(test), allow(dead_code))]
+    Interpret,
+    Compile,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Monkey {
-    items: Vec<usize>,
+    items: SmallVec<[usize; 16]>,
     op: Operation,
     test_divisor: usize,
     true_target: usize,
@@ -36,14 +201,14 @@ fn parse_usize(input: &str) -> IResult<&str, usize> {
 }
 
 fn parse_monkey_header(input: &str) -> IResult<&str, usize> {
-    delimited(tag("Monkey "), parse_usize, tag(":\n"))(input)
+    delimited(tag("Monkey "), parse_usize, pair(char(':'), eol_or_eof))(input)
 }
 
 fn parse_starting_items(input: &str) -> IResult<&str, Vec<usize>> {
     delimited(
         tag("  Starting items: "),
         separated_list1(tag(", "), parse_usize),
-        newline,
+        eol_or_eof,
     )(input)
 }
 
@@ -54,36 +219,72 @@ fn parse_operand(input: &str) -> IResult<&str, Operand> {
     ))(input)
 }
 
-fn parse_operation(input: &str) -> IResult<&str, Operation> {
+fn parse_factor(input: &str) -> IResult<&str, Operation> {
     delimited(
-        tag("  Operation: new = old "),
+        space0,
         alt((
-            map(preceded(tag("+ "), parse_operand), |operand| {
-                Operation::Add(operand)
-            }),
-            map(preceded(tag("* "), parse_operand), |operand| {
-                Operation::Mul(operand)
-            }),
+            map(parse_operand, Operation::Operand),
+            delimited(char('('), parse_expression, char(')')),
         )),
-        newline,
+        space0,
+    )(input)
+}
+
+fn parse_term(input: &str) -> IResult<&str, Operation> {
+    let (input, first) = parse_factor(input)?;
+    fold_many0(
+        pair(one_of("*/"), parse_factor),
+        move || first.clone(),
+        |lhs, (op, rhs)| match op {
+            '*' => Operation::Mul(Box::new(lhs), Box::new(rhs)),
+            '/' => Operation::Div(Box::new(lhs), Box::new(rhs)),
+            _ => unreachable!(),
+        },
+    )(input)
+}
+
+/// Parses an arithmetic expression of `old` and literals, using +, -, *, /,
+/// and parentheses with the usual precedence.
+fn parse_expression(input: &str) -> IResult<&str, Operation> {
+    let (input, first) = parse_term(input)?;
+    fold_many0(
+        pair(one_of("+-"), parse_term),
+        move || first.clone(),
+        |lhs, (op, rhs)| match op {
+            '+' => Operation::Add(Box::new(lhs), Box::new(rhs)),
+            '-' => Operation::Sub(Box::new(lhs), Box::new(rhs)),
+            _ => unreachable!(),
+        },
     )(input)
 }
 
+fn parse_operation(input: &str) -> IResult<&str, Operation> {
+    delimited(tag("  Operation: new ="), parse_expression, eol_or_eof)(input)
+}
+
 fn parse_test_divisor(input: &str) -> IResult<&str, usize> {
-    delimited(tag("  Test: divisible by "), parse_usize, newline)(input)
+    delimited(tag("  Test: divisible by "), parse_usize, eol_or_eof)(input)
 }
 
 fn parse_true_target(input: &str) -> IResult<&str, usize> {
-    delimited(tag("    If true: throw to monkey "), parse_usize, newline)(input)
+    delimited(
+        tag("    If true: throw to monkey "),
+        parse_usize,
+        eol_or_eof,
+    )(input)
 }
 
 fn parse_false_target(input: &str) -> IResult<&str, usize> {
-    delimited(tag("    If false: throw to monkey "), parse_usize, newline)(input)
+    delimited(
+        tag("    If false: throw to monkey "),
+        parse_usize,
+        eol_or_eof,
+    )(input)
 }
 
 fn parse_input(input: &str) -> IResult<&str, Vec<Monkey>> {
     separated_list1(
-        many1(newline),
+        many1(eol),
         cut(map(
             tuple((
                 parse_monkey_header,
@@ -94,84 +295,601 @@ fn parse_input(input: &str) -> IResult<&str, Vec<Monkey>> {
                 parse_false_target,
             )),
             |(_num, items, op, test_divisor, true_target, false_target)| Monkey {
-                items,
+                items: items.into_iter().collect(),
                 op,
                 test_divisor,
                 true_target,
                 false_target,
             },
         )),
-    )(input)
+    )(input.trim_end())
+}
+
+/// Follows a single item through a run, printing each inspection it undergoes.
+#[derive(Debug, Clone)]
+struct Trace {
+    /// The monkey currently holding the item.
+    monkey: usize,
+    /// The item's position in that monkey's list of items.
+    index: usize,
+    /// The number of inspections still to be printed.
+    remaining: usize,
 }
 
-fn run(mut monkeys: Vec<Monkey>, num_iterations: usize, really_worried: bool) -> usize {
+impl Trace {
+    fn parse_item(s: &str) -> Result<(usize, usize)> {
+        let (monkey, index) = s
+            .split_once(',')
+            .with_context(|| format!("Invalid item {:?} (expected monkey,index)", s))?;
+        Ok((monkey.parse()?, index.parse()?))
+    }
+}
+
+/// Fails if any monkey tests divisibility by zero, as worry levels are reduced
+/// modulo the test divisors.
+fn check_test_divisors(monkeys: &[Monkey]) -> Result<()> {
+    match monkeys.iter().position(|m| m.test_divisor == 0) {
+        Some(i) => Err(anyhow!("Monkey {} tests divisibility by zero", i)),
+        None => Ok(()),
+    }
+}
+
+/// Runs the monkeys for a number of rounds, dividing each worry level by
+/// `relief_divisor` after inspection, and returns the number of items each
+/// monkey inspected. `after_round` is called at the end of each round with the
+/// round number, the monkeys, and the inspection counts so far. If `trace` is
+/// given then the inspections of that item are printed.
+fn run(
+    mut monkeys: Vec<Monkey>,
+    rounds: usize,
+    relief_divisor: usize,
+    evaluation: Evaluation,
+    mut trace: Option<Trace>,
+    mut after_round: impl FnMut(usize, &[Monkey], &[usize]),
+) -> Result<Vec<usize>> {
+    if relief_divisor == 0 {
+        return Err(anyhow!("Relief divisor must be non-zero"));
+    }
+    check_test_divisors(&monkeys)?;
+
+    if let Some(trace) = &trace {
+        if monkeys
+            .get(trace.monkey)
+            .is_none_or(|m| trace.index >= m.items.len())
+        {
+            return Err(anyhow!(
+                "Monkey {} has no item {} to trace",
+                trace.monkey,
+                trace.index
+            ));
+        }
+    }
+
     let mut items_inspected = vec![0; monkeys.len()];
 
+    // Only the remainder of each worry level by the monkeys' divisors matters,
+    // so if possible we work modulo their product to keep the levels small.
+    // This isn't possible when worry levels are divided, either by relief or
+    // by an operation.
     let common_divisor = monkeys.iter().map(|m| m.test_divisor).product::<usize>();
+    let modulus = (relief_divisor == 1 && monkeys.iter().all(|m| m.op.is_modular()))
+        .then_some(common_divisor);
+
+    let ops = monkeys
+        .iter()
+        .map(|m| -> CompiledOperation {
+            match evaluation {
+                Evaluation::Interpret => {
+                    let op = m.op.clone();
+                    Box::new(move |old| op.apply(old, modulus).ok())
+                }
+                Evaluation::Compile => m.op.compile(modulus),
+            }
+        })
+        .collect::<Vec<_>>();
 
-    for _round in 0..num_iterations {
+    for round in 1..=rounds {
         for i in 0..monkeys.len() {
-            // Work around the borrow checker. Remember to give the items
-            // back to the monkeys when done.
-            let mut items = std::mem::take(&mut monkeys[i].items);
-            let op = monkeys[i].op;
+            // The monkey throws away all of its items, so take them to work
+            // around the borrow checker. Items are thrown by index, as either
+            // target may be the same monkey as the other or as this one.
+            let items = std::mem::take(&mut monkeys[i].items);
             let test_divisor = monkeys[i].test_divisor;
             let true_target = monkeys[i].true_target;
             let false_target = monkeys[i].false_target;
-            let mut true_items = std::mem::take(&mut monkeys[true_target].items);
-            let mut false_items = std::mem::take(&mut monkeys[false_target].items);
 
             items_inspected[i] += items.len();
 
-            items.drain(..).for_each(|mut item| {
-                match op {
-                    Operation::Add(Operand::Literal(val)) => {
-                        item += val;
-                    }
-                    Operation::Add(Operand::Old) => {
-                        item += item;
-                    }
-                    Operation::Mul(Operand::Literal(val)) => {
-                        item *= val;
-                    }
-                    Operation::Mul(Operand::Old) => {
-                        item *= item;
-                    }
+            for (j, &old) in items.iter().enumerate() {
+                let new = ops[i](old).with_context(|| {
+                    format!("Error evaluating {:?} with old = {}", monkeys[i].op, old)
+                })?;
+                let item = new / relief_divisor;
+
+                let target = if item.is_multiple_of(test_divisor) {
+                    true_target
+                } else {
+                    false_target
                 };
+                monkeys[target].items.push(item);
 
-                if !really_worried {
-                    item /= 3;
+                if let Some(trace) = trace.as_mut().filter(|t| (t.monkey, t.index) == (i, j)) {
+                    if trace.remaining > 0 {
+                        trace.remaining -= 1;
+                        println!(
+                            "Round {}: monkey {} inspects {}, new = {} = {}, relief gives {}, \
+                             {} % {} = {}, thrown to monkey {}",
+                            round,
+                            i,
+                            old,
+                            monkeys[i].op,
+                            new,
+                            item,
+                            item,
+                            test_divisor,
+                            item % test_divisor,
+                            target
+                        );
+                    }
+                    trace.monkey = target;
+                    trace.index = monkeys[target].items.len() - 1;
                 }
+            }
+        }
 
-                item %= common_divisor;
+        after_round(round, &monkeys, &items_inspected);
+    }
 
-                if item % test_divisor == 0 {
-                    true_items.push(item);
-                } else {
-                    false_items.push(item);
-                }
-            });
+    Ok(items_inspected)
+}
+
+/// Runs the monkeys for a number of rounds without relief, returning the number
+/// of items each monkey inspected. Unlike `run`, each item is followed through
+/// every round on its own, which is possible because items never affect one
+/// another. Rather than a single worry level, each item's remainder by every
+/// monkey's divisor is tracked, as those are all that determine where it goes.
+///
+/// With the `parallel` feature, the items are followed in parallel.
+fn run_residues(monkeys: &[Monkey], rounds: usize) -> Result<Vec<usize>> {
+    if !monkeys.iter().all(|m| m.op.is_modular()) {
+        return Err(anyhow!(
+            "Can't track residues of items when operations divide"
+        ));
+    }
+    check_test_divisors(monkeys)?;
+
+    // ops[i][j] applies monkey i's operation modulo monkey j's divisor.
+    let ops = monkeys
+        .iter()
+        .map(|m| {
+            monkeys
+                .iter()
+                .map(|d| m.op.compile(Some(d.test_divisor)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let items_inspected = monkeys
+        .iter()
+        .map(|_| AtomicUsize::new(0))
+        .collect::<Vec<_>>();
+
+    let items = monkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(i, m)| m.items.iter().map(move |&item| (i, item)))
+        .collect::<Vec<_>>();
+    #[cfg(not(feature = "parallel"))]
+    let mut items = items.into_iter();
+    // Each item is independent, so follow them in parallel.
+    #[cfg(feature = "parallel")]
+    let items = items.into_par_iter();
 
-            monkeys[true_target].items = true_items;
-            monkeys[false_target].items = false_items;
+    items.try_for_each(|(mut monkey, item)| -> Result<()> {
+        let mut residues = monkeys
+            .iter()
+            .map(|m| item % m.test_divisor)
+            .collect::<Vec<_>>();
+        let mut counts = vec![0; monkeys.len()];
+
+        let mut round = 1;
+        while round <= rounds {
+            counts[monkey] += 1;
+            for (residue, op) in residues.iter_mut().zip(&ops[monkey]) {
+                *residue = op(*residue).with_context(|| {
+                    format!(
+                        "Error evaluating {:?} with old = {}",
+                        monkeys[monkey].op, residue
+                    )
+                })?;
+            }
+
+            let target = if residues[monkey] == 0 {
+                monkeys[monkey].true_target
+            } else {
+                monkeys[monkey].false_target
+            };
+            // Monkeys take their turns in order, so an item thrown to an
+            // earlier monkey won't be inspected again until next round.
+            if target <= monkey {
+                round += 1;
+            }
+            monkey = target;
+        }
+
+        for (total, count) in items_inspected.iter().zip(counts) {
+            total.fetch_add(count, Ordering::Relaxed);
+        }
+        Ok(())
+    })?;
+
+    Ok(items_inspected
+        .into_iter()
+        .map(AtomicUsize::into_inner)
+        .collect())
+}
+
+/// How part B's rounds are simulated.
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    /// Monkey by monkey, as in part A.
+    Sequential,
+    /// Item by item, using `run_residues`.
+    Residues,
+}
+
+impl variants::Strategy for Strategy {
+    const ALL: &'static [Strategy] = &[Strategy::Sequential, Strategy::Residues];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Sequential => "sequential",
+            Strategy::Residues => "residues",
+        }
+    }
+}
+
+fn part_b(
+    monkeys: Vec<Monkey>,
+    strategy: Strategy,
+    trace: Option<Trace>,
+    after_round: impl FnMut(usize, &[Monkey], &[usize]),
+) -> Result<Vec<usize>> {
+    match strategy {
+        Strategy::Sequential => run(monkeys, 10000, 1, Evaluation::Compile, trace, after_round),
+        Strategy::Residues => run_residues(&monkeys, 10000),
+    }
+}
+
+/// The product of the `top_k` highest inspection counts.
+fn monkey_business(items_inspected: &[usize], top_k: usize) -> usize {
+    items_inspected.iter().sorted().rev().take(top_k).product()
+}
+
+/// Which rounds to print the monkeys' state after.
+#[derive(Debug, Clone)]
+enum DumpRounds {
+    None,
+    All,
+    Selected(Vec<usize>),
+}
+
+impl DumpRounds {
+    fn contains(&self, round: usize) -> bool {
+        match self {
+            DumpRounds::None => false,
+            DumpRounds::All => true,
+            DumpRounds::Selected(rounds) => rounds.contains(&round),
         }
     }
 
-    items_inspected.iter().sorted().rev().take(2).product()
+    /// Prints the state after a round in the format of the puzzle text.
+    fn dump(&self, round: usize, monkeys: &[Monkey], items_inspected: &[usize]) {
+        if !self.contains(round) {
+            return;
+        }
+
+        println!(
+            "After round {}, the monkeys are holding items with these worry levels:",
+            round
+        );
+        for (i, monkey) in monkeys.iter().enumerate() {
+            println!("Monkey {}: {}", i, monkey.items.iter().join(", "));
+        }
+        println!();
+
+        println!("== After round {} ==", round);
+        for (i, count) in items_inspected.iter().enumerate() {
+            println!("Monkey {} inspected items {} times.", i, count);
+        }
+        println!();
+    }
+}
+
+impl FromStr for DumpRounds {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "all" => Ok(DumpRounds::All),
+            _ => Ok(DumpRounds::Selected(
+                s.split(',')
+                    .map(|round| {
+                        round
+                            .parse()
+                            .with_context(|| format!("Invalid round {:?}", round))
+                    })
+                    .collect::<Result<Vec<usize>>>()?,
+            )),
+        }
+    }
+}
+
+/// Checks that each monkey throws to other monkeys which exist, and that
+/// worry levels can be kept small in part B.
+fn validate(monkeys: &[Monkey]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (i, monkey) in monkeys.iter().enumerate() {
+        for target in [monkey.true_target, monkey.false_target] {
+            if target >= monkeys.len() {
+                warnings.push(format!(
+                    "Monkey {} throws to monkey {}, which does not exist",
+                    i, target
+                ));
+            } else if target == i {
+                warnings.push(format!("Monkey {} throws to itself", i));
+            }
+        }
+        if monkey.test_divisor == 0 {
+            warnings.push(format!("Monkey {} tests divisibility by zero", i));
+        }
+        if !monkey.op.is_modular() {
+            warnings.push(format!(
+                "Monkey {}'s operation divides, so part B's worry levels can't be kept small",
+                i
+            ));
+        }
+    }
+    warnings
 }
 
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input11.txt")?;
+    let mut validate_only = false;
+    let mut input_path = "res/input11.txt".to_string();
+    let mut dump_rounds = DumpRounds::None;
+    let mut rounds = None;
+    let mut relief_divisor = None;
+    let mut top_k = 2;
+    let mut dump_parsed = false;
+    let mut trace_item = None;
+    let mut trace_limit = 100;
+    let mut strategy = Strategy::Sequential;
+    let mut cross_check = false;
 
-    let monkeys = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rounds" => {
+                rounds = Some(args.next().context("--rounds requires a value")?.parse()?);
+            }
+            "--relief-divisor" => {
+                relief_divisor = Some(
+                    args.next()
+                        .context("--relief-divisor requires a value")?
+                        .parse()?,
+                );
+            }
+            "--top-k" => top_k = args.next().context("--top-k requires a value")?.parse()?,
+            "--dump-parsed" => dump_parsed = true,
+            "--strategy" => {
+                strategy =
+                    variants::parse_strategy(&args.next().context("--strategy requires a value")?)?;
+            }
+            "--cross-check" => cross_check = true,
+            "--trace-item" => {
+                trace_item = Some(Trace::parse_item(
+                    &args.next().context("--trace-item requires a value")?,
+                )?);
+            }
+            "--trace-limit" => {
+                trace_limit = args
+                    .next()
+                    .context("--trace-limit requires a value")?
+                    .parse()?;
+            }
+            "--dump-rounds" => {
+                dump_rounds = args
+                    .next()
+                    .context("--dump-rounds requires a value")?
+                    .parse()?;
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
+
+    let monkeys = timing::time(Stage::Parse, || parse_all(parse_input, &input))?;
+
+    if validate_only {
+        report_warnings(11, &validate(&monkeys));
+        return Ok(());
+    }
 
-    let result_a = run(monkeys.clone(), 20, false);
+    // Following items item by item can't show the monkeys' state each round.
+    let observing = trace_item.is_some() || !matches!(dump_rounds, DumpRounds::None);
+    if matches!(strategy, Strategy::Residues) && observing {
+        return Err(anyhow!(
+            "--trace-item and --dump-rounds require --strategy sequential"
+        ));
+    }
+
+    let dump = |round: usize, monkeys: &[Monkey], items_inspected: &[usize]| {
+        dump_rounds.dump(round, monkeys, items_inspected)
+    };
+
+    let trace = trace_item.map(|(monkey, index)| Trace {
+        monkey,
+        index,
+        remaining: trace_limit,
+    });
+
+    if dump_parsed {
+        return Ok(print_json(&monkeys)?);
+    }
+
+    // Rather than solving both parts, run a single custom configuration if
+    // requested. Unspecified parameters default to part A's.
+    if rounds.is_some() || relief_divisor.is_some() {
+        let items_inspected = run(
+            monkeys,
+            rounds.unwrap_or(20),
+            relief_divisor.unwrap_or(3),
+            Evaluation::Compile,
+            trace,
+            dump,
+        )?;
+        for (i, count) in items_inspected.iter().enumerate() {
+            println!("Monkey {} inspected items {} times.", i, count);
+        }
+        println!("Day 11: {}", monkey_business(&items_inspected, top_k));
+        return Ok(());
+    }
+
+    let result_a = monkey_business(
+        &timing::time(Stage::PartA, || {
+            run(
+                monkeys.clone(),
+                20,
+                3,
+                Evaluation::Compile,
+                trace.clone(),
+                dump,
+            )
+        })?,
+        top_k,
+    );
     println!("Day 11, part A: {}", result_a);
 
-    let result_b = run(monkeys, 10000, true);
+    let result_b = monkey_business(
+        &timing::time(Stage::PartB, || {
+            part_b(monkeys.clone(), strategy, trace, dump)
+        })?,
+        top_k,
+    );
     println!("Day 11, part B: {}", result_b);
 
+    if cross_check {
+        variants::cross_check(11, strategy, &result_b, |other| {
+            let inspections = part_b(monkeys.clone(), other, None, |_, _, _| {})?;
+            Ok::<_, anyhow::Error>(monkey_business(&inspections, top_k))
+        })?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2022::examples;
+
+    fn example() -> &'static str {
+        examples::for_day(11).next().unwrap().input
+    }
+
+    /// Part A's answer for `input`.
+    fn part_a(input: &str) -> usize {
+        let monkeys = parse_all(parse_input, input).unwrap();
+        let items_inspected = run(monkeys, 20, 3, Evaluation::Compile, None, |_, _, _| {});
+        monkey_business(&items_inspected.unwrap(), 2)
+    }
+
+    #[test]
+    fn parses_the_example() {
+        let monkeys = parse_all(parse_input, example()).unwrap();
+        assert_eq!(monkeys.len(), 4);
+        assert_eq!(part_a(example()), 10605);
+    }
+
+    #[test]
+    fn compiling_matches_interpreting() {
+        let monkeys = parse_all(parse_input, example()).unwrap();
+        for (rounds, relief_divisor) in [(20, 3), (10000, 1)] {
+            let [interpreted, compiled] = [Evaluation::Interpret, Evaluation::Compile].map(|e| {
+                run(
+                    monkeys.clone(),
+                    rounds,
+                    relief_divisor,
+                    e,
+                    None,
+                    |_, _, _| {},
+                )
+                .unwrap()
+            });
+            assert_eq!(interpreted, compiled);
+        }
+    }
+
+    #[test]
+    fn strategies_agree_when_both_targets_are_the_same() {
+        let input = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+";
+        let monkeys = parse_all(parse_input, input).unwrap();
+        let [sequential, residues] = [Strategy::Sequential, Strategy::Residues]
+            .map(|s| monkey_business(&part_b(monkeys.clone(), s, None, |_, _, _| {}).unwrap(), 2));
+        assert_eq!(sequential, residues);
+        // Monkey 0 holds only its own items in the first round.
+        assert_eq!(sequential, (2 + 6 * 9999) * 6 * 10000);
+    }
+
+    #[test]
+    fn rejects_a_zero_test_divisor() {
+        let input = example().replacen("divisible by 23", "divisible by 0", 1);
+        let monkeys = parse_all(parse_input, &input).unwrap();
+        assert!(run(
+            monkeys.clone(),
+            20,
+            3,
+            Evaluation::Compile,
+            None,
+            |_, _, _| {}
+        )
+        .is_err());
+        for strategy in [Strategy::Sequential, Strategy::Residues] {
+            assert!(part_b(monkeys.clone(), strategy, None, |_, _, _| {}).is_err());
+        }
+    }
+
+    #[test]
+    fn accepts_crlf() {
+        assert_eq!(part_a(&example().replace('\n', "\r\n")), 10605);
+    }
+
+    #[test]
+    fn accepts_a_missing_trailing_newline() {
+        assert_eq!(part_a(example().trim_end()), 10605);
+        assert_eq!(part_a(example().replace('\n', "\r\n").trim_end()), 10605);
+    }
+
+    #[test]
+    fn accepts_extra_blank_lines() {
+        assert_eq!(part_a(&format!("{}\n\n", example())), 10605);
+        assert_eq!(part_a(&example().replace("\n\n", "\n\n\n")), 10605);
+    }
+}
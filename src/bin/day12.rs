@@ -1,23 +1,52 @@
 use anyhow::{anyhow, Context, Result};
-use std::collections::{HashMap, HashSet};
+use aoc2022::dump::print_json;
+use aoc2022::render::svg::{self, Style, Svg};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::variants;
+use itertools::Itertools;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Position {
     x: isize,
     y: isize,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Map {
     width: isize,
     length: isize,
     heights: Vec<u32>,
+    /// The cost of moving in to each cell.
+    costs: Vec<isize>,
 }
 
 impl Map {
-    fn height_at(&self, pos: Position) -> Option<u32> {
+    fn idx(&self, pos: Position) -> Option<usize> {
         (pos.x >= 0 && pos.x < self.width && pos.y >= 0 && pos.y < self.length)
-            .then(|| self.heights[(pos.y * self.width + pos.x) as usize])
+            .then_some((pos.y * self.width + pos.x) as usize)
+    }
+
+    fn height_at(&self, pos: Position) -> Option<u32> {
+        self.idx(pos).map(|idx| self.heights[idx])
+    }
+
+    fn cost_at(&self, pos: Position) -> isize {
+        self.costs[self.idx(pos).unwrap()]
+    }
+
+    /// The total cost of following a route, excluding its first cell.
+    fn route_cost(&self, route: &[Position]) -> isize {
+        route.iter().skip(1).map(|pos| self.cost_at(*pos)).sum()
     }
 
     fn neighbours(&self, pos: Position) -> impl Iterator<Item = Position> + '_ {
@@ -35,6 +64,23 @@ impl Map {
             })
     }
 
+    /// Returns the neighbouring positions from which `pos` can be reached,
+    /// for searching backwards from the end.
+    fn reverse_neighbours(&self, pos: Position) -> impl Iterator<Item = Position> + '_ {
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .iter()
+            .map(move |(dx, dy)| Position {
+                x: pos.x + dx,
+                y: pos.y + dy,
+            })
+            .filter(move |neighbour_pos| {
+                match (self.height_at(pos), self.height_at(*neighbour_pos)) {
+                    (Some(height), Some(neighbour_height)) => height <= neighbour_height + 1,
+                    _ => false,
+                }
+            })
+    }
+
     fn lowest_points(&self) -> impl Iterator<Item = Position> + '_ {
         self.heights.iter().enumerate().filter_map(|(i, height)| {
             (*height == 0).then_some(Position {
@@ -46,6 +92,7 @@ impl Map {
 }
 
 fn parse_input(input: &str) -> Result<(Map, Position, Position)> {
+    let input = input.trim_end();
     let width = input.lines().next().context("Empty input")?.len();
     let length = input.lines().count();
 
@@ -53,7 +100,7 @@ fn parse_input(input: &str) -> Result<(Map, Position, Position)> {
     let mut end = None;
     let mut heights = vec![0; width * length];
 
-    for (y, line) in input.trim_end().lines().enumerate() {
+    for (y, line) in input.lines().enumerate() {
         if line.chars().count() != width {
             return Err(anyhow!(
                 "Input row {} has {} chars (expected {})",
@@ -85,7 +132,7 @@ fn parse_input(input: &str) -> Result<(Map, Position, Position)> {
                     });
                     heights[y * width + x] = 'z' as u32 - 'a' as u32;
                 }
-                c if ('a'..='z').contains(&c) => {
+                c if c.is_ascii_lowercase() => {
                     heights[y * width + x] = c as u32 - 'a' as u32;
                 }
                 c => return Err(anyhow!("Unexpected char {:?}", c)),
@@ -97,6 +144,7 @@ fn parse_input(input: &str) -> Result<(Map, Position, Position)> {
         Map {
             width: width as isize,
             length: length as isize,
+            costs: vec![1; heights.len()],
             heights,
         },
         start.context("No start position found")?,
@@ -104,73 +152,667 @@ fn parse_input(input: &str) -> Result<(Map, Position, Position)> {
     ))
 }
 
-fn a_star(start: Position, end: Position, map: &Map) -> Option<isize> {
-    #[derive(Debug, Clone, Copy)]
-    struct Cost {
+/// Parses a grid of digits from 1 to 9, the same size as the map, giving the
+/// cost of moving in to each cell.
+fn parse_costs(input: &str, map: &Map) -> Result<Vec<isize>> {
+    let costs = input
+        .trim_end()
+        .lines()
+        .enumerate()
+        .map(|(y, line)| {
+            if line.chars().count() == map.width as usize {
+                Ok(line.chars())
+            } else {
+                Err(anyhow!(
+                    "Cost row {} has {} chars (expected {})",
+                    y + 1,
+                    line.chars().count(),
+                    map.width
+                ))
+            }
+        })
+        .flatten_ok()
+        .map(|c| {
+            c.and_then(|c| match c.to_digit(10) {
+                Some(cost) if cost > 0 => Ok(cost as isize),
+                _ => Err(anyhow!("Invalid cost character: {:?}", c)),
+            })
+        })
+        .collect::<Result<Vec<isize>>>()?;
+
+    if costs.len() != map.heights.len() {
+        return Err(anyhow!(
+            "Costs have {} rows (expected {})",
+            costs.len() / map.width as usize,
+            map.length
+        ));
+    }
+
+    Ok(costs)
+}
+
+/// Finds the cheapest route from `start` to `end`, including both.
+fn a_star(start: Position, end: Position, map: &Map) -> Option<Vec<Position>> {
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    struct State {
         g: isize,
         h: isize,
+        pos: Position,
+    }
+
+    impl Ord for State {
+        fn cmp(&self, other: &Self) -> Ordering {
+            (other.g + other.h).cmp(&(self.g + self.h))
+        }
+    }
+
+    impl PartialOrd for State {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
     }
 
+    // As every move costs at least 1, the Manhattan distance never
+    // overestimates the remaining cost.
     fn h(pos: Position, end: Position) -> isize {
         (end.x - pos.x).abs() + (end.y - pos.y).abs()
     }
 
-    let mut open: HashMap<Position, Cost> = HashMap::default();
-    let mut closed: HashSet<Position> = HashSet::default();
-    open.insert(
-        start,
-        Cost {
-            g: 0,
-            h: h(start, end),
-        },
-    );
+    let mut open: BinaryHeap<State> = BinaryHeap::new();
+    open.push(State {
+        g: 0,
+        h: h(start, end),
+        pos: start,
+    });
+    let mut costs: FxHashMap<Position, isize> = FxHashMap::default();
+    costs.insert(start, 0);
+    let mut came_from: FxHashMap<Position, Position> = FxHashMap::default();
 
-    while let Some((current_pos, current_cost)) = open
-        .iter()
-        .min_by_key(|(_pos, cost)| cost.g + cost.h)
-        .map(|(pos, cost)| (*pos, *cost))
-    {
-        open.remove(&current_pos);
-        closed.insert(current_pos);
+    while let Some(current) = open.pop() {
+        if current.pos == end {
+            assert_eq!(current.h, 0);
 
-        if current_pos == end {
-            assert_eq!(current_cost.h, 0);
-            return Some(current_cost.g);
+            // Follow the predecessor links back to the start.
+            let mut route = vec![end];
+            while let Some(prev) = came_from.get(route.last().unwrap()) {
+                route.push(*prev);
+            }
+            route.reverse();
+            assert_eq!(map.route_cost(&route), current.g);
+            return Some(route);
+        }
+
+        // Skip stale entries for cells we have since found a shorter route to.
+        if current.g > costs[&current.pos] {
+            continue;
         }
 
         // Calculate the cost for each neighbouring cell and add to open list.
-        for neighbour in map
-            .neighbours(current_pos)
-            .filter(|neighbour| !closed.contains(neighbour))
-        {
-            let g = current_cost.g + 1;
-            let h = h(neighbour, end);
-            open.entry(neighbour)
-                .and_modify(|existing| {
-                    assert_eq!(h, existing.h);
-                    // If we've found a shorter route to an already discovered cell, update its cost.
-                    existing.g = g.min(existing.g);
-                })
-                .or_insert(Cost { g, h });
+        for neighbour in map.neighbours(current.pos) {
+            let g = current.g + map.cost_at(neighbour);
+            if g < *costs.entry(neighbour).or_insert(isize::MAX) {
+                *costs.get_mut(&neighbour).unwrap() = g;
+                came_from.insert(neighbour, current.pos);
+                open.push(State {
+                    g,
+                    h: h(neighbour, end),
+                    pos: neighbour,
+                });
+            }
         }
     }
 
     None
 }
 
+/// The result of a Dijkstra search backwards from the end, giving the cost of
+/// reaching the end from every cell and the next step to take from each.
+/// Cells which cannot reach the end have neither.
+struct ReverseSearch {
+    distances: Vec<Option<isize>>,
+    next: Vec<Option<Position>>,
+}
+
+impl ReverseSearch {
+    fn new(end: Position, map: &Map) -> Self {
+        #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+        struct State {
+            cost: isize,
+            pos: Position,
+        }
+
+        impl Ord for State {
+            fn cmp(&self, other: &Self) -> Ordering {
+                other.cost.cmp(&self.cost)
+            }
+        }
+
+        impl PartialOrd for State {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        let mut distances = vec![None; map.heights.len()];
+        let mut next = vec![None; map.heights.len()];
+        distances[map.idx(end).unwrap()] = Some(0);
+
+        let mut open = BinaryHeap::from([State { cost: 0, pos: end }]);
+        while let Some(State { cost, pos }) = open.pop() {
+            if Some(cost) > distances[map.idx(pos).unwrap()] {
+                continue;
+            }
+
+            // Moving from the neighbour to here costs this cell's cost.
+            let new_cost = cost + map.cost_at(pos);
+            for neighbour in map.reverse_neighbours(pos) {
+                let idx = map.idx(neighbour).unwrap();
+                if distances[idx].is_none_or(|existing| new_cost < existing) {
+                    distances[idx] = Some(new_cost);
+                    next[idx] = Some(pos);
+                    open.push(State {
+                        cost: new_cost,
+                        pos: neighbour,
+                    });
+                }
+            }
+        }
+
+        Self { distances, next }
+    }
+
+    fn distance(&self, map: &Map, pos: Position) -> Option<isize> {
+        self.distances[map.idx(pos).unwrap()]
+    }
+
+    /// Follows the links from `start` to the end, returning the route
+    /// including both.
+    fn route(&self, map: &Map, start: Position) -> Option<Vec<Position>> {
+        self.distance(map, start)?;
+
+        let mut route = vec![start];
+        while let Some(next) = self.next[map.idx(*route.last().unwrap()).unwrap()] {
+            route.push(next);
+        }
+        Some(route)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    AStar,
+    Reverse,
+}
+
+impl variants::Strategy for Strategy {
+    const ALL: &'static [Strategy] = &[Strategy::AStar, Strategy::Reverse];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::AStar => "a-star",
+            Strategy::Reverse => "reverse",
+        }
+    }
+}
+
+/// Finds the shortest routes for parts A and B.
+fn solve(
+    map: &Map,
+    start: Position,
+    end: Position,
+    strategy: Strategy,
+) -> Result<(Vec<Position>, Vec<Position>)> {
+    match strategy {
+        Strategy::AStar => {
+            let route_a = a_star(start, end, map).context("Failed to find path")?;
+            #[cfg(not(feature = "parallel"))]
+            let route_b = map
+                .lowest_points()
+                .filter_map(|start| a_star(start, end, map))
+                .min_by_key(|route| map.route_cost(route));
+            // Each search is independent, so run them in parallel.
+            #[cfg(feature = "parallel")]
+            let route_b = map
+                .lowest_points()
+                .collect::<Vec<Position>>()
+                .into_par_iter()
+                .filter_map(|start| a_star(start, end, map))
+                .min_by_key(|route| map.route_cost(route));
+            let route_b = route_b.context("Failed to find path")?;
+            Ok((route_a, route_b))
+        }
+        Strategy::Reverse => {
+            let search = ReverseSearch::new(end, map);
+            let route_a = search.route(map, start).context("Failed to find path")?;
+            let best_start = map
+                .lowest_points()
+                .filter(|start| search.distance(map, *start).is_some())
+                .min_by_key(|start| search.distance(map, *start))
+                .context("Failed to find path")?;
+            let route_b = search.route(map, best_start).unwrap();
+            Ok((route_a, route_b))
+        }
+    }
+}
+
+/// Draws the route over the height map, with an arrow on each cell of the
+/// route pointing in the direction of the next step.
+fn render_route(map: &Map, route: &[Position]) -> String {
+    let mut chars = map
+        .heights
+        .iter()
+        .map(|height| height_char(*height))
+        .collect::<Vec<char>>();
+
+    for (pos, next) in route.iter().tuple_windows() {
+        chars[map.idx(*pos).unwrap()] = match (next.x - pos.x, next.y - pos.y) {
+            (1, 0) => '>',
+            (-1, 0) => '<',
+            (0, 1) => 'v',
+            (0, -1) => '^',
+            _ => unreachable!(),
+        };
+    }
+    if let Some(end) = route.last() {
+        chars[map.idx(*end).unwrap()] = 'E';
+    }
+
+    chars
+        .chunks(map.width as usize)
+        .map(|row| row.iter().collect::<String>())
+        .join("\n")
+}
+
+/// Explains why the end may be unreachable from the start: how much of the map
+/// can be reached, the heights of the cells bordering that region which were
+/// too high to climb, and a drawing of the region.
+fn diagnose(map: &Map, start: Position, end: Position) -> String {
+    let mut reachable = vec![false; map.heights.len()];
+    reachable[map.idx(start).unwrap()] = true;
+    let mut open = VecDeque::from([start]);
+    while let Some(pos) = open.pop_front() {
+        for neighbour in map.neighbours(pos) {
+            let idx = map.idx(neighbour).unwrap();
+            if !reachable[idx] {
+                reachable[idx] = true;
+                open.push_back(neighbour);
+            }
+        }
+    }
+
+    // Cells next to the reachable region which can't themselves be reached,
+    // grouped by height.
+    let mut blocked = vec![false; map.heights.len()];
+    for (idx, _) in reachable.iter().enumerate().filter(|(_, r)| **r) {
+        let pos = Position {
+            x: idx as isize % map.width,
+            y: idx as isize / map.width,
+        };
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let neighbour = Position {
+                x: pos.x + dx,
+                y: pos.y + dy,
+            };
+            if let Some(neighbour_idx) = map.idx(neighbour) {
+                blocked[neighbour_idx] |= !reachable[neighbour_idx];
+            }
+        }
+    }
+    let blocked_heights = blocked
+        .iter()
+        .zip(&map.heights)
+        .filter(|(blocked, _)| **blocked)
+        .map(|(_, height)| *height)
+        .counts();
+
+    let mut report = format!(
+        "Reachable from start: {} of {} cells\n",
+        reachable.iter().filter(|r| **r).count(),
+        reachable.len()
+    );
+    report += &format!(
+        "End is {}reachable\n",
+        if reachable[map.idx(end).unwrap()] {
+            ""
+        } else {
+            "not "
+        }
+    );
+    report += &format!(
+        "Highest reachable height: {}\n",
+        reachable
+            .iter()
+            .zip(&map.heights)
+            .filter(|(reachable, _)| **reachable)
+            .map(|(_, height)| height_char(*height))
+            .max()
+            .unwrap()
+    );
+    report += &format!(
+        "Blocked by: {}\n",
+        blocked_heights
+            .iter()
+            .sorted()
+            .map(|(height, count)| format!("{} ({} cells)", height_char(*height), count))
+            .join(", ")
+    );
+
+    // Draw the reachable region, with the blocking cells marked by '#'.
+    for (y, row) in reachable.chunks(map.width as usize).enumerate() {
+        for (x, reachable) in row.iter().enumerate() {
+            let pos = Position {
+                x: x as isize,
+                y: y as isize,
+            };
+            let idx = map.idx(pos).unwrap();
+            report.push(if pos == start {
+                'S'
+            } else if pos == end {
+                'E'
+            } else if *reachable {
+                height_char(map.heights[idx])
+            } else if blocked[idx] {
+                '#'
+            } else {
+                '.'
+            });
+        }
+        report.push('\n');
+    }
+
+    report
+}
+
+/// The colour of a cell `dist` from the end, fading from red beside it to
+/// blue at the furthest cell.
+fn heat_colour(dist: isize, max_dist: isize) -> (u8, u8, u8) {
+    let t = dist as f64 / max_dist.max(1) as f64;
+    ((255.0 * (1.0 - t)) as u8, 0, (255.0 * t) as u8)
+}
+
+fn max_distance(search: &ReverseSearch) -> isize {
+    search
+        .distances
+        .iter()
+        .flatten()
+        .max()
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Draws each cell's distance from the end as a heatmap using ANSI 24-bit
+/// colour, fading from red near the end to blue far away. Cells which cannot
+/// reach the end are left uncoloured.
+fn render_heatmap(map: &Map, search: &ReverseSearch) -> String {
+    let max_dist = max_distance(search);
+
+    let mut heatmap = String::new();
+    for (row_dists, row_heights) in search
+        .distances
+        .chunks(map.width as usize)
+        .zip(map.heights.chunks(map.width as usize))
+    {
+        for (dist, height) in row_dists.iter().zip(row_heights) {
+            match dist {
+                Some(dist) => {
+                    let (red, green, blue) = heat_colour(*dist, max_dist);
+                    heatmap += &format!(
+                        "\x1b[48;2;{};{};{}m{}",
+                        red,
+                        green,
+                        blue,
+                        height_char(*height)
+                    );
+                }
+                None => heatmap += &format!("\x1b[0m{}", height_char(*height)),
+            }
+        }
+        heatmap += "\x1b[0m\n";
+    }
+
+    heatmap
+}
+
+/// Draws the heatmap as an SVG image, with cells that can't reach the end
+/// shaded grey by height, and `routes` drawn over it.
+fn render_heatmap_svg(map: &Map, search: &ReverseSearch, routes: &[&[Position]]) -> String {
+    let max_dist = max_distance(search);
+
+    let mut image = Svg::new();
+    for (i, (dist, height)) in search.distances.iter().zip(&map.heights).enumerate() {
+        let x = (i as isize % map.width) as f64;
+        let y = (i as isize / map.width) as f64;
+        let colour = match dist {
+            Some(dist) => {
+                let (red, green, blue) = heat_colour(*dist, max_dist);
+                svg::rgb(red, green, blue)
+            }
+            None => {
+                let shade = 64 + (*height * 6) as u8;
+                svg::rgb(shade, shade, shade)
+            }
+        };
+        image.rect(x, y, 1.0, 1.0, &Style::fill(colour));
+    }
+
+    for route in routes {
+        let centres = route
+            .iter()
+            .map(|pos| (pos.x as f64 + 0.5, pos.y as f64 + 0.5));
+        image.path(
+            &svg::Path::through(centres),
+            &Style::stroke("white").with_stroke_width(2.0),
+        );
+    }
+
+    image.finish(1.0, 8.0)
+}
+
+fn height_char(height: u32) -> char {
+    char::from_u32('a' as u32 + height).unwrap()
+}
+
+/// Checks that the end can be climbed onto from a neighbouring square, and
+/// the start climbed off, which would otherwise make the search fail.
+fn validate(map: &Map, start: Position, end: Position) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if map.reverse_neighbours(end).next().is_none() {
+        warnings.push(format!(
+            "The end at {},{} can't be reached from any neighbouring square",
+            end.x, end.y
+        ));
+    }
+    if map.neighbours(start).next().is_none() {
+        warnings.push(format!(
+            "The start at {},{} has no neighbouring square it can climb to",
+            start.x, start.y
+        ));
+    }
+    warnings
+}
+
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input12.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input12.txt".to_string();
+    let mut strategy = Strategy::Reverse;
+    let mut cross_check = false;
+    let mut show_routes = false;
+    let mut show_diagnostics = false;
+    let mut show_heatmap = false;
+    let mut heatmap_svg_path = None;
+    let mut costs_path = None;
 
-    let (map, start, end) = parse_input(&input).context("Error parsing input")?;
-    let result_a = a_star(start, end, &map).context("Failed to find path")?;
-    println!("Day 12, part A: {}", result_a);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strategy" => {
+                strategy =
+                    variants::parse_strategy(&args.next().context("--strategy requires a value")?)?;
+            }
+            "--cross-check" => cross_check = true,
+            "--route" => show_routes = true,
+            "--diagnose" => show_diagnostics = true,
+            "--heatmap" => show_heatmap = true,
+            "--heatmap-svg" => {
+                heatmap_svg_path = Some(args.next().context("--heatmap-svg requires a path")?);
+            }
+            "--costs" => costs_path = Some(args.next().context("--costs requires a path")?),
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
+
+    // Both routes are found together, so only parsing is timed separately.
+    let (mut map, start, end) =
+        timing::time(Stage::Parse, || parse_input(&input)).context("Error parsing input")?;
 
-    let result_b = map
-        .lowest_points()
-        .filter_map(|start| a_star(start, end, &map))
-        .min()
-        .context("Failed to find path")?;
+    if dump_parsed {
+        return Ok(print_json(&(&map, start, end))?);
+    }
+
+    if validate_only {
+        report_warnings(12, &validate(&map, start, end));
+        return Ok(());
+    }
+
+    if let Some(costs_path) = costs_path {
+        let costs_input = std::fs::read_to_string(&costs_path)?;
+        map.costs = parse_costs(&costs_input, &map)
+            .with_context(|| format!("Error parsing costs from {}", costs_path))?;
+    }
+
+    let (route_a, route_b) = match solve(&map, start, end, strategy) {
+        Ok(routes) => routes,
+        Err(e) if show_diagnostics => {
+            eprint!("{}", diagnose(&map, start, end));
+            return Err(e);
+        }
+        Err(e) => return Err(e.context("Run with --diagnose for more information")),
+    };
+    let result_a = map.route_cost(&route_a);
+    let result_b = map.route_cost(&route_b);
+    println!("Day 12, part A: {}", result_a);
+    if show_routes {
+        println!("{}", render_route(&map, &route_a));
+    }
     println!("Day 12, part B: {}", result_b);
+    if show_routes {
+        println!("{}", render_route(&map, &route_b));
+    }
+
+    if show_heatmap {
+        print!("{}", render_heatmap(&map, &ReverseSearch::new(end, &map)));
+    }
+    if let Some(path) = heatmap_svg_path {
+        let search = ReverseSearch::new(end, &map);
+        let image = render_heatmap_svg(&map, &search, &[&route_a, &route_b]);
+        std::fs::write(&path, image).with_context(|| format!("Error writing {:?}", path))?;
+    }
+
+    if cross_check {
+        variants::cross_check(12, strategy, &(result_a, result_b), |other| {
+            let (other_a, other_b) = solve(&map, start, end, other)?;
+            Ok::<_, anyhow::Error>((map.route_cost(&other_a), map.route_cost(&other_b)))
+        })?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2022::examples;
+    use aoc2022::variants::Strategy as _;
+
+    fn example() -> (Map, Position, Position) {
+        parse_input(examples::for_day(12).next().unwrap().input).unwrap()
+    }
+
+    /// The costs of part A's and part B's routes.
+    fn route_costs(
+        map: &Map,
+        start: Position,
+        end: Position,
+        strategy: Strategy,
+    ) -> (isize, isize) {
+        let (route_a, route_b) = solve(map, start, end, strategy).unwrap();
+        (map.route_cost(&route_a), map.route_cost(&route_b))
+    }
+
+    #[test]
+    fn every_strategy_solves_the_example() {
+        for example in examples::for_day(12) {
+            let (map, start, end) = parse_input(example.input).unwrap();
+            for &strategy in Strategy::ALL {
+                let (result_a, result_b) = route_costs(&map, start, end, strategy);
+                assert_eq!(
+                    Some(result_a.to_string().as_str()),
+                    example.part_a,
+                    "strategy {}",
+                    strategy.name()
+                );
+                assert_eq!(
+                    Some(result_b.to_string().as_str()),
+                    example.part_b,
+                    "strategy {}",
+                    strategy.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn strategies_agree_with_varied_costs() {
+        let (mut map, start, end) = example();
+        let costs = (0..map.length)
+            .map(|y| {
+                (0..map.width)
+                    .map(|x| char::from_digit(((x * 7 + y * 3) % 9 + 1) as u32, 10).unwrap())
+                    .collect::<String>()
+            })
+            .join("\n");
+        map.costs = parse_costs(&costs, &map).unwrap();
+
+        let expected = route_costs(&map, start, end, Strategy::Reverse);
+        // Every step costs at least 1, and most cost more.
+        assert!(expected.0 > 31 && expected.1 > 29);
+        for &strategy in Strategy::ALL {
+            assert_eq!(
+                route_costs(&map, start, end, strategy),
+                expected,
+                "strategy {}",
+                strategy.name()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_costs_rejects_bad_grids() {
+        let (map, _, _) = example();
+        let row = "1".repeat(map.width as usize);
+        let rows = |n: usize| vec![row.as_str(); n].join("\n");
+        assert_eq!(parse_costs(&rows(5), &map).unwrap(), vec![1; 40]);
+        assert!(parse_costs(&rows(4), &map).is_err());
+        assert!(parse_costs(&rows(6), &map).is_err());
+        assert!(parse_costs(&rows(5).replacen('1', "11", 1), &map).is_err());
+        assert!(parse_costs(&rows(5).replacen('1', "0", 1), &map).is_err());
+    }
+
+    #[test]
+    fn ignores_trailing_blank_lines() {
+        let example = examples::for_day(12).next().unwrap().input;
+        let (map, start, end) = parse_input(&format!("{}\n\n", example)).unwrap();
+        assert_eq!(map.length, 5);
+        assert_eq!(map.lowest_points().count(), 6);
+        let (route_a, route_b) = solve(&map, start, end, Strategy::Reverse).unwrap();
+        assert_eq!(map.route_cost(&route_a), 31);
+        assert_eq!(map.route_cost(&route_b), 29);
+    }
+}
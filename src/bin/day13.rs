@@ -1,103 +1,767 @@
 use std::cmp::Ordering;
+use std::fmt;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::{eol, eol_or_eof, parse_all};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::variants;
 use nom::{
-    branch::alt,
     bytes::complete::tag,
-    character::complete::{digit1, newline},
-    combinator::{cut, map, map_res},
-    multi::{separated_list0, separated_list1},
-    sequence::{delimited, pair, terminated},
+    character::complete::digit1,
+    combinator::{cut, map_res},
+    error::ErrorKind,
+    multi::separated_list1,
+    sequence::terminated,
     IResult,
 };
+#[cfg(feature = "serde")]
+use serde_json::Value;
 
-#[derive(Debug, Clone, Eq)]
-enum Data {
+/// A number or list within a packet. The items of a list are stored
+/// contiguously in an `Arena`, from `start`.
+#[derive(Debug, Clone, Copy)]
+enum Node {
     Number(usize),
-    List(Vec<Data>),
+    List { start: usize, len: usize },
 }
 
-impl PartialEq for Data {
+/// Holds the nodes of many packets in one `Vec`, rather than a `Vec` per
+/// list.
+#[derive(Debug, Default)]
+struct Arena {
+    nodes: Vec<Node>,
+    /// The items of lists still being built, which can't be added to `nodes`
+    /// until the list is complete as their own items would be interleaved.
+    pending: Vec<Node>,
+}
+
+impl Arena {
+    /// Moves the items pending from `mark` onwards into a new list.
+    fn end_list(&mut self, mark: usize) -> Node {
+        let start = self.nodes.len();
+        self.nodes.extend(self.pending.drain(mark..));
+        Node::List {
+            start,
+            len: self.nodes.len() - start,
+        }
+    }
+
+    fn packet(&self, node: Node) -> Packet<'_> {
+        Packet {
+            nodes: &self.nodes,
+            node,
+        }
+    }
+
+    fn pairs(&self, pairs: &[(Node, Node)]) -> Vec<(Packet<'_>, Packet<'_>)> {
+        pairs
+            .iter()
+            .map(|(left, right)| (self.packet(*left), self.packet(*right)))
+            .collect()
+    }
+}
+
+/// A packet, or part of one, along with the arena holding its items.
+#[derive(Debug, Clone, Copy)]
+struct Packet<'a> {
+    nodes: &'a [Node],
+    node: Node,
+}
+
+impl<'a> Packet<'a> {
+    /// A list of just `items`, which must be numbers.
+    fn list(items: &'a [Node]) -> Self {
+        Packet {
+            nodes: items,
+            node: Node::List {
+                start: 0,
+                len: items.len(),
+            },
+        }
+    }
+
+    fn items(self) -> impl Iterator<Item = Packet<'a>> {
+        let items = match self.node {
+            Node::Number(_) => &[],
+            Node::List { start, len } => &self.nodes[start..start + len],
+        };
+        items.iter().map(move |node| Packet {
+            nodes: self.nodes,
+            node: *node,
+        })
+    }
+}
+
+/// Packets serialize back into the nested lists they were parsed from, which
+/// `--parser json` can read again. They can't be deserialized directly, as
+/// their items live in an arena.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Packet<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.node {
+            Node::Number(n) => serializer.serialize_u64(n as u64),
+            Node::List { .. } => serializer.collect_seq(self.items()),
+        }
+    }
+}
+
+impl PartialEq for Packet<'_> {
     fn eq(&self, other: &Self) -> bool {
         self.cmp(other).is_eq()
     }
 }
 
-impl PartialOrd for Data {
+impl Eq for Packet<'_> {}
+
+impl PartialOrd for Packet<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Data {
+impl Ord for Packet<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
-        match (self, other) {
-            (Data::Number(lhs), Data::Number(rhs)) => lhs.cmp(rhs),
-            (Data::List(lhs), Data::List(rhs)) => lhs.cmp(rhs),
-            (Data::Number(lhs), Data::List(rhs)) => vec![Data::Number(*lhs)].cmp(rhs),
-            (Data::List(lhs), Data::Number(rhs)) => lhs.cmp(&vec![Data::Number(*rhs)]),
+        compare(*self, *other, 0, &mut |_, _| ())
+    }
+}
+
+/// A step taken while comparing two packets, as described in the puzzle.
+enum Step<'a> {
+    Compare(Packet<'a>, Packet<'a>),
+    ConvertLeft(Packet<'a>),
+    ConvertRight(Packet<'a>),
+    LeftSmaller,
+    RightSmaller,
+    LeftRanOut,
+    RightRanOut,
+}
+
+impl fmt::Display for Step<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Compare(lhs, rhs) => write!(f, "Compare {} vs {}", lhs, rhs),
+            Step::ConvertLeft(converted) => write!(
+                f,
+                "Mixed types; convert left to {} and retry comparison",
+                converted
+            ),
+            Step::ConvertRight(converted) => write!(
+                f,
+                "Mixed types; convert right to {} and retry comparison",
+                converted
+            ),
+            Step::LeftSmaller => {
+                write!(f, "Left side is smaller, so inputs are in the right order")
+            }
+            Step::RightSmaller => write!(
+                f,
+                "Right side is smaller, so inputs are not in the right order"
+            ),
+            Step::LeftRanOut => write!(
+                f,
+                "Left side ran out of items, so inputs are in the right order"
+            ),
+            Step::RightRanOut => write!(
+                f,
+                "Right side ran out of items, so inputs are not in the right order"
+            ),
+        }
+    }
+}
+
+/// Compares two packets, calling `report` with the nesting depth of each step
+/// taken along the way.
+fn compare(
+    lhs: Packet,
+    rhs: Packet,
+    depth: usize,
+    report: &mut impl FnMut(usize, &Step),
+) -> Ordering {
+    report(depth, &Step::Compare(lhs, rhs));
+    match (lhs.node, rhs.node) {
+        (Node::Number(l), Node::Number(r)) => {
+            let ordering = l.cmp(&r);
+            match ordering {
+                Ordering::Less => report(depth + 1, &Step::LeftSmaller),
+                Ordering::Greater => report(depth + 1, &Step::RightSmaller),
+                Ordering::Equal => (),
+            }
+            ordering
+        }
+        (Node::List { len: l, .. }, Node::List { len: r, .. }) => {
+            for (l, r) in lhs.items().zip(rhs.items()) {
+                let ordering = compare(l, r, depth + 1, report);
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            }
+
+            let ordering = l.cmp(&r);
+            match ordering {
+                Ordering::Less => report(depth + 1, &Step::LeftRanOut),
+                Ordering::Greater => report(depth + 1, &Step::RightRanOut),
+                Ordering::Equal => (),
+            }
+            ordering
+        }
+        (Node::Number(_), Node::List { .. }) => {
+            let items = [lhs.node];
+            let converted = Packet::list(&items);
+            report(depth + 1, &Step::ConvertLeft(converted));
+            compare(converted, rhs, depth + 1, report)
+        }
+        (Node::List { .. }, Node::Number(_)) => {
+            let items = [rhs.node];
+            let converted = Packet::list(&items);
+            report(depth + 1, &Step::ConvertRight(converted));
+            compare(lhs, converted, depth + 1, report)
         }
     }
 }
 
+/// Describes how the packets in a pair are compared, in the style of the
+/// puzzle's worked example.
+fn explain(index: usize, pair: &(Packet, Packet)) -> String {
+    let mut out = format!("== Pair {} ==\n", index);
+    compare(pair.0, pair.1, 0, &mut |depth, step| {
+        out.push_str(&format!("{}- {}\n", "  ".repeat(depth), step));
+    });
+    out
+}
+
+impl fmt::Display for Packet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.node {
+            Node::Number(n) => write!(f, "{}", n),
+            Node::List { .. } => {
+                write!(f, "[")?;
+                for (i, item) in self.items().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// Adds a packet parsed as JSON to `arena`.
+#[cfg(feature = "serde")]
+fn from_json(value: &Value, arena: &mut Arena) -> Result<Node> {
+    match value {
+        Value::Number(n) => Ok(Node::Number(
+            n.as_u64()
+                .with_context(|| format!("{} is not a non-negative integer", n))?
+                as usize,
+        )),
+        Value::Array(items) => {
+            let mark = arena.pending.len();
+            for item in items {
+                let item = from_json(item, arena)?;
+                arena.pending.push(item);
+            }
+            Ok(arena.end_list(mark))
+        }
+        _ => Err(anyhow!("Expected a number or list, got {}", value)),
+    }
+}
+
+/// Parses packets as a stream of JSON values, so any whitespace between or
+/// within them is accepted. Like the nom parser, `serde_json` rejects packets
+/// nested more than `MAX_DEPTH` levels deep.
+#[cfg(feature = "serde")]
+fn parse_input_json(input: &str, arena: &mut Arena) -> Result<Vec<(Node, Node)>> {
+    let packets = serde_json::Deserializer::from_str(input)
+        .into_iter::<Value>()
+        .map(|value| from_json(&value?, arena))
+        .collect::<Result<Vec<_>>>()?;
+
+    if packets.len() % 2 != 0 {
+        return Err(anyhow!("Odd number of packets: {}", packets.len()));
+    }
+    Ok(packets
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect())
+}
+
+#[cfg(not(feature = "serde"))]
+fn parse_input_json(_input: &str, _arena: &mut Arena) -> Result<Vec<(Node, Node)>> {
+    Err(anyhow!("The JSON parser requires the serde feature"))
+}
+
+/// Checks the JSON parser agrees with the nom parser, and that displaying
+/// each packet reproduces its line of the input. Packets are compared as
+/// text because `Packet`'s equality treats `1` and `[1]` as equal.
+fn cross_check(input: &str) -> Result<()> {
+    let mut arena = Arena::default();
+    let pairs = parse_all(parse_input(&mut arena), input)?;
+    let pairs = arena.pairs(&pairs);
+    let mut json_arena = Arena::default();
+    let json_pairs = parse_input_json(input, &mut json_arena)?;
+    let json_pairs = json_arena.pairs(&json_pairs);
+    if json_pairs.len() != pairs.len() {
+        return Err(anyhow!(
+            "JSON parser found {} pairs but nom found {}",
+            json_pairs.len(),
+            pairs.len()
+        ));
+    }
+
+    let packets = pairs.iter().flat_map(|pair| [pair.0, pair.1]);
+    let json_packets = json_pairs.iter().flat_map(|pair| [pair.0, pair.1]);
+    let lines = input.lines().filter(|line| !line.is_empty());
+    for (i, ((packet, json_packet), line)) in packets.zip(json_packets).zip(lines).enumerate() {
+        if packet.to_string() != json_packet.to_string() {
+            return Err(anyhow!(
+                "Packet {}: nom parsed {} but JSON parsed {}",
+                i + 1,
+                packet,
+                json_packet
+            ));
+        }
+        if packet.to_string() != line {
+            return Err(anyhow!(
+                "Packet {}: {} does not round trip, got {}",
+                i + 1,
+                line,
+                packet
+            ));
+        }
+    }
+
+    println!("Day 13: parsers agree and packets round trip");
+    Ok(())
+}
+
 fn parse_usize(input: &str) -> IResult<&str, usize> {
     map_res(digit1, |s: &str| s.parse::<usize>())(input)
 }
 
-fn parse_list(input: &str) -> IResult<&str, Vec<Data>> {
-    delimited(tag("["), separated_list0(tag(","), parse_data), tag("]"))(input)
+/// The deepest lists may be nested, matching `serde_json`, which rejects 128
+/// levels. Comparing and displaying packets recurse, so much deeper packets
+/// would overflow the stack.
+const MAX_DEPTH: usize = 127;
+
+/// Parses a number or list into `arena`. Lists nested more than `MAX_DEPTH`
+/// deep are rejected.
+fn parse_data<'a>(input: &'a str, arena: &mut Arena) -> IResult<&'a str, Node> {
+    parse_nested_data(input, arena, 0)
 }
 
-fn parse_data(input: &str) -> IResult<&str, Data> {
-    alt((map(parse_usize, Data::Number), map(parse_list, Data::List)))(input)
+fn parse_nested_data<'a>(
+    input: &'a str,
+    arena: &mut Arena,
+    depth: usize,
+) -> IResult<&'a str, Node> {
+    if let Ok((input, n)) = parse_usize(input) {
+        return Ok((input, Node::Number(n)));
+    }
+
+    let (mut input, _) = tag("[")(input)?;
+    if depth == MAX_DEPTH {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            ErrorKind::TooLarge,
+        )));
+    }
+    let mark = arena.pending.len();
+    let mut item = parse_nested_data(input, arena, depth + 1);
+    while let Ok((rest, node)) = item {
+        arena.pending.push(node);
+        input = rest;
+        item = match input.strip_prefix(',') {
+            Some(rest) => parse_nested_data(rest, arena, depth + 1),
+            None => break,
+        };
+    }
+    if let Err(e @ nom::Err::Failure(_)) = item {
+        arena.pending.truncate(mark);
+        return Err(e);
+    }
+    match tag("]")(input) {
+        Ok((input, _)) => Ok((input, arena.end_list(mark))),
+        Err(e) => {
+            arena.pending.truncate(mark);
+            Err(e)
+        }
+    }
 }
 
-fn parse_input(input: &str) -> IResult<&str, Vec<(Data, Data)>> {
-    separated_list1(
-        newline,
-        cut(pair(
-            terminated(parse_data, newline),
-            terminated(parse_data, newline),
-        )),
-    )(input)
+fn parse_input(arena: &mut Arena) -> impl FnMut(&str) -> IResult<&str, Vec<(Node, Node)>> + '_ {
+    move |input| {
+        separated_list1(
+            eol,
+            cut(|input| {
+                let (input, left) = terminated(|input| parse_data(input, arena), eol)(input)?;
+                let (input, right) =
+                    terminated(|input| parse_data(input, arena), eol_or_eof)(input)?;
+                Ok((input, (left, right)))
+            }),
+        )(input)
+    }
+}
+
+/// How part B finds where the divider packets would be sorted to.
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    /// Sort every packet along with the dividers.
+    Sort,
+    /// Count the packets which sort before each divider.
+    Count,
+}
+
+impl Strategy {
+    fn part_b(&self, pairs: &[(Packet, Packet)]) -> Result<usize> {
+        let mut arena = Arena::default();
+        let divider_a = parse_data("[[2]]", &mut arena)?.1;
+        let divider_b = parse_data("[[6]]", &mut arena)?.1;
+        let divider_a = arena.packet(divider_a);
+        let divider_b = arena.packet(divider_b);
+        let packets = pairs.iter().flat_map(|pair| [pair.0, pair.1]);
+
+        let (divider_a_pos, divider_b_pos) = match self {
+            Strategy::Sort => {
+                let mut all_packets = packets.collect::<Vec<_>>();
+                all_packets.push(divider_a);
+                all_packets.push(divider_b);
+
+                all_packets.sort();
+                let divider_a_pos = all_packets
+                    .iter()
+                    .position(|packet| *packet == divider_a)
+                    .unwrap();
+                let divider_b_pos = all_packets
+                    .iter()
+                    .position(|packet| *packet == divider_b)
+                    .unwrap();
+                (divider_a_pos, divider_b_pos)
+            }
+            Strategy::Count => {
+                // The first divider sorts before the second, so also comes
+                // before it.
+                let divider_a_pos = packets.clone().filter(|packet| *packet < divider_a).count();
+                let divider_b_pos = packets.filter(|packet| *packet < divider_b).count() + 1;
+                (divider_a_pos, divider_b_pos)
+            }
+        };
+
+        Ok((divider_a_pos + 1) * (divider_b_pos + 1))
+    }
+}
+
+impl variants::Strategy for Strategy {
+    const ALL: &'static [Strategy] = &[Strategy::Sort, Strategy::Count];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Sort => "sort",
+            Strategy::Count => "count",
+        }
+    }
+}
+
+/// Checks that neither packet in a pair equals the other, as their order would
+/// be undefined, and that no packet equals a divider packet.
+fn validate(pairs: &[(Packet, Packet)]) -> Result<Vec<String>> {
+    let mut arena = Arena::default();
+    let dividers = [
+        parse_data("[[2]]", &mut arena)?.1,
+        parse_data("[[6]]", &mut arena)?.1,
+    ]
+    .map(|divider| arena.packet(divider));
+    let mut warnings = Vec::new();
+    for (i, (left, right)) in pairs.iter().enumerate() {
+        if left == right {
+            warnings.push(format!(
+                "Pair {}: the packets are equal, so their order is undefined",
+                i + 1
+            ));
+        }
+        for packet in [left, right] {
+            if dividers.contains(packet) {
+                warnings.push(format!("Pair {}: {} is a divider packet", i + 1, packet));
+            }
+        }
+    }
+    Ok(warnings)
 }
 
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input13.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input13.txt".to_string();
+    let mut json = false;
+    let mut check = false;
+    let mut strategy = Strategy::Count;
+    let mut explain_pair = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--parser" => match args.next().context("--parser requires a value")?.as_str() {
+                "nom" => json = false,
+                "json" => json = true,
+                parser => {
+                    return Err(anyhow!(
+                        "Unknown parser {:?} (expected nom or json)",
+                        parser
+                    ))
+                }
+            },
+            "--strategy" => {
+                strategy =
+                    variants::parse_strategy(&args.next().context("--strategy requires a value")?)?
+            }
+            "--cross-check" => check = true,
+            "--explain" => {
+                explain_pair = Some(
+                    args.next()
+                        .context("--explain requires a pair number")?
+                        .parse::<usize>()?,
+                )
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
 
-    let pairs = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
 
-    let result_a = pairs
-        .iter()
-        .enumerate()
-        .filter_map(|(i, pair)| (pair.0 <= pair.1).then_some(i + 1))
-        .sum::<usize>();
+    let mut arena = Arena::default();
+    let pairs = timing::time(Stage::Parse, || {
+        if json {
+            parse_input_json(&input, &mut arena)
+        } else {
+            Ok(parse_all(parse_input(&mut arena), &input)?)
+        }
+    })?;
+    let pairs = arena.pairs(&pairs);
+
+    if dump_parsed {
+        return Ok(print_json(&pairs)?);
+    }
+
+    if validate_only {
+        report_warnings(13, &validate(&pairs)?);
+        return Ok(());
+    }
+
+    if check {
+        cross_check(&input)?;
+    }
+
+    if let Some(index) = explain_pair {
+        let pair = index
+            .checked_sub(1)
+            .and_then(|i| pairs.get(i))
+            .with_context(|| format!("No pair {}, there are {}", index, pairs.len()))?;
+        print!("{}", explain(index, pair));
+        return Ok(());
+    }
+
+    let result_a = timing::time(Stage::PartA, || {
+        pairs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pair)| (pair.0 <= pair.1).then_some(i + 1))
+            .sum::<usize>()
+    });
     println!("Day 13, part A: {}", result_a);
 
-    let divider_a = parse_data("[[2]]")?.1;
-    let divider_b = parse_data("[[6]]")?.1;
-    let mut pairs = pairs;
-    let mut all_packets = pairs
-        .drain(..)
-        .flat_map(|pair| [pair.0, pair.1])
-        .collect::<Vec<Data>>();
-    all_packets.push(divider_a.clone());
-    all_packets.push(divider_b.clone());
-
-    all_packets.sort();
-    let divider_a_pos = all_packets
-        .iter()
-        .position(|packet| packet == &divider_a)
-        .unwrap();
-    let divider_b_pos = all_packets
-        .iter()
-        .position(|packet| packet == &divider_b)
-        .unwrap();
-
-    let result_b = (divider_a_pos + 1) * (divider_b_pos + 1);
+    let result_b = timing::time(Stage::PartB, || strategy.part_b(&pairs))?;
     println!("Day 13, part B: {}", result_b);
 
+    if check {
+        variants::cross_check(13, strategy, &result_b, |other| other.part_b(&pairs))?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2022::examples;
+    use proptest::prelude::{prop, prop_assert, prop_assert_eq, proptest};
+    use proptest::strategy::Strategy as _;
+
+    /// A packet as a plain tree, to generate packets from and to compare them
+    /// by following the puzzle's rules directly.
+    #[derive(Clone, Debug)]
+    enum Data {
+        Number(usize),
+        List(Vec<Data>),
+    }
+
+    impl fmt::Display for Data {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Data::Number(n) => write!(f, "{}", n),
+                Data::List(items) => {
+                    let items = items.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    write!(f, "[{}]", items.join(","))
+                }
+            }
+        }
+    }
+
+    fn reference_cmp(lhs: &Data, rhs: &Data) -> Ordering {
+        match (lhs, rhs) {
+            (Data::Number(l), Data::Number(r)) => l.cmp(r),
+            (Data::List(l), Data::List(r)) => l
+                .iter()
+                .zip(r)
+                .map(|(l, r)| reference_cmp(l, r))
+                .find(|order| order.is_ne())
+                .unwrap_or_else(|| l.len().cmp(&r.len())),
+            (Data::Number(_), Data::List(_)) => reference_cmp(&Data::List(vec![lhs.clone()]), rhs),
+            (Data::List(_), Data::Number(_)) => reference_cmp(lhs, &Data::List(vec![rhs.clone()])),
+        }
+    }
+
+    /// Packets with small numbers and short lists, so that they often compare
+    /// equal up to number-to-list promotion, where the ordering is subtlest.
+    fn packet() -> impl proptest::strategy::Strategy<Value = Data> {
+        let data = (0..3usize)
+            .prop_map(Data::Number)
+            .prop_recursive(4, 32, 3, |inner| {
+                prop::collection::vec(inner, 0..3).prop_map(Data::List)
+            });
+        prop::collection::vec(data, 0..3).prop_map(Data::List)
+    }
+
+    fn parse(data: &[Data], arena: &mut Arena) -> Vec<Node> {
+        data.iter()
+            .map(|data| parse_data(&data.to_string(), arena).unwrap().1)
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn order_matches_the_puzzle_rules(a in packet(), b in packet()) {
+            let mut arena = Arena::default();
+            let nodes = parse(&[a.clone(), b.clone()], &mut arena);
+            let (pa, pb) = (arena.packet(nodes[0]), arena.packet(nodes[1]));
+            prop_assert_eq!(pa.cmp(&pb), reference_cmp(&a, &b));
+        }
+
+        #[test]
+        fn order_is_antisymmetric(a in packet(), b in packet()) {
+            let mut arena = Arena::default();
+            let nodes = parse(&[a, b], &mut arena);
+            let (a, b) = (arena.packet(nodes[0]), arena.packet(nodes[1]));
+            prop_assert_eq!(a.cmp(&a), Ordering::Equal);
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            prop_assert_eq!(a == b, a.cmp(&b).is_eq());
+            prop_assert_eq!(a.partial_cmp(&b), Some(a.cmp(&b)));
+        }
+
+        #[test]
+        fn order_is_transitive(a in packet(), b in packet(), c in packet()) {
+            let mut arena = Arena::default();
+            let nodes = parse(&[a, b, c], &mut arena);
+            let (a, b, c) = (arena.packet(nodes[0]), arena.packet(nodes[1]), arena.packet(nodes[2]));
+            if a <= b && b <= c {
+                prop_assert!(a <= c, "{} <= {} <= {} but {} > {}", a, b, c, a, c);
+            }
+            if a >= b && b >= c {
+                prop_assert!(a >= c, "{} >= {} >= {} but {} < {}", a, b, c, a, c);
+            }
+        }
+
+        #[test]
+        fn sorting_agrees_with_the_puzzle_rules(data in prop::collection::vec(packet(), 0..16)) {
+            let mut arena = Arena::default();
+            let nodes = parse(&data, &mut arena);
+            let mut order = (0..data.len()).collect::<Vec<_>>();
+            order.sort_by_key(|&i| arena.packet(nodes[i]));
+            for pair in order.windows(2) {
+                let (a, b) = (&data[pair[0]], &data[pair[1]]);
+                prop_assert!(reference_cmp(a, b).is_le(), "{} sorted before {}", a, b);
+            }
+        }
+
+        #[test]
+        fn count_matches_sort(data in prop::collection::vec((packet(), packet()), 0..16)) {
+            let mut arena = Arena::default();
+            let data = data.into_iter().flat_map(|(a, b)| [a, b]).collect::<Vec<_>>();
+            let nodes = parse(&data, &mut arena);
+            let pairs = nodes
+                .chunks_exact(2)
+                .map(|pair| (arena.packet(pair[0]), arena.packet(pair[1])))
+                .collect::<Vec<_>>();
+            prop_assert_eq!(
+                Strategy::Count.part_b(&pairs).unwrap(),
+                Strategy::Sort.part_b(&pairs).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn count_matches_sort_on_the_examples() {
+        for example in examples::for_day(13) {
+            let mut arena = Arena::default();
+            let pairs = parse_all(parse_input(&mut arena), example.input).unwrap();
+            let pairs = arena.pairs(&pairs);
+            let result = Strategy::Count.part_b(&pairs).unwrap();
+            assert_eq!(result, Strategy::Sort.part_b(&pairs).unwrap());
+            assert_eq!(Some(result.to_string().as_str()), example.part_b);
+        }
+    }
+
+    fn nested(depth: usize) -> String {
+        format!("{}1{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_parser_round_trips_the_examples() {
+        for example in examples::for_day(13) {
+            cross_check(example.input).unwrap();
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_parser_accepts_nesting_up_to_the_limit() {
+        cross_check(&format!("{}\n{}\n", nested(MAX_DEPTH), nested(1))).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_parser_rejects_nesting_beyond_the_limit() {
+        let input = format!("{}\n{}\n", nested(MAX_DEPTH + 1), nested(1));
+        assert!(parse_input_json(&input, &mut Arena::default()).is_err());
+    }
+
+    #[test]
+    fn rejects_nesting_beyond_the_limit() {
+        let mut arena = Arena::default();
+        let input = format!("{}\n{}\n", nested(MAX_DEPTH), nested(1));
+        let pairs = parse_all(parse_input(&mut arena), &input).unwrap();
+        assert_eq!(arena.pairs(&pairs)[0].0.to_string(), nested(MAX_DEPTH));
+
+        // Deep enough to overflow the stack if parsing didn't stop early.
+        for depth in [MAX_DEPTH + 1, 200_000] {
+            let input = format!("{}\n{}\n", nested(depth), nested(1));
+            assert!(parse_all(parse_input(&mut Arena::default()), &input).is_err());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_parser_accepts_whitespace() {
+        let mut arena = Arena::default();
+        let pairs = parse_input_json("[ 1,\n[2 ] ]  [\t]\n", &mut arena).unwrap();
+        let pairs = arena.pairs(&pairs);
+        assert_eq!(pairs[0].0.to_string(), "[1,[2]]");
+        assert_eq!(pairs[0].1.to_string(), "[]");
+    }
+}
@@ -1,41 +1,72 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::{eol, parse_all};
+use aoc2022::render::animation::Animation;
+use aoc2022::render::svg::{Style, Svg};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::variants;
 use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
-    character::complete::{digit1, newline},
+    character::complete::digit1,
     combinator::{map, map_res},
     multi::separated_list1,
     sequence::separated_pair,
     IResult,
 };
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
 
 const SAND_SOURCE: Position = Position { x: 500, y: 0 };
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Position {
     x: isize,
     y: isize,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Tile {
+    Air,
+    Rock,
+    Sand,
+}
+
+impl Tile {
+    fn is_occupied(&self) -> bool {
+        *self != Tile::Air
+    }
+}
+
 #[derive(Clone)]
 struct Map {
     left: isize,
     top: isize,
     right: isize,
     bottom: isize,
-    tiles: Vec<bool>,
+    /// If set, an infinitely wide floor lies at this y coordinate. The map
+    /// grows horizontally on demand as sand spreads out across the floor.
+    floor: Option<isize>,
+    tiles: Vec<Tile>,
 }
 
 impl Map {
-    fn new(rocks: &[Vec<Position>]) -> Result<Self> {
+    fn new(rocks: &[Vec<Position>], sources: &[Position], has_floor: bool) -> Result<Self> {
         // Find the edges of our map so we can allocate as small a vector as
         // possible for the tiles.
-        let mut left = SAND_SOURCE.x;
-        let mut top = SAND_SOURCE.y;
-        let mut right = SAND_SOURCE.x;
-        let mut bottom = SAND_SOURCE.y;
+        let first = sources
+            .first()
+            .context("At least one sand source is required")?;
+        let mut left = first.x;
+        let mut top = first.y;
+        let mut right = first.x;
+        let mut bottom = first.y;
 
-        for pos in rocks.iter().flatten() {
+        for pos in rocks.iter().flatten().chain(sources) {
             left = left.min(pos.x);
             top = top.min(pos.y);
             right = right.max(pos.x);
@@ -44,14 +75,22 @@ impl Map {
         assert!(right >= left);
         assert!(bottom >= top);
 
+        // The floor lies 2 tiles below the lowest rock. Sand can settle on
+        // the row directly above it, so the map must extend that far.
+        let floor = has_floor.then_some(bottom + 2);
+        if let Some(floor) = floor {
+            bottom = floor - 1;
+        }
+
         let mut map = Self {
             left,
             top,
             bottom,
             right,
+            floor,
             tiles: Vec::new(),
         };
-        map.tiles = vec![false; (map.width() * map.height()) as usize];
+        map.tiles = vec![Tile::Air; (map.width() * map.height()) as usize];
 
         for path in rocks {
             for (start, end) in path.iter().tuple_windows() {
@@ -65,11 +104,11 @@ impl Map {
 
                 let mut cur = *start;
                 while cur != *end {
-                    *map.tile_mut(cur).unwrap() = true;
+                    *map.tile_mut(cur).unwrap() = Tile::Rock;
                     cur.x += (end.x - cur.x).signum();
                     cur.y += (end.y - cur.y).signum();
                 }
-                *map.tile_mut(*end).unwrap() = true;
+                *map.tile_mut(*end).unwrap() = Tile::Rock;
             }
         }
 
@@ -89,15 +128,61 @@ impl Map {
             .then_some(((pos.y - self.top) * self.width() + pos.x - self.left) as usize)
     }
 
-    fn tile(&self, pos: Position) -> Option<&bool> {
-        let idx = self.tile_idx(pos);
-        idx.map(|idx| &self.tiles[idx])
+    fn tile(&self, pos: Position) -> Option<&Tile> {
+        match (self.tile_idx(pos), self.floor) {
+            (Some(idx), _) => Some(&self.tiles[idx]),
+            (None, Some(floor)) if pos.y == floor => Some(&Tile::Rock),
+            // Tiles beyond the allocated width are empty when there is a
+            // floor, as the map extends infinitely to either side.
+            (None, Some(_)) if pos.y >= self.top && pos.y <= self.bottom => Some(&Tile::Air),
+            (None, _) => None,
+        }
     }
 
-    fn tile_mut(&mut self, pos: Position) -> Option<&mut bool> {
+    fn tile_mut(&mut self, pos: Position) -> Option<&mut Tile> {
+        if self.floor.is_some() && pos.y >= self.top && pos.y <= self.bottom {
+            self.grow_to_include(pos.x);
+        }
         let idx = self.tile_idx(pos);
         idx.map(|idx| &mut self.tiles[idx])
     }
+
+    /// Widens the map so that it includes column `x`. The map grows by at
+    /// least half its current width each time to avoid frequent reallocation.
+    fn grow_to_include(&mut self, x: isize) {
+        if x >= self.left && x <= self.right {
+            return;
+        }
+
+        let margin = self.width() / 2 + 1;
+        let left = if x < self.left {
+            x.min(self.left - margin)
+        } else {
+            self.left
+        };
+        let right = if x > self.right {
+            x.max(self.right + margin)
+        } else {
+            self.right
+        };
+        let width = right + 1 - left;
+
+        let mut tiles = vec![Tile::Air; (width * self.height()) as usize];
+        for (y, row) in self.tiles.chunks_exact(self.width() as usize).enumerate() {
+            let start = y * width as usize + (self.left - left) as usize;
+            tiles[start..start + row.len()].copy_from_slice(row);
+        }
+
+        self.left = left;
+        self.right = right;
+        self.tiles = tiles;
+    }
+
+    /// Counts the tiles of a given type within the map. The floor is not
+    /// included in the count of rock tiles.
+    fn count(&self, tile: Tile) -> usize {
+        self.tiles.iter().filter(|t| **t == tile).count()
+    }
 }
 
 fn parse_isize(input: &str) -> IResult<&str, isize> {
@@ -106,7 +191,7 @@ fn parse_isize(input: &str) -> IResult<&str, isize> {
 
 fn parse_input(input: &str) -> IResult<&str, Vec<Vec<Position>>> {
     separated_list1(
-        newline,
+        eol,
         separated_list1(
             tag(" -> "),
             map(
@@ -134,61 +219,645 @@ fn next_positions(pos: Position) -> impl IntoIterator<Item = Position> {
     ]
 }
 
-fn drop_sand(map: &mut Map) -> bool {
-    if *map.tile(SAND_SOURCE).unwrap() {
-        // If the source tile is occupied then no more sand can fall.
-        return false;
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Step {
+    /// The current grain appeared or fell by one tile.
+    Moved,
+    /// The current grain came to rest.
+    Settled,
+    /// No more sand can settle, either because sand is falling in to the
+    /// abyss or because the source is blocked.
+    Finished,
+}
+
+/// Drops sand grains in to a map one at a time, taking turns between each of
+/// the sources. The path taken by each source's grains is recorded, so that
+/// its next grain can resume falling from the last point on that path which
+/// is still free, rather than restarting from the source.
+struct Simulation {
+    map: Map,
+    sources: Vec<Position>,
+    paths: Vec<Vec<Position>>,
+    /// The number of grains from each source which have settled.
+    settled: Vec<usize>,
+    /// Whether each source can still produce grains which settle.
+    active: Vec<bool>,
+    /// The source whose grain is next to fall.
+    current: usize,
+    /// Whether the current source's grain at the end of its path is falling.
+    falling: bool,
+}
+
+impl Simulation {
+    fn new(map: Map, sources: &[Position]) -> Self {
+        Self {
+            map,
+            sources: sources.to_vec(),
+            paths: vec![Vec::new(); sources.len()],
+            settled: vec![0; sources.len()],
+            active: vec![true; sources.len()],
+            current: 0,
+            falling: false,
+        }
     }
 
-    let mut pos = SAND_SOURCE;
-    while let Some(new_pos) = next_positions(pos)
-        .into_iter()
-        .find(|new_pos| map.tile(*new_pos).map_or(true, |occupied| !occupied))
-    {
-        pos = new_pos;
-        if map.tile(new_pos).is_none() {
-            // We are off the edge or bottom of map. All sand from now on will
-            // fall into the abyss.
-            return false;
+    /// Moves on to the next source which is still active, in round-robin
+    /// order. Returns false if every source has finished.
+    fn next_source(&mut self) -> bool {
+        self.falling = false;
+        match (1..=self.sources.len())
+            .map(|i| (self.current + i) % self.sources.len())
+            .find(|i| self.active[*i])
+        {
+            Some(next) => {
+                self.current = next;
+                true
+            }
+            None => false,
         }
     }
 
-    // The sand has settled.
-    *map.tile_mut(pos).unwrap() = true;
-    true
+    /// Advances the simulation by a single tile. A new grain first appears at
+    /// the point its predecessor's path diverged, which is where it would be
+    /// had it fallen from the source.
+    fn step(&mut self) -> Step {
+        if !self.active[self.current] {
+            return Step::Finished;
+        }
+
+        let path = &mut self.paths[self.current];
+        if !self.falling {
+            if path.is_empty() {
+                let source = self.sources[self.current];
+                if self.map.tile(source).unwrap().is_occupied() {
+                    // If the source tile is occupied then no more sand can
+                    // fall from it.
+                    self.active[self.current] = false;
+                    return if self.next_source() {
+                        Step::Moved
+                    } else {
+                        Step::Finished
+                    };
+                }
+                path.push(source);
+            }
+            self.falling = true;
+            return Step::Moved;
+        }
+
+        let pos = *path.last().unwrap();
+        match next_positions(pos).into_iter().find(|new_pos| {
+            self.map
+                .tile(*new_pos)
+                .is_none_or(|tile| !tile.is_occupied())
+        }) {
+            Some(new_pos) if self.map.tile(new_pos).is_none() => {
+                // We are off the edge or bottom of map. All sand from this
+                // source will fall into the abyss from now on.
+                self.active[self.current] = false;
+                if self.next_source() {
+                    Step::Moved
+                } else {
+                    Step::Finished
+                }
+            }
+            Some(new_pos) => {
+                path.push(new_pos);
+                Step::Moved
+            }
+            None => {
+                // The sand has settled. The next grain will follow the same
+                // path until the tile before this one.
+                *self.map.tile_mut(pos).unwrap() = Tile::Sand;
+                path.pop();
+                self.settled[self.current] += 1;
+
+                // If the sand landed on another source's path then that path
+                // is only valid up to the tile before.
+                for (i, other_path) in self.paths.iter_mut().enumerate() {
+                    if i != self.current {
+                        if let Some(idx) = other_path.iter().position(|p| *p == pos) {
+                            other_path.truncate(idx);
+                        }
+                    }
+                }
+
+                self.next_source();
+                Step::Settled
+            }
+        }
+    }
+
+    fn drop_sand(&mut self) -> bool {
+        loop {
+            match self.step() {
+                Step::Moved => {}
+                Step::Settled => return true,
+                Step::Finished => return false,
+            }
+        }
+    }
+
+    /// Drops sand until `stop` holds for the simulation, checking before each
+    /// grain. Returns false if sand stopped settling before that happened.
+    fn run_until(&mut self, mut stop: impl FnMut(&Simulation) -> bool) -> bool {
+        while !stop(self) {
+            if !self.drop_sand() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The total number of grains which have settled from all sources.
+    fn grains(&self) -> usize {
+        self.settled.iter().sum()
+    }
+
+    fn falling_grain(&self) -> Option<Position> {
+        self.falling
+            .then(|| *self.paths[self.current].last().unwrap())
+    }
+}
+
+impl Display for Simulation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let bottom = self.map.floor.unwrap_or(self.map.bottom);
+        for y in self.map.top..=bottom {
+            for x in self.map.left..=self.map.right {
+                let pos = Position { x, y };
+                let c = if Some(pos) == self.falling_grain() {
+                    '~'
+                } else {
+                    match self.map.tile(pos) {
+                        Some(Tile::Rock) => '#',
+                        Some(Tile::Sand) => 'o',
+                        _ if self.sources.contains(&pos) => '+',
+                        _ => '.',
+                    }
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StepSize {
+    Grain,
+    Tile,
+}
+
+impl FromStr for StepSize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "grain" => Ok(StepSize::Grain),
+            "tile" => Ok(StepSize::Tile),
+            _ => Err(anyhow!(
+                "Unknown step size {:?} (expected grain or tile)",
+                s
+            )),
+        }
+    }
+}
+
+/// Redraws the map each time Enter is pressed, advancing the simulation by
+/// either a whole grain or a single tile of a grain's fall.
+fn run_interactive(mut sim: Simulation, step_size: StepSize) -> Result<()> {
+    let stdin = std::io::stdin();
+    loop {
+        // Clear the terminal before drawing.
+        print!("\x1b[2J\x1b[H{}", sim);
+        println!(
+            "Sand settled: {}. Press Enter to continue, or q then Enter to quit.",
+            sim.map.count(Tile::Sand)
+        );
+
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 || line.trim() == "q" {
+            return Ok(());
+        }
+
+        let finished = match step_size {
+            StepSize::Grain => !sim.drop_sand(),
+            StepSize::Tile => sim.step() == Step::Finished,
+        };
+        if finished {
+            print!("\x1b[2J\x1b[H{}", sim);
+            println!("Finished with {} sand settled.", sim.map.count(Tile::Sand));
+            return Ok(());
+        }
+    }
+}
+
+/// Counts how many grains of sand settle before the sources are blocked when
+/// there is a floor. Every tile reachable from a source eventually fills with
+/// sand, so rather than simulating each grain this flood fills downwards one
+/// row at a time: a tile is reachable if it is not rock and either any of the
+/// three tiles above it are reachable, or it is a source.
+fn part_b_flood_fill(rocks: &[Vec<Position>], sources: &[Position]) -> Result<usize> {
+    let map = Map::new(rocks, sources, true)?;
+    let floor = map.floor.unwrap();
+
+    // Sand spreads at most one tile sideways for each tile it falls.
+    let top = sources.iter().map(|source| source.y).min().unwrap();
+    let left = sources.iter().map(|source| source.x).min().unwrap() - (floor - top);
+    let right = sources.iter().map(|source| source.x).max().unwrap() + (floor - top);
+    let width = (right + 1 - left) as usize;
+
+    let mut row = vec![false; width];
+    let mut count = 0;
+
+    for y in top..floor {
+        row = (0..width)
+            .map(|i| {
+                let pos = Position {
+                    x: left + i as isize,
+                    y,
+                };
+                let is_rock = *map.tile(pos).unwrap() == Tile::Rock;
+                let is_reachable = sources.contains(&pos)
+                    || row[i.saturating_sub(1)..=(i + 1).min(width - 1)].contains(&true);
+                !is_rock && is_reachable
+            })
+            .collect();
+        count += row.iter().filter(|reachable| **reachable).count();
+    }
+
+    Ok(count)
+}
+
+/// Simulates sand falling from the sources until `stop` holds, or no more
+/// sand can settle, and returns the resulting state.
+fn simulate_until(
+    rocks: &[Vec<Position>],
+    sources: &[Position],
+    has_floor: bool,
+    stop: impl FnMut(&Simulation) -> bool,
+) -> Result<Simulation> {
+    let mut sim = Simulation::new(Map::new(rocks, sources, has_floor)?, sources);
+    sim.run_until(stop);
+    Ok(sim)
+}
+
+/// Simulates sand falling from the sources until no more can settle.
+fn simulate(rocks: &[Vec<Position>], sources: &[Position], has_floor: bool) -> Result<Simulation> {
+    simulate_until(rocks, sources, has_floor, |_| false)
+}
+
+fn part_b_simulate(rocks: &[Vec<Position>], sources: &[Position]) -> Result<usize> {
+    Ok(simulate(rocks, sources, true)?.map.count(Tile::Sand))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    Simulate,
+    FloodFill,
+}
+
+impl variants::Strategy for Strategy {
+    const ALL: &'static [Strategy] = &[Strategy::Simulate, Strategy::FloodFill];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Simulate => "simulate",
+            Strategy::FloodFill => "flood-fill",
+        }
+    }
+}
+
+fn part_b(rocks: &[Vec<Position>], sources: &[Position], strategy: Strategy) -> Result<usize> {
+    match strategy {
+        Strategy::Simulate => part_b_simulate(rocks, sources),
+        Strategy::FloodFill => part_b_flood_fill(rocks, sources),
+    }
+}
+
+fn parse_source(s: &str) -> Result<Position> {
+    let (x, y) = s
+        .split_once(',')
+        .with_context(|| format!("Expected a source of the form x,y, got {:?}", s))?;
+    Ok(Position {
+        x: x.parse()
+            .with_context(|| format!("Invalid source x: {:?}", x))?,
+        y: y.parse()
+            .with_context(|| format!("Invalid source y: {:?}", y))?,
+    })
+}
+
+/// Draws the cave as an SVG image, with each row's runs of rock and sand
+/// merged into single rectangles to keep the file small.
+fn render_svg(sim: &Simulation) -> String {
+    let map = &sim.map;
+    let mut image = Svg::new();
+    for y in map.top..=map.bottom {
+        let row = (map.left..=map.right).map(|x| map.tile(Position { x, y }));
+        let mut x = map.left;
+        for (tile, run) in &row.group_by(|tile| tile.copied()) {
+            let len = run.count() as isize;
+            let colour = match tile {
+                Some(Tile::Rock) => Some("dimgrey"),
+                Some(Tile::Sand) => Some("goldenrod"),
+                _ => None,
+            };
+            if let Some(colour) = colour {
+                image.rect(x as f64, y as f64, len as f64, 1.0, &Style::fill(colour));
+            }
+            x += len;
+        }
+    }
+
+    if let Some(floor) = map.floor {
+        image.rect(
+            map.left as f64,
+            floor as f64,
+            map.width() as f64,
+            1.0,
+            &Style::fill("dimgrey"),
+        );
+    }
+    for source in &sim.sources {
+        image.rect(
+            source.x as f64,
+            source.y as f64,
+            1.0,
+            1.0,
+            &Style::fill("red"),
+        );
+    }
+
+    image.finish(2.0, 4.0)
+}
+
+/// The colours of the animation's pixels: air, rock, sand and a source.
+const ANIMATION_PALETTE: &[[u8; 3]] = &[
+    [255, 255, 255],
+    [105, 105, 105],
+    [218, 165, 32],
+    [255, 0, 0],
+];
+
+/// Thousands of grains can settle, so only some are animated.
+const MAX_FRAMES: usize = 300;
+
+/// Animates sand filling the cave, and writes it to `path` as a GIF.
+fn animate(
+    rocks: &[Vec<Position>],
+    sources: &[Position],
+    has_floor: bool,
+    path: &str,
+) -> Result<()> {
+    // Run the simulation to the end first, so that every frame can show the
+    // area the sand eventually covers, and the frames can be spread evenly.
+    let finished = simulate(rocks, sources, has_floor)?;
+    let map = &finished.map;
+    let occupied = |x| {
+        (map.top..=map.bottom).any(|y| map.tile(Position { x, y }).is_some_and(Tile::is_occupied))
+    };
+    let left = (map.left..=map.right)
+        .find(|&x| occupied(x))
+        .unwrap_or(map.left)
+        - 1;
+    let right = (map.left..=map.right)
+        .rev()
+        .find(|&x| occupied(x))
+        .unwrap_or(map.right)
+        + 1;
+    let bottom = map.floor.unwrap_or(map.bottom);
+    let width = (right + 1 - left) as usize;
+    let height = (bottom + 1 - map.top) as usize;
+    let frame_every = finished.grains().div_ceil(MAX_FRAMES).max(1);
+
+    let scale = (600 / width.max(height)).max(1);
+    let mut animation = Animation::create(path, width, height, scale, ANIMATION_PALETTE)?;
+    let mut draw = |sim: &Simulation, delay| {
+        let mut frame = Vec::with_capacity(width * height);
+        for y in sim.map.top..=bottom {
+            for x in left..=right {
+                let pos = Position { x, y };
+                frame.push(match sim.map.tile(pos) {
+                    Some(Tile::Rock) => 1,
+                    Some(Tile::Sand) => 2,
+                    _ if sources.contains(&pos) => 3,
+                    _ => 0,
+                });
+            }
+        }
+        animation.frame(&frame, delay)
+    };
+
+    let mut sim = Simulation::new(Map::new(rocks, sources, has_floor)?, sources);
+    draw(&sim, 4)?;
+    while sim.drop_sand() {
+        if sim.grains().is_multiple_of(frame_every) {
+            draw(&sim, 4)?;
+        }
+    }
+    // Linger on the full cave before looping.
+    draw(&sim, 300)?;
+    Ok(())
+}
+
+fn print_per_source(part: char, sim: &Simulation) {
+    for (source, settled) in sim.sources.iter().zip(&sim.settled) {
+        println!(
+            "Day 14, part {}: {} from source {},{}",
+            part, settled, source.x, source.y
+        );
+    }
+}
+
+/// Checks that every rock path runs horizontally or vertically, and that no
+/// sand source is inside rock.
+fn validate(rocks: &[Vec<Position>], sources: &[Position]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (i, path) in rocks.iter().enumerate() {
+        for (from, to) in path.iter().tuple_windows() {
+            if from.x != to.x && from.y != to.y {
+                warnings.push(format!(
+                    "Line {}: {},{} -> {},{} is diagonal",
+                    i + 1,
+                    from.x,
+                    from.y,
+                    to.x,
+                    to.y
+                ));
+            }
+        }
+    }
+
+    for source in sources {
+        let in_rock = rocks.iter().any(|path| {
+            path.iter().tuple_windows().any(|(from, to)| {
+                (from.x.min(to.x)..=from.x.max(to.x)).contains(&source.x)
+                    && (from.y.min(to.y)..=from.y.max(to.y)).contains(&source.y)
+                    && (from.x == to.x || from.y == to.y)
+            })
+        });
+        if in_rock {
+            warnings.push(format!(
+                "The sand source at {},{} is inside rock",
+                source.x, source.y
+            ));
+        }
+    }
+    warnings
 }
 
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input14.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input14.txt".to_string();
+    let mut strategy = Strategy::Simulate;
+    let mut cross_check = false;
+    let mut interactive = None;
+    let mut has_floor = false;
+    let mut sources = Vec::new();
+    let mut grains = None;
+    let mut svg_path = None;
+    let mut animation_path = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strategy" => {
+                strategy =
+                    variants::parse_strategy(&args.next().context("--strategy requires a value")?)?;
+            }
+            "--cross-check" => cross_check = true,
+            "--interactive" => {
+                interactive = Some(
+                    args.next()
+                        .context("--interactive requires a step size")?
+                        .parse()?,
+                );
+            }
+            "--floor" => has_floor = true,
+            "--svg" => svg_path = Some(args.next().context("--svg requires a path")?),
+            "--animate" => {
+                animation_path = Some(args.next().context("--animate requires a path")?);
+            }
+            "--grains" => {
+                grains = Some(
+                    args.next()
+                        .context("--grains requires a value")?
+                        .parse::<usize>()?,
+                );
+            }
+            "--source" => {
+                sources.push(parse_source(
+                    &args.next().context("--source requires a value")?,
+                )?);
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
 
-    let mut rocks = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
+
+    let rocks = timing::time(Stage::Parse, || parse_all(parse_input, &input))?;
+
+    if sources.is_empty() {
+        sources.push(SAND_SOURCE);
+    }
+
+    if dump_parsed {
+        return Ok(print_json(&rocks)?);
+    }
+
+    if validate_only {
+        report_warnings(14, &validate(&rocks, &sources));
+        return Ok(());
+    }
+
+    // Like the interactive mode, this shows part A's cave unless --floor is
+    // given.
+    if let Some(path) = animation_path {
+        animate(&rocks, &sources, has_floor, &path)
+            .with_context(|| format!("Error writing animation {:?}", path))?;
+        return Ok(());
+    }
+
+    if let Some(step_size) = interactive {
+        let map = Map::new(&rocks, &sources, has_floor)?;
+        return run_interactive(Simulation::new(map, &sources), step_size);
+    }
 
-    let mut map_a = Map::new(&rocks)?;
-    let result_a = std::iter::repeat(())
-        .take_while(|_| drop_sand(&mut map_a))
-        .count();
+    if let Some(grains) = grains {
+        let sim = simulate_until(&rocks, &sources, has_floor, |sim| sim.grains() >= grains)?;
+        print!("{}", sim);
+        println!("Sand settled: {}", sim.grains());
+        return Ok(());
+    }
+
+    let sim_a = timing::time(Stage::PartA, || simulate(&rocks, &sources, false))?;
+    let result_a = sim_a.map.count(Tile::Sand);
     println!("Day 14, part A: {}", result_a);
+    if sources.len() > 1 {
+        print_per_source('A', &sim_a);
+    }
 
-    // Add an "infinite" floor 2 tiles below the first map's bottom. In practice
-    // we only need it to extend to either side by the new map's height,
-    // excluding the floor.
-    rocks.push(vec![
-        Position {
-            x: SAND_SOURCE.x - map_a.height() - 1,
-            y: map_a.bottom + 2,
-        },
-        Position {
-            x: SAND_SOURCE.x + map_a.height() + 1,
-            y: map_a.bottom + 2,
-        },
-    ]);
-    let mut map_b = Map::new(&rocks)?;
-    let result_b = std::iter::repeat(())
-        .take_while(|_| drop_sand(&mut map_b))
-        .count();
+    let result_b = timing::time(Stage::PartB, || part_b(&rocks, &sources, strategy))?;
     println!("Day 14, part B: {}", result_b);
+    if sources.len() > 1 || svg_path.is_some() {
+        let sim_b = simulate(&rocks, &sources, true)?;
+        if sources.len() > 1 {
+            print_per_source('B', &sim_b);
+        }
+        // Export the cave once it's full, which is the more striking picture.
+        if let Some(path) = svg_path {
+            std::fs::write(&path, render_svg(&sim_b))
+                .with_context(|| format!("Error writing {:?}", path))?;
+        }
+    }
+
+    if cross_check {
+        variants::cross_check(14, strategy, &result_b, |other| {
+            part_b(&rocks, &sources, other)
+        })?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2022::examples;
+    use aoc2022::variants::Strategy as _;
+
+    #[test]
+    fn every_strategy_solves_the_example() {
+        for example in examples::for_day(14) {
+            let rocks = parse_all(parse_input, example.input).unwrap();
+            for &strategy in Strategy::ALL {
+                let result = part_b(&rocks, &[SAND_SOURCE], strategy).unwrap();
+                assert_eq!(
+                    Some(result.to_string().as_str()),
+                    example.part_b,
+                    "strategy {}",
+                    strategy.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn strategies_agree_with_several_sources() {
+        let example = examples::for_day(14).next().unwrap();
+        let rocks = parse_all(parse_input, example.input).unwrap();
+        let sources = [SAND_SOURCE, Position { x: 497, y: 2 }];
+        let expected = part_b(&rocks, &sources, Strategy::Simulate).unwrap();
+        for &strategy in Strategy::ALL {
+            assert_eq!(part_b(&rocks, &sources, strategy).unwrap(), expected);
+        }
+    }
+}
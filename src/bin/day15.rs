@@ -1,15 +1,29 @@
 use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::parse_line_error;
+use aoc2022::render::svg::{self, Style, Svg};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::variants;
+use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
-    character::complete::{char, digit1, newline},
-    combinator::{cut, map, map_res, opt, recognize},
-    multi::separated_list1,
+    character::complete::{char, digit1},
+    combinator::{all_consuming, map, map_res, opt, recognize},
     sequence::{pair, preceded, separated_pair},
     IResult,
 };
-use std::{collections::HashSet, ops::RangeInclusive};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufRead, BufReader},
+    ops::RangeInclusive,
+};
 
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Position {
     x: isize,
     y: isize,
@@ -19,9 +33,19 @@ impl Position {
     fn dist(&self, other: &Position) -> isize {
         (other.x - self.x).abs() + (other.y - self.y).abs()
     }
+
+    /// Calculates the distress beacon's tuning frequency. This is computed in
+    /// 64 bits, as it exceeds the range of `isize` on 32-bit targets.
+    fn tuning_frequency(&self) -> Result<i64> {
+        (self.x as i64)
+            .checked_mul(4000000)
+            .and_then(|freq| freq.checked_add(self.y as i64))
+            .with_context(|| format!("Tuning frequency overflowed for {:?}", self))
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Sensor {
     pos: Position,
     nearest_beacon: Position,
@@ -42,6 +66,8 @@ fn merge_ranges(
     }
 }
 
+/// A set of positions, stored as non-overlapping ranges sorted by start. Ranges
+/// which are adjacent without overlapping are kept apart.
 #[derive(Debug)]
 struct RangeSet(Vec<RangeInclusive<isize>>);
 
@@ -93,16 +119,27 @@ fn parse_sensor(input: &str) -> IResult<&str, Sensor> {
     )(input)
 }
 
-fn parse_input(input: &str) -> IResult<&str, Vec<Sensor>> {
-    cut(separated_list1(newline, parse_sensor))(input.trim_end())
+/// Parses sensors one line at a time, so that the whole input never needs to
+/// be held in memory.
+fn parse_input(reader: impl BufRead) -> impl Iterator<Item = Result<Sensor>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(i, line)| {
+            let line = line.with_context(|| format!("Error reading line {}", i + 1))?;
+            let sensor = all_consuming(parse_sensor)(line.trim_end())
+                .map_err(|e| parse_line_error(i + 1, line.trim_end(), e))?
+                .1;
+            Ok(sensor)
+        })
 }
 
-fn part_a(sensors: &[Sensor]) -> isize {
-    const ROW: isize = 2000000;
-
+/// Counts the positions in row `row` which can't contain a beacon.
+fn part_a(sensors: &[Sensor], row: isize) -> isize {
     let mut beacons = HashSet::new();
     for sensor in sensors {
-        if sensor.nearest_beacon.y == ROW {
+        if sensor.nearest_beacon.y == row {
             beacons.insert(sensor.nearest_beacon.x);
         }
     }
@@ -111,7 +148,7 @@ fn part_a(sensors: &[Sensor]) -> isize {
 
     for sensor in sensors {
         let beacon_dist = sensor.pos.dist(&sensor.nearest_beacon);
-        let vertical_dist = (ROW - sensor.pos.y).abs();
+        let vertical_dist = (row - sensor.pos.y).abs();
         if beacon_dist - vertical_dist >= 0 {
             let first = sensor.pos.x - (beacon_dist - vertical_dist);
             let last = sensor.pos.x + (beacon_dist - vertical_dist);
@@ -128,57 +165,183 @@ fn part_a(sensors: &[Sensor]) -> isize {
         - beacons.len() as isize
 }
 
-fn part_b(sensors: &[Sensor]) -> Result<isize> {
-    const SEARCH_AREA: isize = 4000000;
+/// Finds the first gap in the sensors' coverage of row `y` within
+/// `0..=search_area`, if there is one.
+fn row_gap(sensors: &[Sensor], search_area: isize, y: isize) -> Option<Position> {
+    let mut not_beacons = RangeSet::new();
+    for sensor in sensors {
+        let beacon_dist = sensor.pos.dist(&sensor.nearest_beacon);
+        let vertical_dist = (y - sensor.pos.y).abs();
+        if beacon_dist - vertical_dist >= 0 {
+            let first = (sensor.pos.x - (beacon_dist - vertical_dist)).max(0);
+            let last = (sensor.pos.x + (beacon_dist - vertical_dist)).min(search_area);
+
+            not_beacons.add(first..=last);
+        }
+    }
+    not_beacons
+        .0
+        .iter()
+        .tuple_windows()
+        .find(|(a, b)| *b.start() > a.end() + 1)
+        .map(|(a, _)| Position { x: a.end() + 1, y })
+}
+
+/// Scans each row of the search area for a gap in the sensors' coverage.
+fn part_b_rows(sensors: &[Sensor], search_area: isize) -> Result<i64> {
+    #[cfg(not(feature = "parallel"))]
+    let beacon = (0..=search_area).find_map(|y| row_gap(sensors, search_area, y));
+    #[cfg(feature = "parallel")]
+    let beacon = (0..=search_area)
+        .into_par_iter()
+        .find_map_any(|y| row_gap(sensors, search_area, y));
+
+    beacon.context("Failed to find beacon")?.tuning_frequency()
+}
 
-    for y in 0..=SEARCH_AREA {
-        let mut not_beacons = RangeSet::new();
-        for sensor in sensors {
-            let beacon_dist = sensor.pos.dist(&sensor.nearest_beacon);
+/// The positions within the search area directly adjacent to the edge of
+/// `sensor`'s exclusion zone.
+fn adjacent_positions(sensor: &Sensor, search_area: isize) -> impl Iterator<Item = Position> + '_ {
+    let beacon_dist = sensor.pos.dist(&sensor.nearest_beacon);
+    let y_range = (sensor.pos.y - beacon_dist)..=(sensor.pos.y + beacon_dist);
+    y_range
+        .flat_map(move |y| {
             let vertical_dist = (y - sensor.pos.y).abs();
-            if beacon_dist - vertical_dist >= 0 {
-                let first = (sensor.pos.x - (beacon_dist - vertical_dist)).max(0);
-                let last = (sensor.pos.x + (beacon_dist - vertical_dist)).min(SEARCH_AREA);
+            [
+                Position {
+                    x: sensor.pos.x - (beacon_dist - vertical_dist) - 1,
+                    y,
+                },
+                Position {
+                    x: sensor.pos.x + (beacon_dist - vertical_dist) + 1,
+                    y,
+                },
+            ]
+        })
+        .filter(move |pos| pos.x >= 0 && pos.y >= 0 && pos.x <= search_area && pos.y <= search_area)
+}
 
-                not_beacons.add(first..=last);
-            }
-        }
-        if not_beacons.0.len() > 1 {
-            return Ok((not_beacons.0[0].end() + 1) * 4000000 + y);
+/// Checks every position directly outside each sensor's exclusion zone.
+fn part_b_perimeter(sensors: &[Sensor], search_area: isize) -> Result<i64> {
+    // Find which of the positions adjacent to each sensor's exclusion zone
+    // isn't in the exclusion zone of any other sensor.
+    let undetected = |pos: &Position| {
+        sensors
+            .iter()
+            .all(|sensor| sensor.pos.dist(pos) > sensor.pos.dist(&sensor.nearest_beacon))
+    };
+
+    #[cfg(not(feature = "parallel"))]
+    let beacon = sensors
+        .iter()
+        .flat_map(|sensor| adjacent_positions(sensor, search_area))
+        .find(undetected);
+    #[cfg(feature = "parallel")]
+    let beacon = sensors
+        .par_iter()
+        .flat_map_iter(|sensor| adjacent_positions(sensor, search_area))
+        .find_any(undetected);
+
+    beacon.context("Failed to find beacon")?.tuning_frequency()
+}
+
+/// The number of sensors `SensorLanes` checks a position against at once.
+/// The compiler turns each chunk into vector instructions.
+const LANES: usize = 8;
+
+/// The sensors' positions and exclusion zone radii as separate arrays, padded
+/// to a whole number of chunks of `LANES` sensors. These are narrowed to
+/// `i32` so that more fit in each vector.
+struct SensorLanes {
+    x: Vec<i32>,
+    y: Vec<i32>,
+    radius: Vec<i32>,
+}
+
+impl SensorLanes {
+    /// Coordinates and radii must be within this of zero, so that distances
+    /// can't overflow.
+    const LIMIT: isize = 1 << 28;
+
+    /// Returns `None` if the sensors or `search_area` are too far out to use
+    /// `i32`.
+    fn new(sensors: &[Sensor], search_area: isize) -> Option<Self> {
+        let len = sensors.len().div_ceil(LANES) * LANES;
+        // The padding has a negative radius, so covers nothing.
+        let mut lanes = SensorLanes {
+            x: vec![0; len],
+            y: vec![0; len],
+            radius: vec![-1; len],
+        };
+        let narrow = |n: isize| (n.abs() <= Self::LIMIT).then_some(n as i32);
+        narrow(search_area)?;
+        for (i, sensor) in sensors.iter().enumerate() {
+            lanes.x[i] = narrow(sensor.pos.x)?;
+            lanes.y[i] = narrow(sensor.pos.y)?;
+            lanes.radius[i] = narrow(sensor.pos.dist(&sensor.nearest_beacon))?;
         }
+        Some(lanes)
     }
 
-    Err(anyhow!("Failed to find beacon"))
+    /// Whether `pos`, which must be within the search area, is within any
+    /// sensor's exclusion zone.
+    fn covers(&self, pos: &Position) -> bool {
+        let (pos_x, pos_y) = (pos.x as i32, pos.y as i32);
+        self.x
+            .chunks_exact(LANES)
+            .zip(self.y.chunks_exact(LANES))
+            .zip(self.radius.chunks_exact(LANES))
+            .any(|((x, y), radius)| {
+                let mut covered = 0u8;
+                for i in 0..LANES {
+                    covered |= ((x[i] - pos_x).abs() + (y[i] - pos_y).abs() <= radius[i]) as u8;
+                }
+                covered != 0
+            })
+    }
 }
 
-/// Alternative solution for part B
-fn part_b_2(sensors: &[Sensor]) -> Result<isize> {
-    const SEARCH_AREA: isize = 4000000;
+/// As `part_b_perimeter`, but checking each position against several sensors
+/// at once. Falls back to `part_b_perimeter` if the coordinates are too large.
+fn part_b_perimeter_simd(sensors: &[Sensor], search_area: isize) -> Result<i64> {
+    let lanes = match SensorLanes::new(sensors, search_area) {
+        Some(lanes) => lanes,
+        None => return part_b_perimeter(sensors, search_area),
+    };
+    sensors
+        .iter()
+        .flat_map(|sensor| adjacent_positions(sensor, search_area))
+        .find(|pos| !lanes.covers(pos))
+        .context("Failed to find beacon")?
+        .tuning_frequency()
+}
 
-    // Find all positions directly adjacent to the exclusion zone around each sensor.
-    let mut adjacent_positions = sensors
+/// As there is only a single possible position for the beacon, it must lie
+/// just outside the exclusion zone of several sensors. The edges of each zone
+/// lie on diagonal lines, so the beacon must be at the intersection of a line
+/// with gradient 1 and one with gradient -1, each just outside a zone.
+fn part_b_lines(sensors: &[Sensor], search_area: isize) -> Result<i64> {
+    // Lines are described by their y-intercepts: y = x + a, and y = -x + b.
+    let mut ascending = HashSet::new();
+    let mut descending = HashSet::new();
+    for sensor in sensors {
+        let dist = sensor.pos.dist(&sensor.nearest_beacon) + 1;
+        ascending.insert(sensor.pos.y - sensor.pos.x + dist);
+        ascending.insert(sensor.pos.y - sensor.pos.x - dist);
+        descending.insert(sensor.pos.y + sensor.pos.x + dist);
+        descending.insert(sensor.pos.y + sensor.pos.x - dist);
+    }
+
+    let beacon = ascending
         .iter()
-        .flat_map(|sensor| {
-            let beacon_dist = sensor.pos.dist(&sensor.nearest_beacon);
-            let y_range = (sensor.pos.y - beacon_dist)..=(sensor.pos.y + beacon_dist);
-            y_range.flat_map(move |y| {
-                let vertical_dist = (y - sensor.pos.y).abs();
-                [
-                    Position {
-                        x: sensor.pos.x - (beacon_dist - vertical_dist) - 1,
-                        y,
-                    },
-                    Position {
-                        x: sensor.pos.x + (beacon_dist - vertical_dist) + 1,
-                        y,
-                    },
-                ]
-            })
+        .cartesian_product(descending.iter())
+        // Lines whose intercepts differ in parity intersect between tiles.
+        .filter(|(a, b)| (*b - *a) % 2 == 0)
+        .map(|(a, b)| Position {
+            x: (b - a) / 2,
+            y: (a + b) / 2,
         })
-        .filter(|pos| pos.x >= 0 && pos.y >= 0 && pos.x <= SEARCH_AREA && pos.y <= SEARCH_AREA);
-
-    // Find which of these positions isn't in the exclusion zone of any other sensor.
-    let beacon = adjacent_positions
+        .filter(|pos| pos.x >= 0 && pos.y >= 0 && pos.x <= search_area && pos.y <= search_area)
         .find(|pos| {
             sensors
                 .iter()
@@ -186,24 +349,423 @@ fn part_b_2(sensors: &[Sensor]) -> Result<isize> {
         })
         .context("Failed to find beacon")?;
 
-    Ok(beacon.x * 4000000 + beacon.y)
+    beacon.tuning_frequency()
+}
+
+/// Scans the search area's rows for the gap on the GPU, one row per shader
+/// invocation. As this is an experiment, the answer is checked against
+/// `part_b_lines` before it is returned.
+#[cfg(feature = "gpu")]
+fn part_b_gpu(sensors: &[Sensor], search_area: isize) -> Result<i64> {
+    let lanes = SensorLanes::new(sensors, search_area)
+        .context("The sensors are too far out to search on the GPU")?;
+    let result = gpu_row_gap(&lanes, search_area)?
+        .context("Failed to find beacon")?
+        .tuning_frequency()?;
+
+    let expected = part_b_lines(sensors, search_area)?;
+    if result != expected {
+        return Err(anyhow!(
+            "The GPU found tuning frequency {} but the CPU found {}",
+            result,
+            expected
+        ));
+    }
+    Ok(result)
+}
+
+/// Runs `day15.wgsl` over every row of the search area, returning the gap it
+/// finds in the sensors' coverage, if any.
+#[cfg(feature = "gpu")]
+fn gpu_row_gap(lanes: &SensorLanes, search_area: isize) -> Result<Option<Position>> {
+    use wgpu::util::DeviceExt;
+
+    const WORKGROUP_SIZE: u32 = 64;
+
+    let instance = wgpu::Instance::default();
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .context("No GPU adapter is available")?;
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .context("Error opening the GPU")?;
+
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("day15"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("day15.wgsl").into()),
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("find_gap"),
+        layout: None,
+        module: &module,
+        entry_point: "find_gap",
+    });
+
+    let storage = |contents: &[i32], usage| {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(contents),
+            usage,
+        })
+    };
+    let sensor_x = storage(&lanes.x, wgpu::BufferUsages::STORAGE);
+    let sensor_y = storage(&lanes.y, wgpu::BufferUsages::STORAGE);
+    let radius = storage(&lanes.radius, wgpu::BufferUsages::STORAGE);
+    let gap = storage(
+        &[-1, -1],
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    );
+    let params = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: gap.size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[&sensor_x, &sensor_y, &radius, &params, &gap]
+            .iter()
+            .enumerate()
+            .map(|(i, buffer)| wgpu::BindGroupEntry {
+                binding: i as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>(),
+    });
+
+    // There may be more rows than one dispatch can cover, so they're
+    // dispatched in batches, stopping once a gap is found.
+    let max_rows =
+        device.limits().max_compute_workgroups_per_dimension as usize * WORKGROUP_SIZE as usize;
+    for first_row in (0..=search_area as usize).step_by(max_rows) {
+        let rows = max_rows.min(search_area as usize + 1 - first_row);
+        // The shader's `Params`. Each field is non-negative, so has the same
+        // representation as an `i32` or `u32`.
+        let contents = [
+            search_area as u32,
+            first_row as u32,
+            lanes.x.len() as u32,
+            0,
+        ];
+        queue.write_buffer(&params, 0, bytemuck::cast_slice(&contents));
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups((rows as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&gap, 0, &readback, 0, gap.size());
+        queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv()?.context("Error reading the GPU's result")?;
+        let found =
+            bytemuck::cast_slice::<u8, i32>(&readback.slice(..).get_mapped_range()).to_vec();
+        readback.unmap();
+
+        if let [x, y] = found[..] {
+            if x >= 0 {
+                return Ok(Some(Position {
+                    x: x as isize,
+                    y: y as isize,
+                }));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    Rows,
+    Perimeter,
+    PerimeterSimd,
+    Lines,
+    #[cfg(feature = "gpu")]
+    Gpu,
+}
+
+impl variants::Strategy for Strategy {
+    #[cfg(not(feature = "gpu"))]
+    const ALL: &'static [Strategy] = &[
+        Strategy::Rows,
+        Strategy::Perimeter,
+        Strategy::PerimeterSimd,
+        Strategy::Lines,
+    ];
+    #[cfg(feature = "gpu")]
+    const ALL: &'static [Strategy] = &[
+        Strategy::Rows,
+        Strategy::Perimeter,
+        Strategy::PerimeterSimd,
+        Strategy::Lines,
+        Strategy::Gpu,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Rows => "rows",
+            Strategy::Perimeter => "perimeter",
+            Strategy::PerimeterSimd => "perimeter-simd",
+            Strategy::Lines => "lines",
+            #[cfg(feature = "gpu")]
+            Strategy::Gpu => "gpu",
+        }
+    }
+}
+
+/// Finds the tuning frequency of the only position within `0..=search_area`
+/// in both axes which can contain the distress beacon.
+fn part_b(sensors: &[Sensor], search_area: isize, strategy: Strategy) -> Result<i64> {
+    match strategy {
+        Strategy::Rows => part_b_rows(sensors, search_area),
+        Strategy::Perimeter => part_b_perimeter(sensors, search_area),
+        Strategy::PerimeterSimd => part_b_perimeter_simd(sensors, search_area),
+        Strategy::Lines => part_b_lines(sensors, search_area),
+        #[cfg(feature = "gpu")]
+        Strategy::Gpu => part_b_gpu(sensors, search_area),
+    }
+}
+
+/// Draws each sensor's coverage as a translucent diamond over the search
+/// area, marking the sensors, their beacons, part A's row and the distress
+/// beacon.
+fn render_svg(
+    sensors: &[Sensor],
+    search_area: isize,
+    row: isize,
+    distress_beacon: Position,
+) -> String {
+    let area = search_area as f64;
+    // Markers would be invisibly small at the real scale, so size them to the
+    // search area.
+    let marker = (area / 200.0).max(1.0);
+    let mark = |image: &mut Svg, pos: &Position, size: f64, colour: &str| {
+        image.rect(
+            pos.x as f64 - size / 2.0,
+            pos.y as f64 - size / 2.0,
+            size,
+            size,
+            &Style::fill(colour),
+        );
+    };
+
+    let mut image = Svg::new();
+    image.rect(0.0, 0.0, area, area, &Style::stroke("black"));
+    for sensor in sensors {
+        let (x, y) = (sensor.pos.x as f64, sensor.pos.y as f64);
+        let radius = sensor.pos.dist(&sensor.nearest_beacon) as f64;
+        let diamond = svg::Path::new()
+            .move_to(x, y - radius)
+            .line_to(x + radius, y)
+            .line_to(x, y + radius)
+            .line_to(x - radius, y)
+            .close();
+        image.path(&diamond, &Style::fill("steelblue").with_opacity(0.3));
+    }
+    image.path(
+        &svg::Path::new()
+            .move_to(0.0, row as f64)
+            .line_to(area, row as f64),
+        &Style::stroke("green"),
+    );
+    for sensor in sensors {
+        mark(&mut image, &sensor.pos, marker, "navy");
+        mark(&mut image, &sensor.nearest_beacon, marker, "black");
+    }
+    mark(&mut image, &distress_beacon, 3.0 * marker, "red");
+
+    image.finish(area / 20.0, 800.0 / area)
+}
+
+/// Checks that no sensor is on its own beacon, and that no two sensors are in
+/// the same place.
+fn validate(sensors: &[Sensor]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut positions = HashSet::new();
+    for sensor in sensors {
+        let Position { x, y } = sensor.pos;
+        if sensor.pos == sensor.nearest_beacon {
+            warnings.push(format!("Sensor at x={}, y={} is on its own beacon", x, y));
+        }
+        if !positions.insert(sensor.pos) {
+            warnings.push(format!("Sensor at x={}, y={} is listed twice", x, y));
+        }
+    }
+    warnings
 }
 
 fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input15.txt")?;
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input15.txt".to_string();
+    let mut strategy = Strategy::Lines;
+    let mut cross_check = false;
+    let mut row = 2000000;
+    let mut search_area = 4000000;
+    let mut svg_path = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strategy" => {
+                strategy =
+                    variants::parse_strategy(&args.next().context("--strategy requires a value")?)?;
+            }
+            "--cross-check" => cross_check = true,
+            "--svg" => svg_path = Some(args.next().context("--svg requires a path")?),
+            "--row" => row = args.next().context("--row requires a value")?.parse()?,
+            "--search-area" => {
+                search_area = args
+                    .next()
+                    .context("--search-area requires a value")?
+                    .parse()?;
+            }
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
 
-    let sensors = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
+    let input = BufReader::new(
+        File::open(&input_path).with_context(|| format!("Error reading {:?}", input_path))?,
+    );
+
+    let sensors = timing::time(Stage::Parse, || {
+        parse_input(input).collect::<Result<Vec<Sensor>>>()
+    })
+    .context("Error parsing input")?;
+
+    if dump_parsed {
+        return Ok(print_json(&sensors)?);
+    }
 
-    let result_a = part_a(&sensors);
+    if validate_only {
+        report_warnings(15, &validate(&sensors));
+        return Ok(());
+    }
+
+    let result_a = timing::time(Stage::PartA, || part_a(&sensors, row));
     println!("Day 15, part A: {}", result_a);
 
-    let result_b = part_b(&sensors)?;
+    let result_b = timing::time(Stage::PartB, || part_b(&sensors, search_area, strategy))?;
     println!("Day 15, part B: {}", result_b);
 
-    let result_b_2 = part_b_2(&sensors)?;
-    println!("Day 15, part B: {}", result_b_2);
+    if let Some(path) = svg_path {
+        // The frequency is the beacon's position, encoded.
+        let distress_beacon = Position {
+            x: (result_b / 4000000) as isize,
+            y: (result_b % 4000000) as isize,
+        };
+        std::fs::write(
+            &path,
+            render_svg(&sensors, search_area, row, distress_beacon),
+        )
+        .with_context(|| format!("Error writing {:?}", path))?;
+    }
+
+    if cross_check {
+        variants::cross_check(15, strategy, &result_b, |other| {
+            part_b(&sensors, search_area, other)
+        })?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2022::variants::Strategy as _;
+    use proptest::prelude::{prop, prop_assert, prop_assert_eq, proptest};
+
+    #[test]
+    fn every_strategy_solves_the_example() {
+        let example = aoc2022::examples::for_day(15).next().unwrap();
+        let sensors = parse_input(example.input.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        for &strategy in Strategy::ALL {
+            let result = part_b(&sensors, 20, strategy).unwrap();
+            assert_eq!(
+                Some(result.to_string().as_str()),
+                example.part_b,
+                "strategy {}",
+                strategy.name()
+            );
+        }
+    }
+
+    fn ranges(adds: &[RangeInclusive<isize>]) -> Vec<RangeInclusive<isize>> {
+        let mut set = RangeSet::new();
+        for range in adds {
+            set.add(range.clone());
+        }
+        set.0
+    }
+
+    #[test]
+    fn adjacent_ranges_are_kept_apart() {
+        assert_eq!(ranges(&[0..=4, 5..=9]), [0..=4, 5..=9]);
+        assert_eq!(ranges(&[5..=9, 0..=4]), [0..=4, 5..=9]);
+    }
+
+    #[test]
+    fn nested_ranges_are_merged() {
+        assert_eq!(ranges(&[0..=9, 3..=5]), [0..=9]);
+        assert_eq!(ranges(&[3..=5, 0..=9]), [0..=9]);
+        assert_eq!(ranges(&[0..=9, 0..=9]), [0..=9]);
+    }
+
+    #[test]
+    fn overlapping_ranges_are_merged() {
+        assert_eq!(ranges(&[0..=5, 5..=9]), [0..=9]);
+        assert_eq!(ranges(&[0..=4, 8..=12, 3..=9]), [0..=12]);
+    }
+
+    #[test]
+    fn disjoint_ranges_are_sorted() {
+        assert_eq!(ranges(&[10..=12, 0..=2, 5..=7]), [0..=2, 5..=7, 10..=12]);
+    }
+
+    proptest! {
+        #[test]
+        fn ranges_cover_what_was_added(
+            adds in prop::collection::vec((0..64isize, 0..16isize), 1..12)
+        ) {
+            let adds = adds
+                .into_iter()
+                .map(|(start, len)| start..=(start + len).min(63))
+                .collect::<Vec<_>>();
+            let set = ranges(&adds);
+
+            for (a, b) in set.iter().tuple_windows() {
+                prop_assert!(a.end() < b.start(), "{:?} and {:?} overlap or are unsorted", a, b);
+            }
+            for x in 0..64 {
+                prop_assert_eq!(
+                    adds.iter().any(|range| range.contains(&x)),
+                    set.iter().any(|range| range.contains(&x)),
+                    "position {}", x
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// Day 15 part B's row scan, with one invocation per row of the search area.
+// Each starts at the left of its row and skips past the coverage of whichever
+// sensor covers its current position, until it either leaves the search area
+// or finds a position no sensor covers.
+
+struct Params {
+    search_area: i32,
+    // The row scanned by the first invocation of this dispatch.
+    first_row: i32,
+    sensor_count: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<storage, read> sensor_x: array<i32>;
+@group(0) @binding(1) var<storage, read> sensor_y: array<i32>;
+@group(0) @binding(2) var<storage, read> radius: array<i32>;
+@group(0) @binding(3) var<uniform> params: Params;
+// The gap's x and y, left as -1 until one is found.
+@group(0) @binding(4) var<storage, read_write> gap: array<atomic<i32>, 2>;
+
+@compute @workgroup_size(64)
+fn find_gap(@builtin(global_invocation_id) id: vec3<u32>) {
+    let y = params.first_row + i32(id.x);
+    if y > params.search_area {
+        return;
+    }
+
+    var x = 0;
+    loop {
+        if x > params.search_area {
+            return;
+        }
+        var covered = false;
+        for (var i = 0u; i < params.sensor_count; i++) {
+            let reach = radius[i] - abs(y - sensor_y[i]);
+            if abs(x - sensor_x[i]) <= reach {
+                x = sensor_x[i] + reach + 1;
+                covered = true;
+            }
+        }
+        if !covered {
+            atomicStore(&gap[0], x);
+            atomicStore(&gap[1], y);
+            return;
+        }
+    }
+}
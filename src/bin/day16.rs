@@ -1,26 +1,37 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use aoc2022::dump::print_json;
+use aoc2022::parse::{eol, parse_all};
+use aoc2022::timing::{self, Stage};
+use aoc2022::validate::report_warnings;
+use aoc2022::variants;
 use itertools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{char, digit1, newline, satisfy},
+    character::complete::{char, digit1, satisfy},
     combinator::{map, map_res, opt, recognize},
-    multi::{fold_many1, many_m_n, separated_list1},
-    sequence::{pair, preceded, terminated, tuple},
+    multi::{many_m_n, separated_list1},
+    sequence::{pair, preceded, tuple},
     AsChar, IResult,
 };
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use smallvec::SmallVec;
 use std::{
     cmp::Ordering,
     collections::{BinaryHeap, HashMap},
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
 };
 
 type ValveId = [char; 2];
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Valve {
     id: [char; 2],
     flow_rate: usize,
-    tunnels: Vec<ValveId>,
+    tunnels: SmallVec<[ValveId; 5]>,
 }
 
 fn parse_usize(input: &str) -> IResult<&str, usize> {
@@ -58,20 +69,43 @@ fn parse_valve(input: &str) -> IResult<&str, Valve> {
         |(id, flow_rate, tunnels)| Valve {
             id,
             flow_rate,
-            tunnels,
+            tunnels: tunnels.into_iter().collect(),
         },
     )(input)
 }
 
-fn parse_input(input: &str) -> IResult<&str, HashMap<ValveId, Valve>> {
-    fold_many1(
-        terminated(parse_valve, opt(newline)),
-        HashMap::default,
-        |mut acc, valve| {
-            acc.insert(valve.id, valve);
-            acc
-        },
-    )(input)
+fn parse_input(input: &str) -> IResult<&str, Vec<Valve>> {
+    separated_list1(eol, parse_valve)(input)
+}
+
+/// Indexes valves by ID, checking that each is listed once, that every tunnel
+/// leads to a listed valve, and that there's a valve AA to start from.
+fn index_valves(valves: Vec<Valve>) -> Result<HashMap<ValveId, Valve>> {
+    let mut index = HashMap::new();
+    for valve in valves {
+        let id = valve.id;
+        if index.insert(id, valve).is_some() {
+            return Err(anyhow!("Valve {}{} is listed twice", id[0], id[1]));
+        }
+    }
+
+    for valve in index.values() {
+        if let Some(tunnel) = valve.tunnels.iter().find(|id| !index.contains_key(*id)) {
+            return Err(anyhow!(
+                "Valve {}{} has a tunnel to unknown valve {}{}",
+                valve.id[0],
+                valve.id[1],
+                tunnel[0],
+                tunnel[1]
+            ));
+        }
+    }
+
+    if !index.contains_key(&['A', 'A']) {
+        return Err(anyhow!("There is no valve AA to start from"));
+    }
+
+    Ok(index)
 }
 
 fn calc_distance(
@@ -102,7 +136,7 @@ fn calc_distance(
         cost: 0,
         pos: *start,
     });
-    let mut costs: HashMap<ValveId, usize> = HashMap::default();
+    let mut costs: FxHashMap<ValveId, usize> = FxHashMap::default();
     costs.insert(*start, 0);
 
     while let Some(State { cost, pos }) = open.pop() {
@@ -138,7 +172,7 @@ fn calc_distance(
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct State<const N: usize> {
     current_pos: [ValveId; N],
-    visited: Vec<ValveId>,
+    visited: SmallVec<[ValveId; 16]>,
     time: [usize; N],
     score: usize,
 }
@@ -159,7 +193,7 @@ impl<const N: usize> State<N> {
     fn new(start: ValveId, time: usize) -> Self {
         Self {
             current_pos: [start; N],
-            visited: Vec::new(),
+            visited: SmallVec::new(),
             time: [time; N],
             score: 0,
         }
@@ -168,7 +202,7 @@ impl<const N: usize> State<N> {
     fn not_visited<'a, 'b>(
         &'a self,
         valves: &'b HashMap<ValveId, Valve>,
-    ) -> impl Iterator<Item = ValveId> + Clone + '_
+    ) -> impl Iterator<Item = ValveId> + Clone + 'a
     where
         'b: 'a,
     {
@@ -185,14 +219,15 @@ impl<const N: usize> State<N> {
         &'a self,
         valves: &'b HashMap<ValveId, Valve>,
         distances: &'a HashMap<(ValveId, ValveId), usize>,
-    ) -> impl Iterator<Item = (Self, usize)> + '_
+    ) -> impl Iterator<Item = (Self, usize)> + 'a
     where
         'b: 'a,
     {
         (0..N)
             .cartesian_product(self.not_visited(valves))
             .filter_map(|(i, next)| {
-                let distance = distances[&(self.current_pos[i], next)];
+                // Valves which can't be reached from here are skipped.
+                let distance = *distances.get(&(self.current_pos[i], next))?;
                 if self.time[i] > distance {
                     let mut new_state = self.clone();
                     new_state.time[i] -= distance + 1;
@@ -220,17 +255,21 @@ impl<const N: usize> State<N> {
     }
 }
 
-fn find_max_pressure_release<const N: usize>(
-    time: usize,
+/// Searches the states reachable from `initial_state`, best first, for the
+/// most pressure which can be released. `best` is the most found so far by
+/// this or any other search, below which states aren't worth exploring.
+fn search<const N: usize>(
+    initial_state: State<N>,
+    initial_score: usize,
     valves: &HashMap<ValveId, Valve>,
     distances: &HashMap<(ValveId, ValveId), usize>,
+    best: &AtomicUsize,
 ) -> usize {
-    let initial_state = State::<N>::new(['A', 'A'], time);
     let mut open = BinaryHeap::new();
-    open.push((initial_state.clone(), 0));
+    open.push((initial_state.clone(), initial_score));
 
-    let mut scores: HashMap<State<N>, usize> = HashMap::default();
-    scores.insert(initial_state, 0);
+    let mut scores: FxHashMap<State<N>, usize> = FxHashMap::default();
+    scores.insert(initial_state, initial_score);
 
     let mut paths = Vec::new();
     let mut max_score = 0;
@@ -241,11 +280,12 @@ fn find_max_pressure_release<const N: usize>(
         }
         paths.push(state.clone());
         max_score = max_score.max(score);
+        best.fetch_max(score, AtomicOrdering::Relaxed);
         for (new_state, score_increase) in state.next_states(valves, distances) {
             let new_score = score + score_increase;
             let potential = new_state.potential_score(valves);
 
-            if new_score + potential > max_score
+            if new_score + potential > best.load(AtomicOrdering::Relaxed)
                 && new_score > *scores.entry(new_state.clone()).or_insert(usize::MIN)
             {
                 *scores.get_mut(&new_state).unwrap() = new_score;
@@ -257,30 +297,265 @@ fn find_max_pressure_release<const N: usize>(
     max_score
 }
 
-fn main() -> Result<()> {
-    let input = std::fs::read_to_string("res/input16.txt")?;
-
-    let valves = parse_input(&input)
-        .map_err(|e| anyhow!("Error parsing input: {:?}", e))?
-        .1;
-
-    let distances =
-        valves
-            .values()
-            .tuple_combinations()
-            .fold(HashMap::new(), |mut acc, (from, to)| {
-                if let Some(cost) = calc_distance(&from.id, &to.id, &valves) {
-                    acc.insert((from.id, to.id), cost);
-                    acc.insert((to.id, from.id), cost);
+/// Finds the most pressure which can be released in `time` minutes.
+#[cfg(not(feature = "parallel"))]
+fn find_max_pressure_release<const N: usize>(
+    time: usize,
+    valves: &HashMap<ValveId, Valve>,
+    distances: &HashMap<(ValveId, ValveId), usize>,
+) -> usize {
+    search(
+        State::<N>::new(['A', 'A'], time),
+        0,
+        valves,
+        distances,
+        &AtomicUsize::new(0),
+    )
+}
+
+/// As above, but searching onwards from each of the first moves in parallel.
+/// The searches share the best score so far for pruning, but not the states
+/// they've seen, so together they explore more states than the serial search.
+#[cfg(feature = "parallel")]
+fn find_max_pressure_release<const N: usize>(
+    time: usize,
+    valves: &HashMap<ValveId, Valve>,
+    distances: &HashMap<(ValveId, ValveId), usize>,
+) -> usize {
+    let best = AtomicUsize::new(0);
+    State::<N>::new(['A', 'A'], time)
+        .next_states(valves, distances)
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|(state, score)| search(state, score, valves, distances, &best))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Finds the most pressure two agents can release together by splitting the
+/// valves between them. For each subset of the valves worth opening, one
+/// agent's best score for opening exactly that subset is found, and the best
+/// pair of disjoint subsets is the answer.
+fn part_b_subsets(
+    time: usize,
+    valves: &HashMap<ValveId, Valve>,
+    distances: &HashMap<(ValveId, ValveId), usize>,
+) -> Result<usize> {
+    let useful = valves
+        .values()
+        .filter(|valve| valve.flow_rate > 0)
+        .map(|valve| valve.id)
+        .sorted()
+        .collect::<Vec<_>>();
+    if useful.len() > 24 {
+        return Err(anyhow!(
+            "{} valves have a flow rate, which is too many to try every subset of",
+            useful.len()
+        ));
+    }
+
+    // Each set bit is a valve in `useful` which has been opened.
+    let mut best = vec![0; 1 << useful.len()];
+    let mut open = vec![(['A', 'A'], time, 0, 0)];
+    while let Some((pos, time, opened, score)) = open.pop() {
+        best[opened] = best[opened].max(score);
+        for (i, next) in useful.iter().enumerate() {
+            if opened & 1 << i != 0 {
+                continue;
+            }
+            // Valves which can't be reached from here are skipped.
+            if let Some(&distance) = distances.get(&(pos, *next)) {
+                if time > distance {
+                    let time = time - distance - 1;
+                    let score = score + valves[next].flow_rate * time;
+                    open.push((*next, time, opened | 1 << i, score));
                 }
-                acc
-            });
+            }
+        }
+    }
+
+    // The best score for opening any subset of each set of valves, so that
+    // the other agent needn't open every valve the first leaves closed.
+    let mut best_within = best.clone();
+    for bit in 0..useful.len() {
+        for opened in 0..best_within.len() {
+            if opened & 1 << bit != 0 {
+                best_within[opened] = best_within[opened].max(best_within[opened ^ 1 << bit]);
+            }
+        }
+    }
+
+    let all = best.len() - 1;
+    Ok((0..best.len())
+        .map(|opened| best[opened] + best_within[all ^ opened])
+        .max()
+        .unwrap())
+}
 
-    let result_a = find_max_pressure_release::<1>(30, &valves, &distances);
+/// How part B finds the most pressure two agents can release together.
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    /// Search the agents' joint states, as for one agent in part A.
+    Search,
+    /// Pair up one agent's best scores for disjoint subsets of the valves.
+    Subsets,
+}
+
+impl Strategy {
+    fn part_b(
+        &self,
+        valves: &HashMap<ValveId, Valve>,
+        distances: &HashMap<(ValveId, ValveId), usize>,
+    ) -> Result<usize> {
+        match self {
+            Strategy::Search => Ok(find_max_pressure_release::<2>(26, valves, distances)),
+            Strategy::Subsets => part_b_subsets(26, valves, distances),
+        }
+    }
+}
+
+impl variants::Strategy for Strategy {
+    const ALL: &'static [Strategy] = &[Strategy::Search, Strategy::Subsets];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Search => "search",
+            Strategy::Subsets => "subsets",
+        }
+    }
+}
+
+/// The distance between every pair of valves which are connected, both ways.
+fn all_distances(valves: &HashMap<ValveId, Valve>) -> HashMap<(ValveId, ValveId), usize> {
+    valves
+        .values()
+        .tuple_combinations()
+        .fold(HashMap::new(), |mut acc, (from, to)| {
+            if let Some(cost) = calc_distance(&from.id, &to.id, valves) {
+                acc.insert((from.id, to.id), cost);
+                acc.insert((to.id, from.id), cost);
+            }
+            acc
+        })
+}
+
+/// Checks that each valve is listed once, that tunnels lead to listed valves
+/// and run both ways, and that there's a valve AA to start from.
+fn validate(valves: &[Valve]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut ids = HashMap::new();
+    for valve in valves {
+        if ids.insert(valve.id, valve).is_some() {
+            warnings.push(format!(
+                "Valve {}{} is listed twice",
+                valve.id[0], valve.id[1]
+            ));
+        }
+    }
+
+    for valve in valves {
+        let [a, b] = valve.id;
+        for tunnel in &valve.tunnels {
+            let [c, d] = *tunnel;
+            match ids.get(tunnel) {
+                None => warnings.push(format!(
+                    "Valve {}{} has a tunnel to unknown valve {}{}",
+                    a, b, c, d
+                )),
+                Some(_) if *tunnel == valve.id => {
+                    warnings.push(format!("Valve {}{} has a tunnel to itself", a, b))
+                }
+                Some(other) if !other.tunnels.contains(&valve.id) => warnings.push(format!(
+                    "Valve {}{} has a tunnel to {}{}, but not the other way",
+                    a, b, c, d
+                )),
+                Some(_) => {}
+            }
+        }
+    }
+
+    if !ids.contains_key(&['A', 'A']) {
+        warnings.push("There is no valve AA to start from".to_string());
+    }
+    warnings
+}
+
+fn main() -> Result<()> {
+    let mut validate_only = false;
+    let mut dump_parsed = false;
+    let mut input_path = "res/input16.txt".to_string();
+    let mut strategy = Strategy::Subsets;
+    let mut cross_check = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strategy" => {
+                strategy =
+                    variants::parse_strategy(&args.next().context("--strategy requires a value")?)?;
+            }
+            "--cross-check" => cross_check = true,
+            "--input" => input_path = args.next().context("--input requires a path")?,
+            "--validate" => validate_only = true,
+            "--dump-parsed" => dump_parsed = true,
+            _ => return Err(anyhow!("Unknown argument {:?}", arg)),
+        }
+    }
+
+    let input = std::fs::read_to_string(&input_path)
+        .with_context(|| format!("Error reading {:?}", input_path))?;
+
+    let valves = timing::time(Stage::Parse, || parse_all(parse_input, &input))?;
+
+    if dump_parsed {
+        return Ok(print_json(&valves)?);
+    }
+
+    if validate_only {
+        report_warnings(16, &validate(&valves));
+        return Ok(());
+    }
+
+    let valves = index_valves(valves)?;
+
+    let distances = all_distances(&valves);
+
+    let result_a = timing::time(Stage::PartA, || {
+        find_max_pressure_release::<1>(30, &valves, &distances)
+    });
     println!("Day 16, part A: {}", result_a);
 
-    let result_b = find_max_pressure_release::<2>(26, &valves, &distances);
+    let result_b = timing::time(Stage::PartB, || strategy.part_b(&valves, &distances))?;
     println!("Day 16, part B: {}", result_b);
 
+    if cross_check {
+        variants::cross_check(16, strategy, &result_b, |other| {
+            other.part_b(&valves, &distances)
+        })?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2022::examples;
+    use aoc2022::variants::Strategy as _;
+
+    #[test]
+    fn subsets_matches_search_on_the_examples() {
+        for example in examples::for_day(16) {
+            let valves = index_valves(parse_all(parse_input, example.input).unwrap()).unwrap();
+            let distances = all_distances(&valves);
+            for &strategy in Strategy::ALL {
+                let result = strategy.part_b(&valves, &distances).unwrap();
+                assert_eq!(
+                    Some(result.to_string().as_str()),
+                    example.part_b,
+                    "strategy {}",
+                    strategy.name()
+                );
+            }
+        }
+    }
+}
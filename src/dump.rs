@@ -0,0 +1,49 @@
+//! Converting the days' data to and from JSON, for `--dump-parsed` and the
+//! other options which print or read JSON, so that other tools can reuse the
+//! days' parsers.
+//!
+//! serde is only a dependency with the `serde` feature, which is on by
+//! default. The days' types only implement its traits with the feature, and
+//! without it these functions report an error instead.
+
+use crate::error::Result;
+
+#[cfg(feature = "serde")]
+pub fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string_pretty(value).map_err(std::io::Error::from)?)
+}
+
+#[cfg(feature = "serde")]
+pub fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> Result<T> {
+    Ok(serde_json::from_str(json).map_err(std::io::Error::from)?)
+}
+
+#[cfg(feature = "serde")]
+pub fn print_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", to_json(value)?);
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn unsupported() -> crate::error::AocError {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "JSON support requires the serde feature",
+    )
+    .into()
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn to_json<T: ?Sized>(_value: &T) -> Result<String> {
+    Err(unsupported())
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn from_json<T>(_json: &str) -> Result<T> {
+    Err(unsupported())
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn print_json<T: ?Sized>(_value: &T) -> Result<()> {
+    Err(unsupported())
+}
@@ -0,0 +1,135 @@
+//! The errors returned by the shared library code. The days themselves use
+//! `anyhow`, which these convert into.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AocError {
+    Io(std::io::Error),
+    /// The input doesn't match the puzzle's format.
+    Parse {
+        /// The day whose input it is, if known.
+        day: Option<u32>,
+        /// The line number, starting from 1.
+        line: usize,
+        /// The column in characters, starting from 1.
+        col: usize,
+        /// What was expected at that point.
+        msg: String,
+        /// The offending line.
+        text: String,
+    },
+    /// The input is well formed but has no answer.
+    NoSolution,
+    /// The input is well formed but breaks one of the puzzle's rules.
+    InvalidInput {
+        reason: String,
+    },
+    /// A strategy name that isn't one of the day's strategies.
+    UnknownStrategy {
+        name: String,
+        /// The names of the strategies available in this build.
+        expected: Vec<&'static str>,
+    },
+    /// Two strategies for the same day gave different answers.
+    Disagreement {
+        strategy: &'static str,
+        other: &'static str,
+        /// The other strategy's answer.
+        got: String,
+        /// The answer from `strategy`.
+        expected: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, AocError>;
+
+impl AocError {
+    /// Records which day's input failed to parse.
+    pub fn for_day(self, day: u32) -> Self {
+        match self {
+            AocError::Parse {
+                line,
+                col,
+                msg,
+                text,
+                ..
+            } => AocError::Parse {
+                day: Some(day),
+                line,
+                col,
+                msg,
+                text,
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for AocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AocError::Io(e) => write!(f, "{}", e),
+            AocError::Parse {
+                day,
+                line,
+                col,
+                msg,
+                text,
+            } => {
+                if let Some(day) = day {
+                    write!(f, "Day {}: ", day)?;
+                }
+                write!(
+                    f,
+                    "Error parsing input at line {}, column {}: {}\n  {}\n  {}^",
+                    line,
+                    col,
+                    msg,
+                    text,
+                    " ".repeat(col - 1)
+                )
+            }
+            AocError::NoSolution => write!(f, "No solution found"),
+            AocError::InvalidInput { reason } => write!(f, "Invalid input: {}", reason),
+            AocError::UnknownStrategy { name, expected } => {
+                write!(f, "Unknown strategy {:?} (expected ", name)?;
+                for (i, strategy) in expected.iter().enumerate() {
+                    let separator = match i {
+                        0 => "",
+                        _ if i + 1 == expected.len() => " or ",
+                        _ => ", ",
+                    };
+                    write!(f, "{}{}", separator, strategy)?;
+                }
+                write!(f, ")")
+            }
+            AocError::Disagreement {
+                strategy,
+                other,
+                got,
+                expected,
+            } => write!(
+                f,
+                "Strategy {} disagrees with {}: {} != {}",
+                other, strategy, got, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AocError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // Display already shows the I/O error itself.
+            AocError::Io(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for AocError {
+    fn from(e: std::io::Error) -> Self {
+        AocError::Io(e)
+    }
+}
@@ -0,0 +1,241 @@
+//! The worked examples from each day's puzzle description, with their answers.
+
+pub struct Example {
+    pub day: u32,
+    /// Tells apart several examples for the same day. Empty for the first.
+    pub name: &'static str,
+    pub input: &'static str,
+    /// Arguments needed to solve the example rather than the real input, for
+    /// days whose puzzle is scaled down for the example.
+    pub args: &'static [&'static str],
+    pub part_a: Option<&'static str>,
+    pub part_b: Option<&'static str>,
+}
+
+const fn example(
+    day: u32,
+    name: &'static str,
+    input: &'static str,
+    part_a: Option<&'static str>,
+    part_b: Option<&'static str>,
+) -> Example {
+    Example {
+        day,
+        name,
+        input,
+        args: &[],
+        part_a,
+        part_b,
+    }
+}
+
+pub const EXAMPLES: &[Example] = &[
+    example(
+        1,
+        "",
+        include_str!("../res/examples/day01.txt"),
+        Some("24000"),
+        Some("45000"),
+    ),
+    example(
+        2,
+        "",
+        include_str!("../res/examples/day02.txt"),
+        Some("15"),
+        Some("12"),
+    ),
+    example(
+        3,
+        "",
+        include_str!("../res/examples/day03.txt"),
+        Some("157"),
+        Some("70"),
+    ),
+    example(
+        4,
+        "",
+        include_str!("../res/examples/day04.txt"),
+        Some("2"),
+        Some("4"),
+    ),
+    example(
+        5,
+        "",
+        include_str!("../res/examples/day05.txt"),
+        Some("CMZ"),
+        Some("MCD"),
+    ),
+    example(
+        6,
+        "",
+        include_str!("../res/examples/day06.txt"),
+        Some("7"),
+        Some("19"),
+    ),
+    example(
+        6,
+        "2",
+        include_str!("../res/examples/day06-2.txt"),
+        Some("5"),
+        Some("23"),
+    ),
+    example(
+        6,
+        "3",
+        include_str!("../res/examples/day06-3.txt"),
+        Some("6"),
+        Some("23"),
+    ),
+    example(
+        6,
+        "4",
+        include_str!("../res/examples/day06-4.txt"),
+        Some("10"),
+        Some("29"),
+    ),
+    example(
+        6,
+        "5",
+        include_str!("../res/examples/day06-5.txt"),
+        Some("11"),
+        Some("26"),
+    ),
+    example(
+        7,
+        "",
+        include_str!("../res/examples/day07.txt"),
+        Some("95437"),
+        Some("24933642"),
+    ),
+    example(
+        8,
+        "",
+        include_str!("../res/examples/day08.txt"),
+        Some("21"),
+        Some("8"),
+    ),
+    example(
+        9,
+        "",
+        include_str!("../res/examples/day09.txt"),
+        Some("13"),
+        Some("1"),
+    ),
+    example(
+        9,
+        "larger",
+        include_str!("../res/examples/day09-larger.txt"),
+        None,
+        Some("36"),
+    ),
+    // The example's image isn't made of letters, so there's nothing to read.
+    example(
+        10,
+        "",
+        include_str!("../res/examples/day10.txt"),
+        Some("13140"),
+        None,
+    ),
+    example(
+        11,
+        "",
+        include_str!("../res/examples/day11.txt"),
+        Some("10605"),
+        Some("2713310158"),
+    ),
+    example(
+        12,
+        "",
+        include_str!("../res/examples/day12.txt"),
+        Some("31"),
+        Some("29"),
+    ),
+    example(
+        13,
+        "",
+        include_str!("../res/examples/day13.txt"),
+        Some("13"),
+        Some("140"),
+    ),
+    example(
+        14,
+        "",
+        include_str!("../res/examples/day14.txt"),
+        Some("24"),
+        Some("93"),
+    ),
+    Example {
+        day: 15,
+        name: "",
+        input: include_str!("../res/examples/day15.txt"),
+        args: &["--row", "10", "--search-area", "20"],
+        part_a: Some("26"),
+        part_b: Some("56000011"),
+    },
+    example(
+        16,
+        "",
+        include_str!("../res/examples/day16.txt"),
+        Some("1651"),
+        Some("1707"),
+    ),
+];
+
+/// The examples for `day`, in the order they appear in the puzzle.
+pub fn for_day(day: u32) -> impl Iterator<Item = &'static Example> {
+    EXAMPLES.iter().filter(move |example| example.day == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_day_has_an_example() {
+        for day in 1..=16 {
+            assert!(for_day(day).next().is_some(), "day {} has no example", day);
+        }
+        assert!(EXAMPLES
+            .iter()
+            .all(|example| (1..=16).contains(&example.day)));
+    }
+
+    #[test]
+    fn examples_are_grouped_by_day() {
+        assert!(EXAMPLES.windows(2).all(|pair| pair[0].day <= pair[1].day));
+    }
+
+    #[test]
+    fn only_the_first_example_is_unnamed() {
+        for day in 1..=16 {
+            let names = for_day(day).map(|example| example.name).collect::<Vec<_>>();
+            assert_eq!(names[0], "", "day {}'s first example is named", day);
+            for (i, name) in names.iter().enumerate().skip(1) {
+                assert!(
+                    !name.is_empty() && !names[..i].contains(name),
+                    "day {} has a duplicate example name {:?}",
+                    day,
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn examples_have_input_and_answers() {
+        for example in EXAMPLES {
+            assert!(
+                example.input.ends_with('\n') && example.input.trim().len() > 1,
+                "day {} example {:?} has no input",
+                example.day,
+                example.name
+            );
+            assert!(
+                example.part_a.is_some() || example.part_b.is_some(),
+                "day {} example {:?} has no answers",
+                example.day,
+                example.name
+            );
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! A rectangular grid of cells, stored row by row.
+
+use crate::error::{AocError, Result};
+use std::ops::Index;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    pub x: usize,
+    pub y: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid from its cells in reading order.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Result<Self> {
+        if cells.len() != width * height {
+            return Err(AocError::InvalidInput {
+                reason: format!(
+                    "grid of {}x{} has {} cells (expected {})",
+                    width,
+                    height,
+                    cells.len(),
+                    width * height
+                ),
+            });
+        }
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// The index of a cell within `cells`.
+    pub fn idx(&self, pos: Position) -> usize {
+        assert!(pos.x < self.width, "invalid x: {}", pos.x);
+        assert!(pos.y < self.height, "invalid y: {}", pos.y);
+        pos.y * self.width + pos.x
+    }
+
+    /// The position of the cell at an index within `cells`.
+    pub fn position(&self, idx: usize) -> Position {
+        Position {
+            x: idx % self.width,
+            y: idx / self.width,
+        }
+    }
+
+    pub fn get(&self, pos: Position) -> Option<&T> {
+        (pos.x < self.width && pos.y < self.height).then(|| &self.cells[self.idx(pos)])
+    }
+
+    /// Every position in the grid, in reading order.
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.width * self.height).map(move |idx| Position {
+            x: idx % width,
+            y: idx / width,
+        })
+    }
+
+    /// The cells seen looking from `pos` in steps of `step`, nearest first,
+    /// up to the edge of the grid. `pos` itself is not included.
+    pub fn ray(&self, pos: Position, step: (isize, isize)) -> Ray<'_, T> {
+        assert!(pos.x < self.width, "invalid x: {}", pos.x);
+        assert!(pos.y < self.height, "invalid y: {}", pos.y);
+        assert!(step != (0, 0), "ray must move");
+
+        Ray {
+            grid: self,
+            pos: (pos.x as isize, pos.y as isize),
+            step,
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Position) -> &T {
+        &self.cells[self.idx(pos)]
+    }
+}
+
+/// An iterator over the cells along a line of sight. See `Grid::ray`.
+#[derive(Debug, Clone)]
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    pos: (isize, isize),
+    step: (isize, isize),
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = (Position, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pos.0 += self.step.0;
+        self.pos.1 += self.step.1;
+        if self.pos.0 >= 0
+            && self.pos.1 >= 0
+            && self.pos.0 < self.grid.width as isize
+            && self.pos.1 < self.grid.height as isize
+        {
+            let pos = Position {
+                x: self.pos.0 as usize,
+                y: self.pos.1 as usize,
+            };
+            Some((pos, &self.grid[pos]))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Loading puzzle input.
+//!
+//! Real inputs are small enough to read into a `String`, but synthetic ones
+//! can run to gigabytes. Those are memory-mapped instead, so that parsers
+//! working over a `&str` can handle them without copying the whole file.
+
+use crate::error::{AocError, Result};
+use memmap2::Mmap;
+use std::fs::File;
+use std::ops::Deref;
+use std::path::Path;
+
+pub enum Input {
+    Read(String),
+    Mapped(Mmap),
+}
+
+impl Input {
+    /// Files at least this large are memory-mapped rather than read.
+    pub const MAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+    /// Opens the input at `path`, memory-mapping it if it's large. The file
+    /// must not be modified while the input is open.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+
+        if len < Self::MAP_THRESHOLD {
+            return Ok(Input::Read(std::fs::read_to_string(path)?));
+        }
+
+        // SAFETY: The mapping is only valid while nothing else modifies the
+        // file, which is documented above as the caller's responsibility.
+        let map = unsafe { Mmap::map(&file) }?;
+        if let Err(e) = std::str::from_utf8(&map) {
+            return Err(AocError::InvalidInput {
+                reason: format!("the input is not UTF-8: {}", e),
+            });
+        }
+        Ok(Input::Mapped(map))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Input::Read(input) => input,
+            // SAFETY: The mapping was checked to be UTF-8 when it was opened.
+            Input::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+        }
+    }
+}
+
+impl Deref for Input {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
@@ -0,0 +1,17 @@
+//! Code shared between days.
+
+pub mod dump;
+pub mod error;
+pub mod examples;
+pub mod grid;
+pub mod input;
+pub mod parse;
+pub mod perf;
+pub mod render;
+#[cfg(feature = "serde")]
+pub mod results;
+pub mod rng;
+pub mod timing;
+pub mod validate;
+pub mod variants;
+pub mod vm;
@@ -0,0 +1,200 @@
+//! Parsers shared between days.
+//!
+//! Inputs may have been saved with Windows line endings, or without a newline
+//! at the end of the file, so line-based parsers should use these rather than
+//! matching a bare `\n`.
+
+use crate::error::{AocError, Result};
+use nom::{
+    branch::alt,
+    character::complete::line_ending,
+    character::complete::multispace0,
+    combinator::{all_consuming, eof},
+    error::{Error, ErrorKind},
+    sequence::terminated,
+    IResult,
+};
+
+/// Matches the end of a line, either LF or CRLF.
+pub fn eol(input: &str) -> IResult<&str, &str> {
+    line_ending(input)
+}
+
+/// Matches the end of a line, either LF or CRLF, or the end of the input.
+pub fn eol_or_eof(input: &str) -> IResult<&str, &str> {
+    alt((line_ending, eof))(input)
+}
+
+/// Describes what the parser that failed was looking for.
+fn expected(kind: ErrorKind) -> String {
+    match kind {
+        ErrorKind::Tag => "expected a specific string".to_string(),
+        ErrorKind::Char => "expected a specific character".to_string(),
+        ErrorKind::OneOf | ErrorKind::Satisfy => {
+            "expected one of the allowed characters".to_string()
+        }
+        ErrorKind::Digit => "expected a number".to_string(),
+        ErrorKind::MapRes => "expected a number in range".to_string(),
+        ErrorKind::CrLf => "expected a line ending".to_string(),
+        ErrorKind::Space | ErrorKind::MultiSpace => "expected whitespace".to_string(),
+        ErrorKind::Count => "too many items".to_string(),
+        ErrorKind::Eof => "unexpected input which doesn't match any line".to_string(),
+        ErrorKind::Alt => "expected one of several alternatives".to_string(),
+        _ => format!("parser {:?} failed", kind),
+    }
+}
+
+/// Builds an error at byte `offset` of `text`, a line of input numbered
+/// `line_no`, pointing at the offending column.
+fn error_at(line_no: usize, text: &str, offset: usize, msg: String) -> AocError {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    AocError::Parse {
+        day: None,
+        line: line_no + text[..line_start].matches('\n').count(),
+        col: text[line_start..offset].chars().count() + 1,
+        msg,
+        text: text[line_start..].lines().next().unwrap_or("").to_string(),
+    }
+}
+
+/// Converts a nom error from parsing `input` into one reporting the line and
+/// column where parsing failed, what was expected there, and the offending
+/// line.
+pub fn parse_error(input: &str, err: nom::Err<Error<&str>>) -> AocError {
+    parse_line_error(1, input, err)
+}
+
+/// Like `parse_error`, for when `input` starts at line `line_no` of the
+/// whole input.
+pub fn parse_line_error(line_no: usize, input: &str, err: nom::Err<Error<&str>>) -> AocError {
+    match err {
+        nom::Err::Incomplete(_) => error_at(
+            line_no,
+            input,
+            input.len(),
+            "unexpected end of input".to_string(),
+        ),
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            // The remaining input is normally a suffix of what was parsed, but
+            // if not, blame the end of the input.
+            let offset = input
+                .len()
+                .checked_sub(e.input.len())
+                .filter(|&offset| input.is_char_boundary(offset))
+                .unwrap_or(input.len());
+            error_at(line_no, input, offset, expected(e.code))
+        }
+    }
+}
+
+/// Runs `parser` over the whole of `input`, allowing trailing whitespace, and
+/// reports where it failed if it doesn't match everything.
+pub fn parse_all<'a, O>(
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+    input: &'a str,
+) -> Result<O> {
+    all_consuming(terminated(parser, multispace0))(input)
+        .map(|(_, output)| output)
+        .map_err(|e| parse_error(input, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::{
+        bytes::complete::tag,
+        character::complete::{char, digit1},
+        combinator::map_res,
+        multi::many1,
+        sequence::separated_pair,
+    };
+
+    fn number(input: &str) -> IResult<&str, u32> {
+        map_res(digit1, str::parse)(input)
+    }
+
+    fn pair(input: &str) -> IResult<&str, (u32, u32)> {
+        separated_pair(number, char(','), number)(input)
+    }
+
+    fn lines(input: &str) -> IResult<&str, Vec<(u32, u32)>> {
+        many1(terminated(pair, eol_or_eof))(input)
+    }
+
+    /// The line, column and offending line text of a parse error.
+    fn location(err: AocError) -> (usize, usize, String) {
+        match err {
+            AocError::Parse {
+                line, col, text, ..
+            } => (line, col, text),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn eol_accepts_lf_and_crlf() {
+        assert_eq!(eol("\nrest"), Ok(("rest", "\n")));
+        assert_eq!(eol("\r\nrest"), Ok(("rest", "\r\n")));
+        assert!(eol("\rrest").is_err());
+        assert!(eol("").is_err());
+    }
+
+    #[test]
+    fn eol_or_eof_accepts_the_end_of_input() {
+        assert_eq!(eol_or_eof(""), Ok(("", "")));
+        assert_eq!(eol_or_eof("\r\n"), Ok(("", "\r\n")));
+        assert!(eol_or_eof("x").is_err());
+    }
+
+    #[test]
+    fn parse_all_accepts_any_line_endings() {
+        let expected = vec![(1, 2), (3, 4)];
+        assert_eq!(parse_all(lines, "1,2\n3,4\n").unwrap(), expected);
+        assert_eq!(parse_all(lines, "1,2\r\n3,4\r\n").unwrap(), expected);
+        assert_eq!(parse_all(lines, "1,2\n3,4").unwrap(), expected);
+        assert_eq!(parse_all(lines, "1,2\r\n3,4").unwrap(), expected);
+        assert_eq!(parse_all(lines, "1,2\n3,4\n\n  \n").unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_all_reports_where_parsing_failed() {
+        // `many1` backtracks to the start of the line it couldn't parse.
+        assert_eq!(
+            location(parse_all(lines, "1,2\n3,x\n").unwrap_err()),
+            (2, 1, "3,x".to_string())
+        );
+        assert_eq!(
+            location(parse_all(lines, "1,2\r\n3,4\r\n5;6\r\n").unwrap_err()),
+            (3, 1, "5;6".to_string())
+        );
+        assert_eq!(
+            location(parse_all(pair, "12,x").unwrap_err()),
+            (1, 4, "12,x".to_string())
+        );
+        assert_eq!(
+            location(parse_all(pair, "12,3 4").unwrap_err()),
+            (1, 6, "12,3 4".to_string())
+        );
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        assert_eq!(
+            location(parse_all(|input| tag("é,")(input), "é,x").unwrap_err()),
+            (1, 3, "é,x".to_string())
+        );
+        assert_eq!(
+            location(parse_all(pair, "1,2\r\néé").unwrap_err()),
+            (2, 1, "éé".to_string())
+        );
+    }
+
+    #[test]
+    fn line_errors_are_offset() {
+        let err = number("x").unwrap_err();
+        assert_eq!(
+            location(parse_line_error(5, "x", err)),
+            (5, 1, "x".to_string())
+        );
+    }
+}
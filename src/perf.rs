@@ -0,0 +1,183 @@
+//! Hardware performance counters, read through Linux's `perf_event_open`.
+//!
+//! The counters follow the thread which opens them and every thread it starts
+//! while they are open, so a day can count the events in each of its stages,
+//! including those run in parallel. Only user-space events are counted, which
+//! the default `perf_event_paranoid` setting allows without privileges.
+
+use crate::error::Result;
+use std::io;
+
+/// Counts of the events which were most useful for tuning the slower days.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub instructions: u64,
+    pub cache_misses: u64,
+    pub branch_misses: u64,
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    /// `struct perf_event_attr` from `linux/perf_event.h`, as of version 5
+    /// of its layout.
+    #[repr(C)]
+    #[derive(Default)]
+    struct Attr {
+        kind: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+        config2: u64,
+        branch_sample_type: u64,
+        sample_regs_user: u64,
+        sample_stack_user: u32,
+        clockid: i32,
+        sample_regs_intr: u64,
+        aux_watermark: u32,
+        sample_max_stack: u16,
+        reserved: u16,
+    }
+
+    const TYPE_HARDWARE: u32 = 0;
+    pub const INSTRUCTIONS: u64 = 1;
+    pub const CACHE_MISSES: u64 = 3;
+    pub const BRANCH_MISSES: u64 = 5;
+
+    const DISABLED: u64 = 1 << 0;
+    const INHERIT: u64 = 1 << 1;
+    const EXCLUDE_KERNEL: u64 = 1 << 5;
+    const EXCLUDE_HV: u64 = 1 << 6;
+
+    const FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+    const IOC_ENABLE: libc::c_ulong = 0x2400;
+    const IOC_DISABLE: libc::c_ulong = 0x2401;
+    const IOC_RESET: libc::c_ulong = 0x2403;
+
+    /// Opens a disabled counter of the hardware event `config` for this
+    /// thread and the threads it starts in future.
+    pub fn open(config: u64) -> io::Result<File> {
+        let attr = Attr {
+            kind: TYPE_HARDWARE,
+            size: std::mem::size_of::<Attr>() as u32,
+            config,
+            flags: DISABLED | INHERIT | EXCLUDE_KERNEL | EXCLUDE_HV,
+            ..Default::default()
+        };
+        // SAFETY: `attr` is a valid `perf_event_attr` which outlives the call.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const Attr,
+                0,
+                -1,
+                -1,
+                FLAG_FD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: The file descriptor was just opened, and nothing else owns it.
+        Ok(unsafe { File::from_raw_fd(fd as i32) })
+    }
+
+    fn ioctl(counter: &File, request: libc::c_ulong) -> io::Result<()> {
+        // SAFETY: These requests take no argument.
+        if unsafe { libc::ioctl(counter.as_raw_fd(), request as _, 0) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn start(counter: &File) -> io::Result<()> {
+        ioctl(counter, IOC_RESET)?;
+        ioctl(counter, IOC_ENABLE)
+    }
+
+    pub fn stop(counter: &File) -> io::Result<()> {
+        ioctl(counter, IOC_DISABLE)
+    }
+
+    /// Reads a counter, which includes the counts of the threads it follows.
+    pub fn read(mut counter: &File) -> io::Result<u64> {
+        let mut count = [0; 8];
+        counter.read_exact(&mut count)?;
+        Ok(u64::from_ne_bytes(count))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub struct Counters {
+    instructions: std::fs::File,
+    cache_misses: std::fs::File,
+    branch_misses: std::fs::File,
+}
+
+#[cfg(target_os = "linux")]
+impl Counters {
+    pub fn open() -> Result<Self> {
+        let open = |config| {
+            sys::open(config).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("performance counters are unavailable: {}", e),
+                )
+            })
+        };
+        Ok(Counters {
+            instructions: open(sys::INSTRUCTIONS)?,
+            cache_misses: open(sys::CACHE_MISSES)?,
+            branch_misses: open(sys::BRANCH_MISSES)?,
+        })
+    }
+
+    /// Counts the events while running `f`, including in any threads it
+    /// uses which were started after the counters were opened. `f` is run
+    /// even if the counters fail.
+    pub fn measure<T>(&self, f: impl FnOnce() -> T) -> (T, Result<Counts>) {
+        let counters = [&self.instructions, &self.cache_misses, &self.branch_misses];
+        let started = counters.into_iter().try_for_each(sys::start);
+        let result = f();
+        let stopped = counters.into_iter().try_for_each(sys::stop);
+
+        let counts = || -> Result<Counts> {
+            started?;
+            stopped?;
+            Ok(Counts {
+                instructions: sys::read(&self.instructions)?,
+                cache_misses: sys::read(&self.cache_misses)?,
+                branch_misses: sys::read(&self.branch_misses)?,
+            })
+        };
+        (result, counts())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub struct Counters;
+
+#[cfg(not(target_os = "linux"))]
+impl Counters {
+    pub fn open() -> Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "performance counters are only supported on Linux",
+        )
+        .into())
+    }
+
+    pub fn measure<T>(&self, f: impl FnOnce() -> T) -> (T, Result<Counts>) {
+        (f(), Ok(Counts::default()))
+    }
+}
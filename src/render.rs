@@ -0,0 +1,5 @@
+//! Drawing the days' state as images and animations, for the visualizations
+//! some of them can export.
+
+pub mod animation;
+pub mod svg;
@@ -0,0 +1,86 @@
+//! Encoding animated GIFs, for the days which can export their simulations as
+//! a shareable animation rather than only showing them in the terminal.
+
+use crate::error::{AocError, Result};
+use gif::{Encoder, EncodingError, Frame, Repeat};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+pub struct Animation {
+    encoder: Encoder<BufWriter<File>>,
+    width: usize,
+    height: usize,
+    scale: usize,
+}
+
+fn encoding_error(e: EncodingError) -> AocError {
+    match e {
+        EncodingError::Io(e) => AocError::Io(e),
+        EncodingError::Format(e) => AocError::InvalidInput {
+            reason: format!("the animation can't be encoded: {}", e),
+        },
+    }
+}
+
+impl Animation {
+    /// Creates an animation at `path` of frames `width` by `height` pixels,
+    /// each drawn as a square `scale` pixels wide. Frames pick their pixels'
+    /// colours from `palette`, which can hold up to 256.
+    pub fn create(
+        path: impl AsRef<Path>,
+        width: usize,
+        height: usize,
+        scale: usize,
+        palette: &[[u8; 3]],
+    ) -> Result<Self> {
+        let size = |len: usize| {
+            u16::try_from(len * scale).map_err(|_| AocError::InvalidInput {
+                reason: format!(
+                    "an animation {} pixels across is too large for a GIF",
+                    len * scale
+                ),
+            })
+        };
+        let (scaled_width, scaled_height) = (size(width)?, size(height)?);
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = Encoder::new(file, scaled_width, scaled_height, &palette.concat())
+            .map_err(encoding_error)?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(encoding_error)?;
+        Ok(Animation {
+            encoder,
+            width,
+            height,
+            scale,
+        })
+    }
+
+    /// Adds a frame, given as palette indices in reading order, which is shown
+    /// for `delay` hundredths of a second.
+    pub fn frame(&mut self, pixels: &[u8], delay: u16) -> Result<()> {
+        assert_eq!(pixels.len(), self.width * self.height);
+
+        let mut scaled = Vec::with_capacity(pixels.len() * self.scale * self.scale);
+        for row in pixels.chunks_exact(self.width) {
+            let scaled_row = row
+                .iter()
+                .flat_map(|&pixel| std::iter::repeat_n(pixel, self.scale))
+                .collect::<Vec<_>>();
+            for _ in 0..self.scale {
+                scaled.extend_from_slice(&scaled_row);
+            }
+        }
+
+        let frame = Frame {
+            width: (self.width * self.scale) as u16,
+            height: (self.height * self.scale) as u16,
+            buffer: scaled.into(),
+            delay,
+            ..Default::default()
+        };
+        self.encoder.write_frame(&frame).map_err(encoding_error)
+    }
+}
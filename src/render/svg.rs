@@ -0,0 +1,235 @@
+//! Building SVG documents. Shapes are drawn in the puzzle's own coordinates,
+//! and the document's view box is fitted around everything drawn, so days
+//! don't need to work out their own scaling or margins.
+
+use std::fmt::Write;
+
+/// How a shape is filled and outlined. Outlines keep their width in pixels
+/// however the drawing is scaled.
+#[derive(Debug, Clone, Default)]
+pub struct Style {
+    pub fill: Option<String>,
+    pub stroke: Option<String>,
+    pub stroke_width: Option<f64>,
+    pub opacity: Option<f64>,
+}
+
+impl Style {
+    pub fn fill(colour: impl Into<String>) -> Self {
+        Style {
+            fill: Some(colour.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn stroke(colour: impl Into<String>) -> Self {
+        Style {
+            stroke: Some(colour.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_stroke_width(mut self, width: f64) -> Self {
+        self.stroke_width = Some(width);
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    fn attributes(&self) -> String {
+        let mut attributes = format!(r#" fill="{}""#, self.fill.as_deref().unwrap_or("none"));
+        if let Some(stroke) = &self.stroke {
+            write!(
+                attributes,
+                r#" stroke="{}" vector-effect="non-scaling-stroke""#,
+                stroke
+            )
+            .unwrap();
+        }
+        if let Some(width) = self.stroke_width {
+            write!(attributes, r#" stroke-width="{}""#, width).unwrap();
+        }
+        if let Some(opacity) = self.opacity {
+            write!(attributes, r#" opacity="{}""#, opacity).unwrap();
+        }
+        attributes
+    }
+}
+
+/// Formats a colour from its red, green and blue components.
+pub fn rgb(red: u8, green: u8, blue: u8) -> String {
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
+
+/// The outline of a shape, made of straight lines.
+#[derive(Debug, Clone, Default)]
+pub struct Path {
+    points: Vec<(f64, f64)>,
+    data: String,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new line at `(x, y)`.
+    pub fn move_to(mut self, x: f64, y: f64) -> Self {
+        write!(self.data, "M{} {}", x, y).unwrap();
+        self.points.push((x, y));
+        self
+    }
+
+    pub fn line_to(mut self, x: f64, y: f64) -> Self {
+        write!(self.data, "L{} {}", x, y).unwrap();
+        self.points.push((x, y));
+        self
+    }
+
+    /// Joins the current line back to where it started.
+    pub fn close(mut self) -> Self {
+        self.data.push('Z');
+        self
+    }
+
+    /// A line through each of `points` in turn.
+    pub fn through(points: impl IntoIterator<Item = (f64, f64)>) -> Self {
+        let mut points = points.into_iter();
+        let mut path = match points.next() {
+            Some((x, y)) => Self::new().move_to(x, y),
+            None => return Self::new(),
+        };
+        for (x, y) in points {
+            path = path.line_to(x, y);
+        }
+        path
+    }
+}
+
+/// The smallest rectangle containing everything drawn so far.
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    left: f64,
+    top: f64,
+    right: f64,
+    bottom: f64,
+}
+
+impl Bounds {
+    fn include(bounds: &mut Option<Bounds>, x: f64, y: f64) {
+        let b = bounds.get_or_insert(Bounds {
+            left: x,
+            top: y,
+            right: x,
+            bottom: y,
+        });
+        b.left = b.left.min(x);
+        b.top = b.top.min(y);
+        b.right = b.right.max(x);
+        b.bottom = b.bottom.max(y);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Svg {
+    body: String,
+    bounds: Option<Bounds>,
+}
+
+impl Svg {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, style: &Style) {
+        writeln!(
+            self.body,
+            r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+            x,
+            y,
+            width,
+            height,
+            style.attributes()
+        )
+        .unwrap();
+        Bounds::include(&mut self.bounds, x, y);
+        Bounds::include(&mut self.bounds, x + width, y + height);
+    }
+
+    pub fn path(&mut self, path: &Path, style: &Style) {
+        writeln!(
+            self.body,
+            r#"<path d="{}"{}/>"#,
+            path.data,
+            style.attributes()
+        )
+        .unwrap();
+        for &(x, y) in &path.points {
+            Bounds::include(&mut self.bounds, x, y);
+        }
+    }
+
+    /// Writes `text` with its baseline starting at `(x, y)`, `size` units
+    /// tall.
+    pub fn text(&mut self, x: f64, y: f64, size: f64, text: &str, colour: &str) {
+        let escaped = text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        writeln!(
+            self.body,
+            r#"<text x="{}" y="{}" font-size="{}" fill="{}">{}</text>"#,
+            x, y, size, colour, escaped
+        )
+        .unwrap();
+        // The text's extent depends on the font, so estimate it from a
+        // typical character's width.
+        Bounds::include(&mut self.bounds, x, y - size);
+        Bounds::include(
+            &mut self.bounds,
+            x + 0.6 * size * text.chars().count() as f64,
+            y,
+        );
+    }
+
+    /// Finishes the document on a white background, with its view box fitted
+    /// around everything drawn plus `margin` on each side, and `scale` pixels
+    /// to each unit.
+    pub fn finish(self, margin: f64, scale: f64) -> String {
+        let bounds = self.bounds.unwrap_or(Bounds {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        });
+        let left = bounds.left - margin;
+        let top = bounds.top - margin;
+        let view_width = bounds.right - bounds.left + 2.0 * margin;
+        let view_height = bounds.bottom - bounds.top + 2.0 * margin;
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            (view_width * scale).round(),
+            (view_height * scale).round(),
+            left,
+            top,
+            view_width,
+            view_height
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="white"/>"#,
+            left, top, view_width, view_height
+        )
+        .unwrap();
+        svg.push_str(&self.body);
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
@@ -0,0 +1,87 @@
+//! The manifest of each day's latest answers on its real input, which the
+//! runner keeps up to date in `results.json`. It records enough to compare
+//! timings over time, and to notice when an input file has changed.
+//!
+//! The answers known to be correct are kept separately, in
+//! `res/answers.json`.
+
+use crate::error::{AocError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Results {
+    pub days: BTreeMap<u32, DayResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct DayResult {
+    pub part_a: Option<String>,
+    pub part_b: Option<String>,
+    /// The whole process's wall time, in seconds.
+    pub seconds: f64,
+    pub input_hash: String,
+    /// The git revision the day was built from, if known.
+    pub revision: Option<String>,
+}
+
+/// A day's answers, as they're printed.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Answers {
+    pub part_a: Option<String>,
+    pub part_b: Option<String>,
+}
+
+pub const GOLDEN_PATH: &str = "res/answers.json";
+
+/// Loads the known correct answers for each day.
+pub fn load_golden(path: impl AsRef<Path>) -> Result<BTreeMap<u32, Answers>> {
+    let json = std::fs::read_to_string(path)?;
+    serde_json::from_str(&json).map_err(|e| AocError::InvalidInput {
+        reason: format!("the golden answers are corrupt: {}", e),
+    })
+}
+
+impl Results {
+    pub const PATH: &'static str = "results.json";
+
+    /// Loads the manifest at `path`, or an empty one if there isn't one yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&json).map_err(|e| AocError::InvalidInput {
+            reason: format!("the results manifest is corrupt: {}", e),
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self).expect("results are serializable");
+        json.push('\n');
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// The SHA-256 digest of an input file's contents, in hex.
+pub fn hash_input(path: impl AsRef<Path>) -> Result<String> {
+    let digest = Sha256::digest(std::fs::read(path)?);
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// The current git revision, marked if the working tree has changes.
+pub fn git_revision() -> Option<String> {
+    let output = Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
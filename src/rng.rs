@@ -0,0 +1,34 @@
+//! A small pseudo-random number generator, for generating benchmark and test
+//! inputs. A xorshift generator is plenty random enough for this, and with a
+//! fixed seed every run generates the same inputs.
+
+/// A xorshift generator.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Creates a generator from a non-zero seed.
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "a xorshift generator needs a non-zero seed");
+        Rng(seed)
+    }
+
+    /// The next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..n`, or 0 if `n` is 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(0x2545_f491_4f6c_dd1d)
+    }
+}
@@ -0,0 +1,163 @@
+//! Timing the steps of a day, for `aoc run --timings`. The runner sets
+//! `AOC_TIMINGS` in the days' environment, and they then report how long they
+//! spent parsing and solving each part on extra lines of output, which the
+//! runner picks out. If the runner also sets `AOC_PERF`, each step's hardware
+//! performance counts are reported too.
+
+use crate::perf::{Counters, Counts};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+pub const ENV_VAR: &str = "AOC_TIMINGS";
+
+pub const PERF_ENV_VAR: &str = "AOC_PERF";
+
+const PREFIX: &str = "Timing: ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Parse,
+    PartA,
+    PartB,
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::PartA => "part-a",
+            Stage::PartB => "part-b",
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Stage {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        [Stage::Parse, Stage::PartA, Stage::PartB]
+            .into_iter()
+            .find(|stage| stage.name() == s)
+            .ok_or(())
+    }
+}
+
+/// How long a stage took, and with `AOC_PERF` its counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    pub stage: Stage,
+    pub elapsed: Duration,
+    pub counts: Option<Counts>,
+}
+
+/// The counters shared by every stage, opened on first use so that they
+/// follow any threads started afterwards. `None` if they weren't asked for or
+/// can't be opened, in which case stages are only timed.
+fn counters() -> Option<&'static Counters> {
+    static COUNTERS: OnceLock<Option<Counters>> = OnceLock::new();
+    COUNTERS
+        .get_or_init(|| {
+            std::env::var_os(PERF_ENV_VAR)
+                .is_some()
+                .then(Counters::open)
+                .and_then(Result::ok)
+        })
+        .as_ref()
+}
+
+/// Runs `f`, reporting how long it took as `stage` if the runner asked for
+/// timings.
+pub fn time<T>(stage: Stage, f: impl FnOnce() -> T) -> T {
+    if std::env::var_os(ENV_VAR).is_none() {
+        return f();
+    }
+
+    let start = Instant::now();
+    let (result, counts) = match counters() {
+        Some(counters) => {
+            let (result, counts) = counters.measure(f);
+            (result, counts.ok())
+        }
+        None => (f(), None),
+    };
+    let elapsed = start.elapsed();
+    print!("{}{} {}", PREFIX, stage, elapsed.as_nanos());
+    if let Some(counts) = counts {
+        print!(
+            " {} {} {}",
+            counts.instructions, counts.cache_misses, counts.branch_misses
+        );
+    }
+    println!();
+    result
+}
+
+/// Reads a line reported by `time`, or `None` if it's any other output.
+pub fn parse_line(line: &str) -> Option<Measurement> {
+    let mut fields = line.strip_prefix(PREFIX)?.split(' ');
+    let stage = fields.next()?.parse().ok()?;
+    let elapsed = Duration::from_nanos(fields.next()?.parse().ok()?);
+    let counts = fields
+        .map(str::parse)
+        .collect::<Result<Vec<u64>, _>>()
+        .ok()?;
+    let counts = match counts[..] {
+        [] => None,
+        [instructions, cache_misses, branch_misses] => Some(Counts {
+            instructions,
+            cache_misses,
+            branch_misses,
+        }),
+        _ => return None,
+    };
+    Some(Measurement {
+        stage,
+        elapsed,
+        counts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_read_with_and_without_counts() {
+        assert_eq!(
+            parse_line("Timing: part-a 1500"),
+            Some(Measurement {
+                stage: Stage::PartA,
+                elapsed: Duration::from_nanos(1500),
+                counts: None,
+            })
+        );
+        assert_eq!(
+            parse_line("Timing: part-b 20 300 4 5"),
+            Some(Measurement {
+                stage: Stage::PartB,
+                elapsed: Duration::from_nanos(20),
+                counts: Some(Counts {
+                    instructions: 300,
+                    cache_misses: 4,
+                    branch_misses: 5,
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn other_lines_are_ignored() {
+        assert_eq!(parse_line("Day 6, part A: 1848"), None);
+        assert_eq!(parse_line("Timing: part-c 20"), None);
+        assert_eq!(parse_line("Timing: parse 20 300"), None);
+        assert_eq!(parse_line("Timing: parse x"), None);
+    }
+}
@@ -0,0 +1,14 @@
+//! Reporting problems found in a day's input by `--validate`, which checks the
+//! assumptions each solution makes without solving.
+
+/// Prints each warning, followed by a summary.
+pub fn report_warnings(day: u32, warnings: &[String]) {
+    for warning in warnings {
+        println!("Warning: {}", warning);
+    }
+    match warnings.len() {
+        0 => println!("Day {}: input looks valid", day),
+        1 => println!("Day {}: 1 warning", day),
+        n => println!("Day {}: {} warnings", day, n),
+    }
+}
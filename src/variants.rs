@@ -0,0 +1,185 @@
+//! The alternative implementations which some days can be run with, selected
+//! by their arguments, so that they can be compared against each other.
+
+use crate::error::{AocError, Result};
+use std::fmt::Debug;
+
+pub struct Variant {
+    pub day: u32,
+    pub name: &'static str,
+    pub args: &'static [&'static str],
+    /// Whether the variant is only built with the `parallel` feature.
+    pub parallel: bool,
+    /// Whether the variant is only built with the `gpu` feature.
+    pub gpu: bool,
+}
+
+const fn variant(day: u32, name: &'static str, args: &'static [&'static str]) -> Variant {
+    Variant {
+        day,
+        name,
+        args,
+        parallel: false,
+        gpu: false,
+    }
+}
+
+pub const VARIANTS: &[Variant] = &[
+    variant(8, "scan", &["--strategy", "scan"]),
+    variant(8, "linear", &["--strategy", "linear"]),
+    variant(8, "simd", &["--strategy", "simd"]),
+    Variant {
+        day: 8,
+        name: "parallel",
+        args: &["--strategy", "parallel"],
+        parallel: true,
+        gpu: false,
+    },
+    variant(9, "steps", &["--strategy", "steps"]),
+    variant(9, "segments", &["--strategy", "segments"]),
+    variant(
+        9,
+        "steps-bitset",
+        &["--strategy", "steps", "--tracking", "bitset"],
+    ),
+    variant(
+        9,
+        "segments-bitset",
+        &["--strategy", "segments", "--tracking", "bitset"],
+    ),
+    variant(11, "sequential", &["--strategy", "sequential"]),
+    variant(11, "residues", &["--strategy", "residues"]),
+    variant(12, "a-star", &["--strategy", "a-star"]),
+    variant(12, "reverse", &["--strategy", "reverse"]),
+    variant(13, "sort", &["--strategy", "sort"]),
+    variant(13, "count", &["--strategy", "count"]),
+    variant(13, "json", &["--parser", "json"]),
+    variant(14, "simulate", &["--strategy", "simulate"]),
+    variant(14, "flood-fill", &["--strategy", "flood-fill"]),
+    variant(15, "rows", &["--strategy", "rows"]),
+    variant(15, "perimeter", &["--strategy", "perimeter"]),
+    variant(15, "perimeter-simd", &["--strategy", "perimeter-simd"]),
+    variant(15, "lines", &["--strategy", "lines"]),
+    Variant {
+        day: 15,
+        name: "gpu",
+        args: &["--strategy", "gpu"],
+        parallel: false,
+        gpu: true,
+    },
+    variant(16, "search", &["--strategy", "search"]),
+    variant(16, "subsets", &["--strategy", "subsets"]),
+];
+
+/// The variants of `day` available in this build.
+pub fn for_day(day: u32) -> impl Iterator<Item = &'static Variant> {
+    VARIANTS
+        .iter()
+        .filter(move |variant| variant.day == day)
+        .filter(|variant| !variant.parallel || cfg!(feature = "parallel"))
+        .filter(|variant| !variant.gpu || cfg!(feature = "gpu"))
+}
+
+/// A day's choice of implementation, as selected with `--strategy`.
+pub trait Strategy: Copy + 'static {
+    /// Every strategy available in this build.
+    const ALL: &'static [Self];
+
+    /// The strategy's name on the command line.
+    fn name(self) -> &'static str;
+}
+
+/// Finds the strategy called `name`.
+pub fn parse_strategy<S: Strategy>(name: &str) -> Result<S> {
+    S::ALL
+        .iter()
+        .copied()
+        .find(|strategy| strategy.name() == name)
+        .ok_or_else(|| AocError::UnknownStrategy {
+            name: name.to_string(),
+            expected: S::ALL.iter().map(|strategy| strategy.name()).collect(),
+        })
+}
+
+/// Checks that every strategy gives the same answer as `strategy`, whose
+/// answer is `expected`, by running `solve` with each of the others.
+pub fn cross_check<S, T, E>(
+    day: u32,
+    strategy: S,
+    expected: &T,
+    mut solve: impl FnMut(S) -> std::result::Result<T, E>,
+) -> std::result::Result<(), E>
+where
+    S: Strategy,
+    T: PartialEq + Debug,
+    E: From<AocError>,
+{
+    for &other in S::ALL {
+        if other.name() == strategy.name() {
+            continue;
+        }
+        let got = solve(other)?;
+        if got != *expected {
+            return Err(AocError::Disagreement {
+                strategy: strategy.name(),
+                other: other.name(),
+                got: format!("{:?}", got),
+                expected: format!("{:?}", expected),
+            }
+            .into());
+        }
+    }
+    println!("Day {}: all strategies agree", day);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Colour {
+        Red,
+        Green,
+        Blue,
+    }
+
+    impl Strategy for Colour {
+        const ALL: &'static [Self] = &[Colour::Red, Colour::Green, Colour::Blue];
+
+        fn name(self) -> &'static str {
+            match self {
+                Colour::Red => "red",
+                Colour::Green => "green",
+                Colour::Blue => "blue",
+            }
+        }
+    }
+
+    #[test]
+    fn parse_strategy_finds_names() {
+        assert_eq!(parse_strategy::<Colour>("green").unwrap(), Colour::Green);
+        let e = parse_strategy::<Colour>("purple").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Unknown strategy \"purple\" (expected red, green or blue)"
+        );
+    }
+
+    #[test]
+    fn cross_check_reports_disagreements() {
+        let solve = |colour: Colour| Ok::<_, AocError>(colour.name().len());
+        assert!(cross_check(1, Colour::Red, &3, |_| Ok::<_, AocError>(3)).is_ok());
+        let e = cross_check(1, Colour::Red, &3, solve).unwrap_err();
+        assert_eq!(e.to_string(), "Strategy green disagrees with red: 5 != 3");
+    }
+
+    #[test]
+    fn variants_for_day_have_the_day() {
+        assert!(for_day(8).all(|variant| variant.day == 8));
+        assert_eq!(
+            for_day(8).any(|variant| variant.name == "parallel"),
+            cfg!(feature = "parallel")
+        );
+    }
+}
@@ -0,0 +1,544 @@
+//! A small virtual machine for assembly-flavoured puzzles.
+//!
+//! Programs are lists of instructions operating on a handful of registers.
+//! Each instruction takes a configurable number of cycles to execute, and the
+//! CPU can be stepped a cycle at a time to observe its state during each one.
+
+use crate::error::{AocError, Result};
+use crate::parse::parse_error;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, digit1, line_ending, one_of, space1},
+    combinator::{all_consuming, map, map_res, opt, recognize},
+    multi::separated_list1,
+    sequence::{pair, preceded, separated_pair, tuple},
+    IResult,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Register {
+    W,
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    pub const ALL: [Register; 4] = [Register::W, Register::X, Register::Y, Register::Z];
+
+    pub fn name(&self) -> char {
+        match self {
+            Register::W => 'w',
+            Register::X => 'x',
+            Register::Y => 'y',
+            Register::Z => 'z',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Registers([isize; 4]);
+
+impl Index<Register> for Registers {
+    type Output = isize;
+
+    fn index(&self, register: Register) -> &isize {
+        &self.0[register as usize]
+    }
+}
+
+impl IndexMut<Register> for Registers {
+    fn index_mut(&mut self, register: Register) -> &mut isize {
+        &mut self.0[register as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Instr {
+    Noop,
+    /// Adds a value to a register, e.g. `addx 3`.
+    Add(Register, isize),
+    /// Multiplies a register by a value, e.g. `mulx -2`.
+    Mul(Register, isize),
+    /// Sets a register to a value, e.g. `setx 0`.
+    Set(Register, isize),
+    /// Jumps by an offset relative to the current instruction, e.g. `jmp -2`.
+    Jmp(isize),
+    /// Jumps by an offset if a register is non-zero, e.g. `jnz x -2`.
+    Jnz(Register, isize),
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instr::Noop => write!(f, "noop"),
+            Instr::Add(register, val) => write!(f, "add{} {}", register.name(), val),
+            Instr::Mul(register, val) => write!(f, "mul{} {}", register.name(), val),
+            Instr::Set(register, val) => write!(f, "set{} {}", register.name(), val),
+            Instr::Jmp(val) => write!(f, "jmp {}", val),
+            Instr::Jnz(register, val) => write!(f, "jnz {} {}", register.name(), val),
+        }
+    }
+}
+
+/// Builds a program in code, one instruction at a time.
+#[derive(Debug, Clone, Default)]
+pub struct Assembler {
+    program: Vec<Instr>,
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn noop(&mut self) -> &mut Self {
+        self.push(Instr::Noop)
+    }
+
+    pub fn add(&mut self, register: Register, val: isize) -> &mut Self {
+        self.push(Instr::Add(register, val))
+    }
+
+    pub fn mul(&mut self, register: Register, val: isize) -> &mut Self {
+        self.push(Instr::Mul(register, val))
+    }
+
+    pub fn set(&mut self, register: Register, val: isize) -> &mut Self {
+        self.push(Instr::Set(register, val))
+    }
+
+    pub fn jmp(&mut self, offset: isize) -> &mut Self {
+        self.push(Instr::Jmp(offset))
+    }
+
+    pub fn jnz(&mut self, register: Register, offset: isize) -> &mut Self {
+        self.push(Instr::Jnz(register, offset))
+    }
+
+    pub fn push(&mut self, instr: Instr) -> &mut Self {
+        self.program.push(instr);
+        self
+    }
+
+    /// The number of instructions so far, which is the address of the next.
+    pub fn len(&self) -> usize {
+        self.program.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.program.is_empty()
+    }
+
+    pub fn finish(&mut self) -> Vec<Instr> {
+        std::mem::take(&mut self.program)
+    }
+}
+
+/// The number of cycles each kind of instruction takes to execute. The
+/// defaults are those of 2022 day 10.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Costs {
+    pub noop: usize,
+    pub add: usize,
+    pub mul: usize,
+    pub set: usize,
+    pub jmp: usize,
+    pub jnz: usize,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs {
+            noop: 1,
+            add: 2,
+            mul: 1,
+            set: 1,
+            jmp: 1,
+            jnz: 1,
+        }
+    }
+}
+
+impl Costs {
+    /// The number of cycles `instr` takes. Costs below one are treated as one.
+    pub fn cycles(&self, instr: &Instr) -> usize {
+        let cycles = match instr {
+            Instr::Noop => self.noop,
+            Instr::Add(_, _) => self.add,
+            Instr::Mul(_, _) => self.mul,
+            Instr::Set(_, _) => self.set,
+            Instr::Jmp(_) => self.jmp,
+            Instr::Jnz(_, _) => self.jnz,
+        };
+        cycles.max(1)
+    }
+}
+
+/// A CPU running a program. The program is borrowed so that it can be run any
+/// number of times, and cloning the CPU is cheap.
+#[derive(Debug, Clone)]
+pub struct Cpu<'a> {
+    cycle_count: usize,
+    pub registers: Registers,
+    program: &'a [Instr],
+    pc: usize,
+    instr_remaining_cycles: usize,
+    costs: Costs,
+}
+
+/// Everything about a CPU except its program, so that it can be saved part way
+/// through a run and later restored to continue where it left off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Snapshot {
+    pub cycle_count: usize,
+    pub registers: Registers,
+    pub pc: usize,
+    pub instr_remaining_cycles: usize,
+    pub costs: Costs,
+}
+
+/// The state of the CPU during a cycle, before the instruction being executed
+/// has taken effect.
+#[derive(Debug, Clone, Copy)]
+pub struct CpuState {
+    pub cycle: usize,
+    pub pc: usize,
+    pub registers: Registers,
+    /// The instruction being executed.
+    pub instr: Instr,
+    /// The number of cycles the instruction needs after this one to finish.
+    pub remaining_cycles: usize,
+}
+
+impl<'a> Cpu<'a> {
+    /// Creates a CPU to run `program`, with all registers initially zero.
+    pub fn new(program: &'a [Instr], costs: Costs) -> Self {
+        Cpu {
+            cycle_count: 0,
+            registers: Registers::default(),
+            program,
+            pc: 0,
+            instr_remaining_cycles: 0,
+            costs,
+        }
+    }
+
+    /// Restores a CPU from a snapshot of one running `program`.
+    pub fn restore(program: &'a [Instr], snapshot: Snapshot) -> Self {
+        Cpu {
+            cycle_count: snapshot.cycle_count,
+            registers: snapshot.registers,
+            program,
+            pc: snapshot.pc,
+            instr_remaining_cycles: snapshot.instr_remaining_cycles,
+            costs: snapshot.costs,
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cycle_count: self.cycle_count,
+            registers: self.registers,
+            pc: self.pc,
+            instr_remaining_cycles: self.instr_remaining_cycles,
+            costs: self.costs,
+        }
+    }
+
+    /// Runs a single cycle, returning the state during it, or `None` if the
+    /// program has halted by running off either end. Fails if an instruction
+    /// overflows a register or jumps further than can be represented.
+    pub fn tick(&mut self) -> Result<Option<CpuState>> {
+        let instr = match self.program.get(self.pc) {
+            Some(&instr) => instr,
+            None => return Ok(None),
+        };
+
+        self.cycle_count += 1;
+
+        if self.instr_remaining_cycles == 0 {
+            self.instr_remaining_cycles = self.costs.cycles(&instr) - 1;
+        } else {
+            self.instr_remaining_cycles -= 1;
+        }
+
+        let state = CpuState {
+            cycle: self.cycle_count,
+            pc: self.pc,
+            registers: self.registers,
+            instr,
+            remaining_cycles: self.instr_remaining_cycles,
+        };
+
+        if self.instr_remaining_cycles == 0 {
+            self.execute(instr)?;
+        }
+
+        Ok(Some(state))
+    }
+
+    fn execute(&mut self, instr: Instr) -> Result<()> {
+        let overflow = |register: Register| AocError::InvalidInput {
+            reason: format!(
+                "Instruction {} ({}) overflows register {}",
+                self.pc + 1,
+                instr,
+                register.name()
+            ),
+        };
+
+        let mut offset = 1;
+        match instr {
+            Instr::Noop => {}
+            Instr::Add(register, val) => {
+                self.registers[register] = self.registers[register]
+                    .checked_add(val)
+                    .ok_or_else(|| overflow(register))?;
+            }
+            Instr::Mul(register, val) => {
+                self.registers[register] = self.registers[register]
+                    .checked_mul(val)
+                    .ok_or_else(|| overflow(register))?;
+            }
+            Instr::Set(register, val) => self.registers[register] = val,
+            Instr::Jmp(val) => offset = val,
+            Instr::Jnz(register, val) => {
+                if self.registers[register] != 0 {
+                    offset = val;
+                }
+            }
+        }
+
+        // The program counter always indexes the program, so fits in an
+        // `isize`. Jumping before the start of the program halts it, as does
+        // running off the end.
+        let target =
+            (self.pc as isize)
+                .checked_add(offset)
+                .ok_or_else(|| AocError::InvalidInput {
+                    reason: format!("Instruction {} ({}) jumps out of range", self.pc + 1, instr),
+                })?;
+        self.pc = usize::try_from(target).unwrap_or(usize::MAX);
+        Ok(())
+    }
+
+    /// Iterates over the states of each cycle, stopping after the first
+    /// error.
+    pub fn iter(self) -> CpuIter<'a> {
+        CpuIter { cpu: self }
+    }
+}
+
+pub struct CpuIter<'a> {
+    cpu: Cpu<'a>,
+}
+
+impl<'a> Iterator for CpuIter<'a> {
+    type Item = Result<CpuState>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.cpu.tick();
+        if state.is_err() {
+            self.cpu.pc = usize::MAX;
+        }
+        state.transpose()
+    }
+}
+
+fn parse_register(input: &str) -> IResult<&str, Register> {
+    map(one_of("wxyz"), |c| match c {
+        'w' => Register::W,
+        'x' => Register::X,
+        'y' => Register::Y,
+        'z' => Register::Z,
+        _ => unreachable!(),
+    })(input)
+}
+
+fn parse_value(input: &str) -> IResult<&str, isize> {
+    map_res(recognize(pair(opt(char('-')), digit1)), |s: &str| {
+        s.parse::<isize>()
+    })(input)
+}
+
+/// Parses an instruction whose mnemonic is suffixed with its register, such as
+/// `addx 3`.
+fn parse_register_instr<'a>(
+    mnemonic: &'static str,
+    instr: fn(Register, isize) -> Instr,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Instr> {
+    map(
+        separated_pair(preceded(tag(mnemonic), parse_register), space1, parse_value),
+        move |(register, val)| instr(register, val),
+    )
+}
+
+fn parse_instr(input: &str) -> IResult<&str, Instr> {
+    alt((
+        map(tag("noop"), |_| Instr::Noop),
+        parse_register_instr("add", Instr::Add),
+        parse_register_instr("mul", Instr::Mul),
+        parse_register_instr("set", Instr::Set),
+        map(
+            separated_pair(tag("jmp"), space1, parse_value),
+            |(_, val)| Instr::Jmp(val),
+        ),
+        map(
+            tuple((tag("jnz"), space1, parse_register, space1, parse_value)),
+            |(_, _, register, _, val)| Instr::Jnz(register, val),
+        ),
+    ))(input)
+}
+
+/// Parses a program, one instruction per line.
+pub fn parse_program(input: &str) -> Result<Vec<Instr>> {
+    let input = input.trim_end();
+    all_consuming(separated_list1(line_ending, parse_instr))(input)
+        .map(|(_, program)| program)
+        .map_err(|e| parse_error(input, e))
+}
+
+/// Converts a program back to text which `parse_program` accepts, one
+/// instruction per line.
+pub fn disassemble(program: &[Instr]) -> String {
+    program.iter().map(|instr| format!("{}\n", instr)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn assembled_programs_round_trip() {
+        let program = Assembler::new()
+            .noop()
+            .add(Register::X, 3)
+            .add(Register::W, -5)
+            .mul(Register::Y, -2)
+            .set(Register::Z, 0)
+            .jmp(-4)
+            .jnz(Register::X, 2)
+            .finish();
+        let text = disassemble(&program);
+        assert_eq!(
+            text,
+            "noop\naddx 3\naddw -5\nmuly -2\nsetz 0\njmp -4\njnz x 2\n"
+        );
+        assert_eq!(parse_program(&text).unwrap(), program);
+    }
+
+    #[test]
+    fn extreme_values_round_trip() {
+        let program = Assembler::new()
+            .set(Register::W, isize::MIN)
+            .add(Register::X, isize::MAX)
+            .jmp(isize::MIN)
+            .finish();
+        assert_eq!(parse_program(&disassemble(&program)).unwrap(), program);
+    }
+
+    /// Runs `program` to completion, returning the final registers.
+    fn run(program: &[Instr]) -> Result<Registers> {
+        let mut cpu = Cpu::new(program, Costs::default());
+        while cpu.tick()?.is_some() {}
+        Ok(cpu.registers)
+    }
+
+    #[test]
+    fn runs_until_the_program_halts() {
+        let program = Assembler::new()
+            .set(Register::X, 3)
+            .add(Register::Y, 2)
+            .add(Register::X, -1)
+            .jnz(Register::X, -2)
+            .mul(Register::Y, -3)
+            .finish();
+        let registers = run(&program).unwrap();
+        assert_eq!(registers[Register::X], 0);
+        assert_eq!(registers[Register::Y], -18);
+    }
+
+    #[test]
+    fn jumping_before_the_start_halts() {
+        let program = Assembler::new().jmp(-1).add(Register::X, 1).finish();
+        assert_eq!(run(&program).unwrap()[Register::X], 0);
+        let program = Assembler::new().noop().jmp(isize::MIN).finish();
+        assert!(run(&program).is_ok());
+    }
+
+    #[test]
+    fn overflowing_registers_is_an_error() {
+        let program = Assembler::new()
+            .set(Register::X, isize::MAX)
+            .add(Register::X, 1)
+            .finish();
+        assert!(run(&program).is_err());
+        let program = Assembler::new()
+            .set(Register::X, isize::MIN)
+            .mul(Register::X, -1)
+            .finish();
+        assert!(run(&program).is_err());
+    }
+
+    #[test]
+    fn jumping_out_of_range_is_an_error() {
+        let program = Assembler::new().noop().jmp(isize::MAX).finish();
+        assert!(run(&program).is_err());
+    }
+
+    #[test]
+    fn iteration_stops_after_an_error() {
+        let program = Assembler::new()
+            .set(Register::X, isize::MAX)
+            .mul(Register::X, 2)
+            .noop()
+            .finish();
+        let states = Cpu::new(&program, Costs::default())
+            .iter()
+            .collect::<Vec<_>>();
+        assert_eq!(states.len(), 2);
+        assert!(states[0].is_ok() && states[1].is_err());
+    }
+
+    fn register() -> impl Strategy<Value = Register> {
+        prop::sample::select(&Register::ALL[..])
+    }
+
+    fn instr() -> impl Strategy<Value = Instr> {
+        prop_oneof![
+            Just(Instr::Noop),
+            (register(), any::<isize>()).prop_map(|(r, val)| Instr::Add(r, val)),
+            (register(), any::<isize>()).prop_map(|(r, val)| Instr::Mul(r, val)),
+            (register(), any::<isize>()).prop_map(|(r, val)| Instr::Set(r, val)),
+            any::<isize>().prop_map(Instr::Jmp),
+            (register(), any::<isize>()).prop_map(|(r, val)| Instr::Jnz(r, val)),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn any_program_round_trips(program in prop::collection::vec(instr(), 1..32)) {
+            let mut assembler = Assembler::new();
+            for &instr in &program {
+                assembler.push(instr);
+            }
+            let assembled = assembler.finish();
+            prop_assert_eq!(&assembled, &program);
+            prop_assert_eq!(parse_program(&disassemble(&assembled)).unwrap(), program);
+        }
+    }
+}
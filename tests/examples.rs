@@ -0,0 +1,23 @@
+//! Runs every day on its puzzle examples through `aoc run --example`, which
+//! fails if any of them give the wrong answer.
+
+#![cfg(feature = "serde")]
+
+use std::process::Command;
+
+#[test]
+fn every_example_gives_the_expected_answers() {
+    let output = Command::new(env!("CARGO_BIN_EXE_aoc"))
+        .args(["run", "--example"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+
+    let answers = stdout.lines().filter(|line| line.ends_with(" ok")).count();
+    let expected = aoc2022::examples::EXAMPLES
+        .iter()
+        .map(|example| example.part_a.iter().chain(&example.part_b).count())
+        .sum::<usize>();
+    assert_eq!(answers, expected, "{}", stdout);
+}